@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+#ifndef PKGSRC_H
+#define PKGSRC_H
+
+/* Generated by cbindgen from capi/src/lib.rs.  Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ * Test whether `lhs` is less than `rhs`.
+ */
+#define DEWEY_LT 0
+
+/*
+ * Test whether `lhs` is less than or equal to `rhs`.
+ */
+#define DEWEY_LE 1
+
+/*
+ * Test whether `lhs` is equal to `rhs`.
+ */
+#define DEWEY_EQ 2
+
+/*
+ * Test whether `lhs` is greater than or equal to `rhs`.
+ */
+#define DEWEY_GE 3
+
+/*
+ * Test whether `lhs` is greater than `rhs`.
+ */
+#define DEWEY_GT 4
+
+/*
+ * Test whether `lhs` is not equal to `rhs`.
+ */
+#define DEWEY_NE 5
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ * Return 1 if `pkg` matches `pattern`, otherwise 0.
+ *
+ * # Safety
+ *
+ * `pattern` and `pkg` must each be NULL or a valid NUL-terminated string.
+ */
+int pkg_match(const char *pattern, const char *pkg);
+
+/*
+ * Return which of two packages is the better match for `pattern`: 0 if
+ * neither matches, 1 if `first_pkg` is better, and 2 if `second_pkg` is
+ * better.  Either package may be NULL.  When both match with equal
+ * versions, the lexicographically smaller name wins.  As in libinstall, a
+ * matching `first_pkg` with no version is only chosen when `second_pkg` is
+ * NULL.
+ *
+ * # Safety
+ *
+ * `pattern`, `first_pkg` and `second_pkg` must each be NULL or a valid
+ * NUL-terminated string.
+ */
+int pkg_order(const char *pattern, const char *first_pkg, const char *second_pkg);
+
+/*
+ * Compare two version strings using one of the `DEWEY_*` operators,
+ * returning 1 if the comparison holds, otherwise 0.
+ *
+ * # Safety
+ *
+ * `lhs` and `rhs` must each be NULL or a valid NUL-terminated string.
+ */
+int dewey_cmp(const char *lhs, int op, const char *rhs);
+
+/*
+ * Return 1 if `pkg` matches the dewey pattern `pattern`, 0 if it does not,
+ * or -1 if `pattern` is not a valid dewey pattern.
+ *
+ * # Safety
+ *
+ * `pattern` and `pkg` must each be NULL or a valid NUL-terminated string.
+ */
+int dewey_match(const char *pattern, const char *pkg);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PKGSRC_H */
@@ -0,0 +1,362 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * C ABI for the [`pkgsrc`] pattern matching and version comparison
+ * routines, compatible with `pkg_install`'s `libinstall`.
+ *
+ * The following functions are exported with the same signatures and
+ * results as their `libinstall` equivalents, so that C tools such as
+ * `pkgin` can link against this library instead:
+ *
+ * ```c
+ * int pkg_match(const char *pattern, const char *pkg);
+ * int pkg_order(const char *pattern, const char *first_pkg,
+ *               const char *second_pkg);
+ * int dewey_cmp(const char *lhs, int op, const char *rhs);
+ * int dewey_match(const char *pattern, const char *pkg);
+ * ```
+ *
+ * The `DEWEY_*` operator constants match `libinstall`'s `dewey.h`.  A
+ * header is provided in `include/pkgsrc.h`.
+ *
+ * Where `libinstall` would abort the process on an invalid pattern, these
+ * functions instead return 0 (no match).  NULL pointers and strings that
+ * are not valid UTF-8 are also treated as not matching.
+ */
+
+use pkgsrc::{Dewey, Pattern, Version};
+use std::cmp::Ordering;
+use std::ffi::{CStr, c_char, c_int};
+
+/** Test whether `lhs` is less than `rhs`. */
+pub const DEWEY_LT: c_int = 0;
+/** Test whether `lhs` is less than or equal to `rhs`. */
+pub const DEWEY_LE: c_int = 1;
+/** Test whether `lhs` is equal to `rhs`. */
+pub const DEWEY_EQ: c_int = 2;
+/** Test whether `lhs` is greater than or equal to `rhs`. */
+pub const DEWEY_GE: c_int = 3;
+/** Test whether `lhs` is greater than `rhs`. */
+pub const DEWEY_GT: c_int = 4;
+/** Test whether `lhs` is not equal to `rhs`. */
+pub const DEWEY_NE: c_int = 5;
+
+/*
+ * Convert a C string to a &str, returning None for NULL or non-UTF-8 input.
+ *
+ * Safety: ptr must be NULL or point to a valid NUL-terminated string that
+ * outlives the returned reference.
+ */
+unsafe fn to_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/*
+ * Split a pattern at its first top-level brace group into the expanded
+ * alternatives, as libinstall's alternate_match() does.  Returns None if
+ * there are no braces, and Some(Err) if they are unbalanced.
+ */
+fn expand_alternates(pattern: &str) -> Option<Result<Vec<String>, ()>> {
+    let open = pattern.find('{')?;
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut close = None;
+    for (i, c) in pattern[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => commas.push(open + i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return Some(Err(()));
+    };
+    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+    let mut start = open + 1;
+    let mut expanded = Vec::with_capacity(commas.len() + 1);
+    for end in commas.into_iter().chain(std::iter::once(close)) {
+        expanded.push(format!("{prefix}{}{suffix}", &pattern[start..end]));
+        start = end + 1;
+    }
+    Some(Ok(expanded))
+}
+
+/*
+ * Implement libinstall's pkg_match() rules.  Unlike Pattern::matches, a
+ * glob or simple pattern that does not match is retried with "-[0-9]*"
+ * appended, so that "foo" matches "foo-1.0".
+ */
+fn match_pattern(pattern: &str, pkg: &str) -> bool {
+    if let Some(expanded) = expand_alternates(pattern) {
+        return expanded
+            .is_ok_and(|alts| alts.iter().any(|alt| match_pattern(alt, pkg)));
+    }
+    if pattern.contains(['<', '>']) {
+        return Dewey::new(pattern).is_ok_and(|d| d.matches(pkg));
+    }
+    if pattern.contains(['*', '?', '[', ']'])
+        && Pattern::new(pattern).is_ok_and(|p| p.matches(pkg))
+    {
+        return true;
+    }
+    if pattern == pkg {
+        return true;
+    }
+    Pattern::new(&format!("{pattern}-[0-9]*")).is_ok_and(|p| p.matches(pkg))
+}
+
+fn version_cmp(lhs: &str, op: c_int, rhs: &str) -> bool {
+    let (Ok(lhs), Ok(rhs)) = (Version::new(lhs), Version::new(rhs)) else {
+        return false;
+    };
+    let ord = lhs.cmp(&rhs);
+    match op {
+        DEWEY_LT => ord == Ordering::Less,
+        DEWEY_LE => ord != Ordering::Greater,
+        DEWEY_EQ => ord == Ordering::Equal,
+        DEWEY_GE => ord != Ordering::Less,
+        DEWEY_GT => ord == Ordering::Greater,
+        DEWEY_NE => ord != Ordering::Equal,
+        _ => false,
+    }
+}
+
+/**
+ * Return 1 if `pkg` matches `pattern`, otherwise 0.
+ *
+ * # Safety
+ *
+ * `pattern` and `pkg` must each be NULL or a valid NUL-terminated string.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pkg_match(
+    pattern: *const c_char,
+    pkg: *const c_char,
+) -> c_int {
+    let (Some(pattern), Some(pkg)) =
+        (unsafe { to_str(pattern) }, unsafe { to_str(pkg) })
+    else {
+        return 0;
+    };
+    c_int::from(match_pattern(pattern, pkg))
+}
+
+/**
+ * Return which of two packages is the better match for `pattern`: 0 if
+ * neither matches, 1 if `first_pkg` is better, and 2 if `second_pkg` is
+ * better.  Either package may be NULL.  When both match with equal
+ * versions, the lexicographically smaller name wins.  As in libinstall, a
+ * matching `first_pkg` with no version is only chosen when `second_pkg` is
+ * NULL.
+ *
+ * # Safety
+ *
+ * `pattern`, `first_pkg` and `second_pkg` must each be NULL or a valid
+ * NUL-terminated string.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pkg_order(
+    pattern: *const c_char,
+    first_pkg: *const c_char,
+    second_pkg: *const c_char,
+) -> c_int {
+    let Some(pattern) = (unsafe { to_str(pattern) }) else {
+        return 0;
+    };
+    let first = unsafe { to_str(first_pkg) };
+    let second = unsafe { to_str(second_pkg) };
+    let matches =
+        |pkg: &str, n| if match_pattern(pattern, pkg) { n } else { 0 };
+
+    /*
+     * Follow libinstall exactly, including that a first package without a
+     * version defers to the second even if only the first matches.
+     */
+    let (first, second) = match (first, second) {
+        (None, None) => return 0,
+        (None, Some(second)) => return matches(second, 2),
+        (Some(first), None) => return matches(first, 1),
+        (Some(first), Some(second)) => (first, second),
+    };
+    let Some((_, v1)) = first
+        .rsplit_once('-')
+        .filter(|_| match_pattern(pattern, first))
+    else {
+        return matches(second, 2);
+    };
+    let Some((_, v2)) = second
+        .rsplit_once('-')
+        .filter(|_| match_pattern(pattern, second))
+    else {
+        return matches(first, 1);
+    };
+    if version_cmp(v1, DEWEY_GT, v2) {
+        1
+    } else if version_cmp(v1, DEWEY_LT, v2) {
+        2
+    } else if first < second {
+        1
+    } else {
+        2
+    }
+}
+
+/**
+ * Compare two version strings using one of the `DEWEY_*` operators,
+ * returning 1 if the comparison holds, otherwise 0.
+ *
+ * # Safety
+ *
+ * `lhs` and `rhs` must each be NULL or a valid NUL-terminated string.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dewey_cmp(
+    lhs: *const c_char,
+    op: c_int,
+    rhs: *const c_char,
+) -> c_int {
+    let (Some(lhs), Some(rhs)) =
+        (unsafe { to_str(lhs) }, unsafe { to_str(rhs) })
+    else {
+        return 0;
+    };
+    c_int::from(version_cmp(lhs, op, rhs))
+}
+
+/**
+ * Return 1 if `pkg` matches the dewey pattern `pattern`, 0 if it does not,
+ * or -1 if `pattern` is not a valid dewey pattern.
+ *
+ * # Safety
+ *
+ * `pattern` and `pkg` must each be NULL or a valid NUL-terminated string.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dewey_match(
+    pattern: *const c_char,
+    pkg: *const c_char,
+) -> c_int {
+    let (Some(pattern), Some(pkg)) =
+        (unsafe { to_str(pattern) }, unsafe { to_str(pkg) })
+    else {
+        return 0;
+    };
+    match Dewey::new(pattern) {
+        Ok(dewey) => c_int::from(dewey.matches(pkg)),
+        Err(_) => -1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ptr;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).expect("no NUL")
+    }
+
+    fn pm(pattern: &str, pkg: &str) -> c_int {
+        unsafe { pkg_match(c(pattern).as_ptr(), c(pkg).as_ptr()) }
+    }
+
+    fn order(
+        pattern: &str,
+        first: Option<&str>,
+        second: Option<&str>,
+    ) -> c_int {
+        let first = first.map(c);
+        let second = second.map(c);
+        unsafe {
+            pkg_order(
+                c(pattern).as_ptr(),
+                first.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+                second.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+            )
+        }
+    }
+
+    #[test]
+    fn test_pkg_match() {
+        assert_eq!(pm("mktool-[0-9]*", "mktool-1.4.2"), 1);
+        assert_eq!(pm("mktool>=1.5", "mktool-1.4.2"), 0);
+        assert_eq!(pm("{mktool,pkgin}>=1", "pkgin-25.1.0"), 1);
+        assert_eq!(pm("mktool-1.4.2", "mktool-1.4.2"), 1);
+        /* libinstall retries without a version. */
+        assert_eq!(pm("mktool", "mktool-1.4.2"), 1);
+        assert_eq!(pm("mk*", "mktool-1.4.2"), 1);
+        assert_eq!(pm("{mktool,pkgin}", "pkgin-25.1.0"), 1);
+        assert_eq!(pm("mktool", "mktool-git"), 0);
+        /* Invalid patterns and NULLs do not match. */
+        assert_eq!(pm("{mktool", "mktool-1.0"), 0);
+        assert_eq!(pm("mktool>1<2<3", "mktool-1.5"), 0);
+        assert_eq!(unsafe { pkg_match(ptr::null(), ptr::null()) }, 0);
+    }
+
+    #[test]
+    fn test_pkg_order() {
+        let p = "mktool-[0-9]*";
+        assert_eq!(order(p, None, None), 0);
+        assert_eq!(order(p, Some("mktool-1.0"), None), 1);
+        assert_eq!(order(p, None, Some("mktool-1.0")), 2);
+        assert_eq!(order(p, Some("pkgin-1.0"), Some("mktool-1.0")), 2);
+        assert_eq!(order(p, Some("mktool-1.0"), Some("mktool-1.1")), 2);
+        assert_eq!(order(p, Some("mktool-1.1"), Some("mktool-1.0")), 1);
+        assert_eq!(order(p, Some("mktool-1.0"), Some("mktool-1.0.0")), 1);
+        assert_eq!(order(p, Some("mktool-1.0.0"), Some("mktool-1.0")), 2);
+
+        /* Matching packages without a version, as in libinstall. */
+        let p = "mk*";
+        assert_eq!(order(p, Some("mktool"), None), 1);
+        assert_eq!(order(p, None, Some("mktool")), 2);
+        assert_eq!(order(p, Some("pkgin-1.0"), Some("mktool")), 2);
+        assert_eq!(order(p, Some("mktool-1.0"), Some("mktool")), 1);
+        assert_eq!(order(p, Some("mktool"), Some("mktool-1.0")), 2);
+        assert_eq!(order(p, Some("mktool"), Some("pkgin-1.0")), 0);
+    }
+
+    #[test]
+    fn test_dewey() {
+        let cmp = |l: &str, op, r: &str| unsafe {
+            dewey_cmp(c(l).as_ptr(), op, c(r).as_ptr())
+        };
+        assert_eq!(cmp("1.0", DEWEY_LT, "1.0nb1"), 1);
+        assert_eq!(cmp("1.0rc1", DEWEY_LT, "1.0"), 1);
+        assert_eq!(cmp("1.0", DEWEY_EQ, "1"), 1);
+        assert_eq!(cmp("1.0", DEWEY_NE, "1"), 0);
+        assert_eq!(cmp("2.0", DEWEY_GE, "1.9"), 1);
+        assert_eq!(cmp("2.0", 99, "1.9"), 0);
+
+        let dm = |p: &str, pkg: &str| unsafe {
+            dewey_match(c(p).as_ptr(), c(pkg).as_ptr())
+        };
+        assert_eq!(dm("foo>=1<2", "foo-1.5"), 1);
+        assert_eq!(dm("foo>=1<2", "foo-2.0"), 0);
+        assert_eq!(dm("foo", "foo-2.0"), -1);
+    }
+}
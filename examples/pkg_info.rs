@@ -58,65 +58,6 @@ fn output_default<P: FileRead>(pkg: &P) -> Result<()> {
     Ok(())
 }
 
-/// Variables from BUILD_INFO that are valid in pkg_summary.
-const SUMMARY_BUILD_VARS: &[&str] = &[
-    "BUILD_DATE",
-    "CATEGORIES",
-    "HOMEPAGE",
-    "LICENSE",
-    "MACHINE_ARCH",
-    "OPSYS",
-    "OS_VERSION",
-    "PKGPATH",
-    "PKGTOOLS_VERSION",
-    "PKG_OPTIONS",
-    "PREV_PKGPATH",
-    "PROVIDES",
-    "REQUIRES",
-    "SUPERSEDES",
-];
-
-fn output_summary<P: FileRead>(pkg: &P) -> Result<()> {
-    let contents = pkg.contents()?;
-    let comment = pkg.comment()?;
-    let desc = pkg.desc()?;
-
-    // PKGNAME, DEPENDS, CONFLICTS from +CONTENTS in file order
-    for line in contents.lines() {
-        if let Some(name) = line.strip_prefix("@name ") {
-            println!("PKGNAME={}", name);
-        } else if let Some(dep) = line.strip_prefix("@pkgdep ") {
-            println!("DEPENDS={}", dep);
-        } else if let Some(cfl) = line.strip_prefix("@pkgcfl ") {
-            println!("CONFLICTS={}", cfl);
-        }
-    }
-
-    println!("COMMENT={}", comment);
-
-    if let Some(size) = pkg.size_pkg()? {
-        println!("SIZE_PKG={}", size.trim());
-    }
-
-    // BUILD_INFO variables (filtered, in file order)
-    if let Some(build_info) = pkg.build_info()? {
-        for line in build_info.lines() {
-            if let Some(var) = line.split('=').next()
-                && SUMMARY_BUILD_VARS.contains(&var)
-            {
-                println!("{}", line);
-            }
-        }
-    }
-
-    for line in desc.lines() {
-        println!("DESCRIPTION={}", line);
-    }
-
-    println!();
-    Ok(())
-}
-
 /// Extract summary from a binary package.
 fn extract_summary(path: &Path, opts: &SummaryOptions) -> Result<String> {
     let pkg = BinaryPackage::open(path)?;
@@ -149,7 +90,7 @@ fn process_installed(cmd: &OptArgs) -> Result<()> {
         }
 
         if cmd.sumout {
-            output_summary(&pkg)?;
+            println!("{}\n", pkg.to_summary()?);
         } else {
             output_default(&pkg)?;
         }
@@ -32,6 +32,7 @@
  * | `Vec<T>` | `#[kv(multiline)]` | Multiple lines collected into Vec |
  * | `Option<Vec<T>>` | `#[kv(multiline)]` | Optional multiple lines |
  * | `HashMap<String, String>` | `#[kv(collect)]` | Collects unhandled keys |
+ * | `T: Default` | `#[kv(skip)]` | Not parsed, initialised with `Default::default()` |
  *
  * # Container Attributes
  *
@@ -44,7 +45,11 @@
  * - `#[kv(variable = "KEY")]` - Use custom key name instead of uppercased field name
  * - `#[kv(multiline)]` - Collect multiple lines with the same key into a `Vec`
  * - `#[kv(collect)]` - Collect all unhandled keys into this `HashMap<String, String>`
- * - `#[kv(lenient)]` - For an `Option<T>` field, treat a value that fails to parse as `None` rather than erroring. A struct with any `lenient` field also gains a generated `parse_with_warnings` method that appends the dropped values to a `Vec<KvWarning>`.
+ * - `#[kv(skip)]` - Do not parse this field, initialising it with `Default::default()` instead. Skipped fields are also skipped by the `serde` implementations.
+ * - `#[kv(lenient)]` - For an `Option<T>` field, treat a value that fails to parse as `None` rather than erroring. A struct with any `lenient` field also gains a generated `parse_with_warnings` method that appends the dropped values to a `Vec<KvWarning>`, a `LENIENT_VARIABLES` constant listing the lenient keys, and a `lenient_warning` method that checks a single value the same way, for callers that scan input without parsing it.
+ *
+ * Every derived struct also gains a `VARIABLES` constant listing the keys
+ * of its parsed fields.
  *
  * # Duplicate Key Behavior
  *
@@ -193,7 +198,7 @@ fn generate_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
         parsed_fields.iter().find(|f| f.kind == FieldKind::Collect);
     let regular_fields: Vec<_> = parsed_fields
         .iter()
-        .filter(|f| f.kind != FieldKind::Collect)
+        .filter(|f| f.kind != FieldKind::Collect && f.kind != FieldKind::Skip)
         .collect();
 
     /*
@@ -277,8 +282,47 @@ fn generate_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
         }
     };
 
+    let lenient_fields: Vec<_> =
+        regular_fields.iter().filter(|f| f.lenient).collect();
+    let lenient_keys: Vec<_> =
+        lenient_fields.iter().map(|f| &f.key_name).collect();
+    let lenient_types: Vec<_> =
+        lenient_fields.iter().map(|f| &f.inner_type).collect();
+
     let parse_methods = if has_lenient {
         quote! {
+            /**
+             * The variables of the `#[kv(lenient)]` fields.
+             */
+            pub const LENIENT_VARIABLES: &'static [&'static str] =
+                &[#(#lenient_keys),*];
+
+            /**
+             * Checks a single value of a `#[kv(lenient)]` variable,
+             * returning the `KvWarning` that
+             * [`parse_with_warnings`](Self::parse_with_warnings) would
+             * record for it, or `None` if the value parses or `variable`
+             * is not lenient.
+             */
+            #[must_use]
+            pub fn lenient_warning(
+                variable: &str,
+                value: &str,
+                span: #kv::Span,
+            ) -> Option<#kv::KvWarning> {
+                use #kv::FromKv;
+
+                let ok = match variable {
+                    #(#lenient_keys => <#lenient_types as FromKv>::from_kv(value, span).is_ok(),)*
+                    _ => true,
+                };
+                (!ok).then(|| #kv::KvWarning {
+                    variable: variable.to_string(),
+                    value: value.to_string(),
+                    span,
+                })
+            }
+
             /**
              * Parses from `KEY=VALUE` formatted input, discarding any
              * warnings produced by `#[kv(lenient)]` fields.
@@ -342,8 +386,16 @@ fn generate_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
         }
     };
 
+    let keys: Vec<_> = regular_fields.iter().map(|f| &f.key_name).collect();
+
     Ok(quote! {
         impl #name {
+            /**
+             * The variables of every parsed field, excluding `#[kv(skip)]`
+             * and `#[kv(collect)]` fields.
+             */
+            pub const VARIABLES: &'static [&'static str] = &[#(#keys),*];
+
             #parse_methods
         }
 
@@ -393,6 +445,7 @@ fn ensure_at_most_one(
 fn generate_field_declarations(fields: &[ParsedField]) -> Vec<TokenStream2> {
     fields
         .iter()
+        .filter(|f| f.kind != FieldKind::Skip)
         .map(|f| {
             let ident = &f.ident;
             let state_ty = f.state_type();
@@ -420,18 +473,18 @@ fn generate_match_arms(
             if f.lenient {
                 let inner = &f.inner_type;
                 match warnings_ident {
+                    /*
+                     * Failures are reported through the generated
+                     * lenient_warning, so that callers scanning input
+                     * without parsing it report exactly the same warnings.
+                     */
                     Some(warnings) => quote! {
                         #key_name => {
-                            match <#inner as FromKv>::from_kv(value, value_span) {
-                                Ok(parsed) => #ident = Some(parsed),
-                                Err(_) => {
-                                    #ident = None;
-                                    #warnings.push(#kv::KvWarning {
-                                        variable: key.to_string(),
-                                        value: value.to_string(),
-                                        span: value_span,
-                                    });
-                                }
+                            #ident = <#inner as FromKv>::from_kv(value, value_span).ok();
+                            if #ident.is_none() {
+                                #warnings.extend(
+                                    Self::lenient_warning(key, value, value_span),
+                                );
                             }
                         }
                     },
@@ -494,6 +547,16 @@ fn generate_unknown_handling(
  * whether to emit these, so the generated impls are not themselves cfg-gated.
  */
 fn generate_serde_impl(name: &Ident, fields: &[ParsedField]) -> TokenStream2 {
+    let skipped: Vec<_> = fields
+        .iter()
+        .filter(|f| f.kind == FieldKind::Skip)
+        .map(|f| &f.ident)
+        .collect();
+    let fields: Vec<&ParsedField> = fields
+        .iter()
+        .filter(|f| f.kind != FieldKind::Skip)
+        .collect();
+
     let field_defs: Vec<_> = fields
         .iter()
         .map(|f| {
@@ -517,6 +580,7 @@ fn generate_serde_impl(name: &Ident, fields: &[ParsedField]) -> TokenStream2 {
                         #[serde(flatten)]
                     }
                 }
+                FieldKind::Skip => unreachable!("skipped fields are filtered"),
             };
 
             quote! {
@@ -562,6 +626,7 @@ fn generate_serde_impl(name: &Ident, fields: &[ParsedField]) -> TokenStream2 {
                         #ident: &'a #ty
                     }
                 }
+                FieldKind::Skip => unreachable!("skipped fields are filtered"),
             }
         })
         .collect();
@@ -630,6 +695,7 @@ fn generate_serde_impl(name: &Ident, fields: &[ParsedField]) -> TokenStream2 {
                 let helper = Helper::deserialize(deserializer)?;
                 Ok(Self {
                     #(#from_fields,)*
+                    #(#skipped: Default::default(),)*
                 })
             }
         }
@@ -691,6 +757,8 @@ struct FieldAttrs {
     collect: bool,
     /** Whether an unparseable value becomes `None` instead of erroring. */
     lenient: bool,
+    /** Whether this field is not parsed at all. */
+    skip: bool,
 }
 
 impl FieldAttrs {
@@ -717,9 +785,12 @@ impl FieldAttrs {
                 } else if meta.path.is_ident("lenient") {
                     result.lenient = true;
                     Ok(())
+                } else if meta.path.is_ident("skip") {
+                    result.skip = true;
+                    Ok(())
                 } else {
                     Err(meta.error(
-                        "unknown field attribute; expected `variable`, `multiline`, `collect`, or `lenient`",
+                        "unknown field attribute; expected `variable`, `multiline`, `collect`, `lenient`, or `skip`",
                     ))
                 }
             })?;
@@ -746,6 +817,8 @@ enum FieldKind {
     OptionMultiLine,
     /** `HashMap<String, String>` with `collect` - collects unhandled keys. */
     Collect,
+    /** Any `T: Default` with `skip` - not parsed. */
+    Skip,
 }
 
 /** A parsed and analyzed struct field. */
@@ -784,6 +857,27 @@ impl ParsedField {
             ));
         }
 
+        if attrs.skip {
+            if attrs.collect
+                || attrs.lenient
+                || attrs.multiline
+                || attrs.variable.is_some()
+            {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "`skip` cannot be combined with other field attributes",
+                ));
+            }
+            return Ok(Self {
+                ident,
+                key_name: String::new(),
+                kind: FieldKind::Skip,
+                inner_type: field.ty.clone(),
+                original_type: field.ty.clone(),
+                lenient: false,
+            });
+        }
+
         /* Validate collect field type */
         if attrs.collect {
             validate_collect_type(&field.ty, field)?;
@@ -840,6 +934,9 @@ impl ParsedField {
             FieldKind::Collect => {
                 quote! { std::collections::HashMap<String, String> }
             }
+            FieldKind::Skip => {
+                unreachable!("state_type is not called for Skip fields")
+            }
         }
     }
 
@@ -874,7 +971,7 @@ impl ParsedField {
                     }
                 }
             }
-            FieldKind::Collect => {
+            FieldKind::Collect | FieldKind::Skip => {
                 unreachable!(
                     "merge_expr is not called for {:?} fields",
                     self.kind
@@ -900,6 +997,7 @@ impl ParsedField {
             | FieldKind::Collect => {
                 quote! { #ident }
             }
+            FieldKind::Skip => quote! { Default::default() },
         }
     }
 }
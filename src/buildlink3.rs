@@ -0,0 +1,373 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Parse `buildlink3.mk` files.
+ *
+ * A package that other packages build against installs a `buildlink3.mk`
+ * describing the dependency it adds:
+ *
+ * ```text
+ * BUILDLINK_TREE+=        libfoo
+ *
+ * .if !defined(LIBFOO_BUILDLINK3_MK)
+ * LIBFOO_BUILDLINK3_MK:=
+ *
+ * BUILDLINK_API_DEPENDS.libfoo+=  libfoo>=1.0
+ * BUILDLINK_ABI_DEPENDS.libfoo+=  libfoo>=1.0nb1
+ * BUILDLINK_PKGSRCDIR.libfoo?=    ../../devel/libfoo
+ *
+ * .include "../../devel/zlib/buildlink3.mk"
+ * .endif  # LIBFOO_BUILDLINK3_MK
+ *
+ * BUILDLINK_TREE+=        -libfoo
+ * ```
+ *
+ * The API dependency is the oldest version that provides the interfaces a
+ * dependent package compiles against, and the ABI dependency the oldest
+ * binary-compatible version.  Raising the ABI dependency after an
+ * incompatible change is what requires every package that includes the
+ * file to have its `PKGREVISION` bumped.
+ *
+ * A [`Buildlink3`] is evaluated with the [`makefile`](crate::makefile)
+ * module.  Nested includes are recorded but not followed, so each file is
+ * examined in isolation.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::buildlink3::Buildlink3;
+ *
+ * let bl3 = concat!(
+ *     env!("CARGO_MANIFEST_DIR"),
+ *     "/tests/data/pkgsrc/devel/libfoo/buildlink3.mk"
+ * );
+ * let bl3 = Buildlink3::read(bl3)?;
+ * assert_eq!(bl3.id(), "libfoo");
+ * assert_eq!(bl3.pkgsrcdir().as_str(), "devel/libfoo");
+ * assert!(bl3.api_depends()[0].pattern().matches("libfoo-1.0"));
+ * assert!(!bl3.abi_depends()[0].pattern().matches("libfoo-1.0"));
+ * # Ok::<(), pkgsrc::buildlink3::Buildlink3Error>(())
+ * ```
+ */
+
+use crate::makefile::{Evaluator, Makefile, MakefileError};
+use crate::{Depend, DependError, PkgPath, PkgPathError};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/**
+ * Errors that can occur when parsing a `buildlink3.mk` file.
+ */
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Buildlink3Error {
+    /**
+     * The file could not be read or evaluated.
+     */
+    #[error(transparent)]
+    Makefile(#[from] MakefileError),
+    /**
+     * No package was added to `BUILDLINK_TREE`.
+     */
+    #[error("No BUILDLINK_TREE entry")]
+    MissingTree,
+    /**
+     * The `BUILDLINK_PKGSRCDIR` is not a valid package path.
+     */
+    #[error("Invalid BUILDLINK_PKGSRCDIR.{id} \"{value}\"")]
+    PkgSrcDir {
+        /** The package id. */
+        id: String,
+        /** The invalid value. */
+        value: String,
+        /** The underlying error. */
+        #[source]
+        source: PkgPathError,
+    },
+    /**
+     * A `BUILDLINK_API_DEPENDS` or `BUILDLINK_ABI_DEPENDS` pattern is
+     * invalid.
+     */
+    #[error("Invalid {variable} pattern \"{value}\"")]
+    Depend {
+        /** The variable containing the pattern. */
+        variable: String,
+        /** The invalid pattern. */
+        value: String,
+        /** The underlying error. */
+        #[source]
+        source: DependError,
+    },
+}
+
+/**
+ * The dependency information from a single `buildlink3.mk` file.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Buildlink3 {
+    id: String,
+    pkgsrcdir: PkgPath,
+    api_depends: Vec<Depend>,
+    abi_depends: Vec<Depend>,
+    includes: Vec<PathBuf>,
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    }
+}
+
+impl Buildlink3 {
+    /**
+     * Read and parse a `buildlink3.mk` file.
+     *
+     * # Errors
+     *
+     * Returns [`Buildlink3Error`] if the file cannot be read or evaluated,
+     * or does not contain valid dependency information.
+     */
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Buildlink3Error> {
+        let path = path.as_ref();
+        let mf = Evaluator::new()
+            .with_follow_includes(false)
+            .evaluate(path)?;
+        Self::from_makefile(&mf, path)
+    }
+
+    /**
+     * Parse `buildlink3.mk` contents as if read from `path`, which is used
+     * to resolve includes and, if it is not set, `BUILDLINK_PKGSRCDIR`.
+     *
+     * # Errors
+     *
+     * Returns [`Buildlink3Error`] if the contents cannot be evaluated or do
+     * not contain valid dependency information.
+     */
+    pub fn parse(
+        contents: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, Buildlink3Error> {
+        let path = path.as_ref();
+        let mf = Evaluator::new()
+            .with_follow_includes(false)
+            .evaluate_str(contents, path)?;
+        Self::from_makefile(&mf, path)
+    }
+
+    /**
+     * Extract the dependency information from a `buildlink3.mk` that has
+     * already been evaluated, for example with default variables such as
+     * `OPSYS` that its conditionals depend on.  `path` is the file that
+     * was evaluated.
+     *
+     * # Errors
+     *
+     * Returns [`Buildlink3Error`] if the variables do not contain valid
+     * dependency information.
+     */
+    pub fn from_makefile(
+        mf: &Makefile,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, Buildlink3Error> {
+        let path = path.as_ref();
+        let tree = mf.get("BUILDLINK_TREE")?.unwrap_or_default();
+        let id = tree
+            .split_ascii_whitespace()
+            .find(|w| !w.starts_with('-'))
+            .ok_or(Buildlink3Error::MissingTree)?
+            .to_string();
+
+        /*
+         * Fall back to the directory containing the file.
+         */
+        let pkgsrcdir = match mf.get(&format!("BUILDLINK_PKGSRCDIR.{id}"))? {
+            Some(dir) => dir,
+            None => {
+                let dir = parent_dir(path);
+                let pkg = dir.file_name().unwrap_or_default();
+                let cat = parent_dir(dir).file_name().unwrap_or_default();
+                format!("{}/{}", cat.to_string_lossy(), pkg.to_string_lossy())
+            }
+        };
+        let pkgsrcdir = PkgPath::new(pkgsrcdir.trim()).map_err(|source| {
+            Buildlink3Error::PkgSrcDir {
+                id: id.clone(),
+                value: pkgsrcdir.clone(),
+                source,
+            }
+        })?;
+
+        let depends = |kind: &str| -> Result<Vec<Depend>, Buildlink3Error> {
+            let variable = format!("BUILDLINK_{kind}_DEPENDS.{id}");
+            let value = mf.get(&variable)?.unwrap_or_default();
+            value
+                .split_ascii_whitespace()
+                .map(|pattern| {
+                    Depend::new(&format!("{pattern}:{pkgsrcdir}")).map_err(
+                        |source| Buildlink3Error::Depend {
+                            variable: variable.clone(),
+                            value: pattern.to_string(),
+                            source,
+                        },
+                    )
+                })
+                .collect()
+        };
+        let api_depends = depends("API")?;
+        let abi_depends = depends("ABI")?;
+
+        let dir = parent_dir(path);
+        let includes = mf.includes()[1..]
+            .iter()
+            .chain(mf.skipped())
+            .map(|p| p.strip_prefix(dir).unwrap_or(p).to_path_buf())
+            .collect();
+
+        Ok(Self {
+            id,
+            pkgsrcdir,
+            api_depends,
+            abi_depends,
+            includes,
+        })
+    }
+
+    /**
+     * Return the package id, the name used in `BUILDLINK_TREE` and as the
+     * suffix of the other `BUILDLINK_*` variables.  This is usually, but
+     * not always, the `PKGBASE` of the package.
+     */
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /**
+     * Return the location of the package in pkgsrc.
+     */
+    #[must_use]
+    pub fn pkgsrcdir(&self) -> &PkgPath {
+        &self.pkgsrcdir
+    }
+
+    /**
+     * Return the `BUILDLINK_API_DEPENDS` patterns.
+     */
+    #[must_use]
+    pub fn api_depends(&self) -> &[Depend] {
+        &self.api_depends
+    }
+
+    /**
+     * Return the `BUILDLINK_ABI_DEPENDS` patterns.  These are often
+     * unset, in which case the API dependencies also apply to binary
+     * packages.
+     */
+    #[must_use]
+    pub fn abi_depends(&self) -> &[Depend] {
+        &self.abi_depends
+    }
+
+    /**
+     * Return every file included by the `buildlink3.mk`, relative to its
+     * directory, for example `../../devel/zlib/buildlink3.mk`.
+     */
+    #[must_use]
+    pub fn includes(&self) -> &[PathBuf] {
+        &self.includes
+    }
+
+    /**
+     * Return the packages whose `buildlink3.mk` is included, which are the
+     * packages this one passes on as dependencies.  Other includes, such as
+     * `../../mk/pthread.buildlink3.mk`, are ignored.
+     */
+    #[must_use]
+    pub fn dependencies(&self) -> Vec<PkgPath> {
+        self.includes
+            .iter()
+            .filter(|p| p.file_name().is_some_and(|f| f == "buildlink3.mk"))
+            .filter_map(|p| PkgPath::new(p.parent()?.to_str()?).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() -> Result<(), Buildlink3Error> {
+        let bl3 = "\
+BUILDLINK_TREE+=\tpy-foo
+
+.if !defined(PY_FOO_BUILDLINK3_MK)
+PY_FOO_BUILDLINK3_MK:=
+
+BUILDLINK_API_DEPENDS.py-foo+=\t${PYPKGPREFIX}-foo>=2.0
+BUILDLINK_PKGSRCDIR.py-foo?=\t../../devel/py-foo
+
+.include \"../../devel/libfoo/buildlink3.mk\"
+.include \"../../lang/python/extension.mk\"
+.include \"../../mk/pthread.buildlink3.mk\"
+.endif # PY_FOO_BUILDLINK3_MK
+
+BUILDLINK_TREE+=\t-py-foo
+";
+        let path = "/usr/pkgsrc/devel/py-foo/buildlink3.mk";
+        let mf = Evaluator::new()
+            .with_var("PYPKGPREFIX", "py313")
+            .with_follow_includes(false)
+            .evaluate_str(bl3, path)?;
+        let bl3 = Buildlink3::from_makefile(&mf, path)?;
+        assert_eq!(bl3.id(), "py-foo");
+        assert_eq!(bl3.api_depends().len(), 1);
+        assert_eq!(
+            bl3.api_depends()[0].to_string(),
+            "py313-foo>=2.0:../../devel/py-foo"
+        );
+        assert!(bl3.abi_depends().is_empty());
+        assert_eq!(bl3.includes().len(), 3);
+        assert_eq!(
+            bl3.includes()[0],
+            Path::new("../../devel/libfoo/buildlink3.mk")
+        );
+        let deps = bl3.dependencies();
+        let deps: Vec<_> = deps.iter().map(PkgPath::as_str).collect();
+        assert_eq!(deps, ["devel/libfoo"]);
+        Ok(())
+    }
+
+    #[test]
+    fn defaults() -> Result<(), Buildlink3Error> {
+        let bl3 = "BUILDLINK_TREE+=\tbar\nBUILDLINK_TREE+=\t-bar\n";
+        let bl3 = Buildlink3::parse(bl3, "devel/bar/buildlink3.mk")?;
+        assert_eq!(bl3.pkgsrcdir().as_str(), "devel/bar");
+        assert!(bl3.api_depends().is_empty());
+
+        let err = Buildlink3::parse("FOO=\tbar\n", "buildlink3.mk");
+        assert!(matches!(err, Err(Buildlink3Error::MissingTree)));
+        let err = Buildlink3::parse(
+            "BUILDLINK_TREE+=\tbar\nBUILDLINK_API_DEPENDS.bar+=\tbar>1>2\n",
+            "devel/bar/buildlink3.mk",
+        );
+        assert!(matches!(err, Err(Buildlink3Error::Depend { .. })));
+        Ok(())
+    }
+}
@@ -241,6 +241,30 @@ impl DeweyVersion {
             pkgrevision,
         })
     }
+
+    /*
+     * Return the numeric version components, excluding PKGREVISION.
+     */
+    pub fn components(&self) -> &[i64] {
+        &self.version
+    }
+
+    /*
+     * Return the PKGREVISION component.
+     */
+    pub fn pkgrevision(&self) -> i64 {
+        self.pkgrevision
+    }
+
+    /*
+     * Return a copy of this version with a different PKGREVISION.
+     */
+    pub fn with_pkgrevision(&self, pkgrevision: i64) -> Self {
+        Self {
+            version: self.version.clone(),
+            pkgrevision,
+        }
+    }
 }
 
 /*
@@ -451,6 +475,15 @@ impl Dewey {
     pub fn pkgbase(&self) -> &str {
         &self.pkgbase
     }
+
+    /*
+     * Return each comparison operator and the version it is tested against.
+     */
+    pub(crate) fn constraints(
+        &self,
+    ) -> impl Iterator<Item = (DeweyOp, &DeweyVersion)> {
+        self.matches.iter().map(|m| (m.op, &m.version))
+    }
 }
 
 /*
@@ -507,6 +540,19 @@ pub(crate) fn dewey_cmp(
     dewey_test(lhs.pkgrevision, op, rhs.pkgrevision)
 }
 
+/*
+ * Return the total ordering of two DeweyVersions.
+ */
+pub(crate) fn dewey_order(lhs: &DeweyVersion, rhs: &DeweyVersion) -> Ordering {
+    if dewey_cmp(lhs, &DeweyOp::LT, rhs) {
+        Ordering::Less
+    } else if dewey_cmp(lhs, &DeweyOp::GT, rhs) {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,753 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Build scheduling graph for resolved pbulk scan output.
+ *
+ * A [`BuildGraph`] is constructed from [`ScanIndex`] records whose
+ * [`resolved_depends`] have been filled in, for example by a
+ * [`Resolver`].  Each package is a node identified by its index in the
+ * input slice, with edges to the packages it depends on.
+ *
+ * Construction fails with [`GraphError::Cycle`] if the dependencies contain
+ * a cycle, reporting the full path around it.
+ *
+ * # Priorities
+ *
+ * Each package is given a critical-path priority: its own `PBULK_WEIGHT`
+ * (defaulting to 100) plus the highest priority of any package that depends
+ * on it.  Building the highest priority package first keeps the longest
+ * chains of dependents moving, and [`BuildGraph::order`] returns a
+ * topological order that follows this rule.
+ *
+ * # Scheduling
+ *
+ * A [`Scheduler`] tracks the state of a build.  The caller takes ready
+ * packages with [`Scheduler::pop`], and reports each result with
+ * [`Scheduler::succeeded`] or [`Scheduler::failed`].  As with pbulk,
+ * packages that already have a `PKG_SKIP_REASON` or `PKG_FAIL_REASON` are
+ * `prefailed`, and every package that depends on a failed or prefailed
+ * package is marked `indirect-failed` or `indirect-prefailed` without
+ * being built.
+ *
+ * ```
+ * use pkgsrc::ScanIndex;
+ * use pkgsrc::graph::{BuildGraph, BuildStatus};
+ *
+ * let scan = "PKGNAME=mktool-1.4.2\n\
+ *             DEPENDS=rust-1.86.0\n\
+ *             PKGNAME=rust-1.86.0\n\
+ *             MAKE_JOBS_SAFE=no\n\
+ *             PKGNAME=pkgin-25.1.0\n\
+ *             PKG_FAIL_REASON=broken\n";
+ * let pkgs = ScanIndex::from_reader(scan.as_bytes())
+ *     .collect::<Result<Vec<_>, _>>()?;
+ * let graph = BuildGraph::new(&pkgs)?;
+ * assert_eq!(graph.priority(1), 200);
+ *
+ * let mut sched = graph.scheduler();
+ * assert_eq!(sched.status(2), BuildStatus::Prefailed);
+ *
+ * let job = sched.pop().expect("rust is ready");
+ * assert_eq!(graph.pkgname(job.id()).pkgname(), "rust-1.86.0");
+ * assert!(!job.make_jobs_safe());
+ * assert!(sched.pop().is_none());
+ *
+ * let failed = sched.failed(job.id());
+ * assert_eq!(failed, [0]);
+ * assert_eq!(sched.status(0), BuildStatus::IndirectFailed);
+ * assert!(sched.is_finished());
+ * # Ok::<(), Box<dyn std::error::Error>>(())
+ * ```
+ *
+ * # Rebuild Impact
+ *
+ * An [`ImpactGraph`] answers the opposite question: given a set of packages
+ * that are about to change, which packages would need rebuilding.  See its
+ * documentation for details.
+ *
+ * # Export
+ *
+ * A [`DependGraph`] renders the dependencies of a scan, binary package
+ * repository or installed package database as Graphviz DOT or JSON, with
+ * optional restriction to selected packages and transitive reduction.
+ *
+ * [`Resolver`]: crate::scanindex::Resolver
+ * [`resolved_depends`]: ScanIndex::resolved_depends
+ */
+
+use crate::{PkgName, ScanIndex};
+use hashbrown::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+use thiserror::Error;
+
+mod export;
+mod impact;
+mod packages;
+
+pub use export::{ColorBy, DependGraph, Dot, Json, NodeStatus};
+pub use impact::{Impact, ImpactGraph};
+
+/**
+ * The `PBULK_WEIGHT` assumed for packages that do not set one.
+ */
+pub const DEFAULT_WEIGHT: u32 = 100;
+
+/**
+ * Errors that can occur when constructing a [`BuildGraph`].
+ */
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[non_exhaustive]
+pub enum GraphError {
+    /**
+     * The same `PKGNAME` appears more than once.
+     */
+    #[error("Duplicate PKGNAME {0}")]
+    Duplicate(PkgName),
+    /**
+     * A package depends on a `PKGNAME` that is not in the graph.
+     */
+    #[error("{pkgname} depends on unknown package {depend}")]
+    UnknownDepend {
+        /** The package with the dependency. */
+        pkgname: PkgName,
+        /** The unknown dependency. */
+        depend: PkgName,
+    },
+    /**
+     * The dependencies contain a cycle.  The path starts and ends with the
+     * same package, with each package depending on the next.
+     */
+    #[error("Dependency cycle: {}", fmt_cycle(.0))]
+    Cycle(Vec<PkgName>),
+}
+
+fn fmt_cycle(path: &[PkgName]) -> String {
+    path.iter()
+        .map(PkgName::pkgname)
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    pkgname: PkgName,
+    weight: u32,
+    make_jobs_safe: bool,
+    prefailed: bool,
+}
+
+/**
+ * A dependency graph of packages to build.  See the [module
+ * documentation](self) for details.
+ */
+#[derive(Clone, Debug)]
+pub struct BuildGraph {
+    nodes: Vec<Node>,
+    index: HashMap<String, usize>,
+    deps: Vec<Vec<usize>>,
+    rdeps: Vec<Vec<usize>>,
+    priority: Vec<u64>,
+    order: Vec<usize>,
+}
+
+impl BuildGraph {
+    /**
+     * Construct a graph from resolved scan records.  Node ids are indices
+     * into `packages`.
+     *
+     * # Errors
+     *
+     * Returns [`GraphError`] if a `PKGNAME` is duplicated, a dependency is
+     * not in `packages`, or the dependencies contain a cycle.
+     */
+    pub fn new(packages: &[ScanIndex]) -> Result<Self, GraphError> {
+        let mut index = HashMap::with_capacity(packages.len());
+        let mut nodes = Vec::with_capacity(packages.len());
+        for (id, pkg) in packages.iter().enumerate() {
+            let name = pkg.pkgname.pkgname();
+            if index.insert(name.to_string(), id).is_some() {
+                return Err(GraphError::Duplicate(pkg.pkgname.clone()));
+            }
+            let has_reason = |r: &Option<String>| {
+                r.as_deref().is_some_and(|s| !s.is_empty())
+            };
+            nodes.push(Node {
+                pkgname: pkg.pkgname.clone(),
+                weight: pkg.pbulk_weight.unwrap_or(DEFAULT_WEIGHT),
+                make_jobs_safe: pkg
+                    .make_jobs_safe
+                    .as_ref()
+                    .is_none_or(|m| m.is_safe()),
+                prefailed: has_reason(&pkg.pkg_skip_reason)
+                    || has_reason(&pkg.pkg_fail_reason),
+            });
+        }
+
+        let mut deps = vec![Vec::new(); packages.len()];
+        let mut rdeps = vec![Vec::new(); packages.len()];
+        for (id, pkg) in packages.iter().enumerate() {
+            for dep in pkg.depends() {
+                let Some(&dep_id) = index.get(dep.pkgname()) else {
+                    return Err(GraphError::UnknownDepend {
+                        pkgname: pkg.pkgname.clone(),
+                        depend: dep.clone(),
+                    });
+                };
+                deps[id].push(dep_id);
+                rdeps[dep_id].push(id);
+            }
+        }
+
+        let topo = topo_sort(&deps, &nodes)?;
+
+        /*
+         * Dependents always come after their dependencies in topo, so
+         * walking it backwards visits every dependent first.
+         */
+        let mut priority = vec![0u64; nodes.len()];
+        for &id in topo.iter().rev() {
+            let max = rdeps[id].iter().map(|&r| priority[r]).max();
+            priority[id] = u64::from(nodes[id].weight) + max.unwrap_or(0);
+        }
+
+        /*
+         * Kahn's algorithm, always taking the highest priority ready node,
+         * with ties broken by input order.
+         */
+        let mut remaining: Vec<usize> = deps.iter().map(Vec::len).collect();
+        let mut ready: BinaryHeap<(u64, Reverse<usize>)> = remaining
+            .iter()
+            .enumerate()
+            .filter(|&(_, &n)| n == 0)
+            .map(|(id, _)| (priority[id], Reverse(id)))
+            .collect();
+        let mut order = Vec::with_capacity(nodes.len());
+        while let Some((_, Reverse(id))) = ready.pop() {
+            order.push(id);
+            for &r in &rdeps[id] {
+                remaining[r] -= 1;
+                if remaining[r] == 0 {
+                    ready.push((priority[r], Reverse(r)));
+                }
+            }
+        }
+
+        Ok(Self {
+            nodes,
+            index,
+            deps,
+            rdeps,
+            priority,
+            order,
+        })
+    }
+
+    /**
+     * Return the number of packages in the graph.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /**
+     * Return true if the graph contains no packages.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /**
+     * Return the id of a package by its `PKGNAME`.
+     */
+    #[must_use]
+    pub fn id(&self, pkgname: &str) -> Option<usize> {
+        self.index.get(pkgname).copied()
+    }
+
+    /**
+     * Return the `PKGNAME` of a package.
+     *
+     * # Panics
+     *
+     * Panics if `id` is out of range.  The same applies to all other
+     * methods that take a package id.
+     */
+    #[must_use]
+    pub fn pkgname(&self, id: usize) -> &PkgName {
+        &self.nodes[id].pkgname
+    }
+
+    /**
+     * Return the ids of the packages that `id` depends on directly.
+     */
+    #[must_use]
+    pub fn depends(&self, id: usize) -> &[usize] {
+        &self.deps[id]
+    }
+
+    /**
+     * Return the ids of the packages that depend on `id` directly.
+     */
+    #[must_use]
+    pub fn dependents(&self, id: usize) -> &[usize] {
+        &self.rdeps[id]
+    }
+
+    /**
+     * Return the `PBULK_WEIGHT` of a package, or [`DEFAULT_WEIGHT`] if it
+     * was not set.
+     */
+    #[must_use]
+    pub fn weight(&self, id: usize) -> u32 {
+        self.nodes[id].weight
+    }
+
+    /**
+     * Return the critical-path priority of a package.
+     */
+    #[must_use]
+    pub fn priority(&self, id: usize) -> u64 {
+        self.priority[id]
+    }
+
+    /**
+     * Return false if the package sets `MAKE_JOBS_SAFE=no` and must not be
+     * built with parallel make jobs.
+     */
+    #[must_use]
+    pub fn make_jobs_safe(&self, id: usize) -> bool {
+        self.nodes[id].make_jobs_safe
+    }
+
+    /**
+     * Return true if the package had a `PKG_SKIP_REASON` or
+     * `PKG_FAIL_REASON` and will not be built.
+     */
+    #[must_use]
+    pub fn is_prefailed(&self, id: usize) -> bool {
+        self.nodes[id].prefailed
+    }
+
+    /**
+     * Return all package ids in a topological order, with dependencies
+     * before their dependents and higher priority packages first where
+     * there is a choice.
+     */
+    #[must_use]
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /**
+     * Return the pbulk `PKG_DEPTH` of every package, indexed by id: the
+     * number of packages that depend on it directly or indirectly, plus one
+     * for the package itself.
+     */
+    #[must_use]
+    pub fn depths(&self) -> Vec<usize> {
+        /*
+         * Uses an epoch counter to avoid clearing the visited array on each
+         * traversal.
+         */
+        let mut depth = vec![1; self.len()];
+        let mut visited = vec![0u32; self.len()];
+        let mut queue = VecDeque::new();
+        for (id, epoch) in (0..self.len()).zip(1u32..) {
+            queue.clear();
+            queue.push_back(id);
+            visited[id] = epoch;
+            let mut count = 0;
+            while let Some(node) = queue.pop_front() {
+                count += 1;
+                for &r in &self.rdeps[node] {
+                    if visited[r] != epoch {
+                        visited[r] = epoch;
+                        queue.push_back(r);
+                    }
+                }
+            }
+            depth[id] = count;
+        }
+        depth
+    }
+
+    /**
+     * Create a new [`Scheduler`] for building this graph.
+     */
+    #[must_use]
+    pub fn scheduler(&self) -> Scheduler<'_> {
+        Scheduler::new(self)
+    }
+}
+
+/*
+ * Depth-first topological sort, returning dependencies before dependents,
+ * or the first cycle found.
+ */
+fn topo_sort(
+    deps: &[Vec<usize>],
+    nodes: &[Node],
+) -> Result<Vec<usize>, GraphError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        Active,
+        Done,
+    }
+    let mut mark = vec![Mark::New; deps.len()];
+    let mut order = Vec::with_capacity(deps.len());
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for root in 0..deps.len() {
+        if mark[root] != Mark::New {
+            continue;
+        }
+        mark[root] = Mark::Active;
+        stack.push((root, 0));
+        while let Some((id, next)) = stack.last_mut() {
+            let id = *id;
+            let Some(&dep) = deps[id].get(*next) else {
+                mark[id] = Mark::Done;
+                order.push(id);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            match mark[dep] {
+                Mark::New => {
+                    mark[dep] = Mark::Active;
+                    stack.push((dep, 0));
+                }
+                Mark::Active => {
+                    let start = stack
+                        .iter()
+                        .position(|&(n, _)| n == dep)
+                        .expect("active node on stack");
+                    let path = stack[start..]
+                        .iter()
+                        .map(|&(n, _)| n)
+                        .chain(std::iter::once(dep))
+                        .map(|n| nodes[n].pkgname.clone())
+                        .collect();
+                    return Err(GraphError::Cycle(path));
+                }
+                Mark::Done => {}
+            }
+        }
+    }
+    Ok(order)
+}
+
+/**
+ * The state of a package in a [`Scheduler`].  The [`Display`] form matches
+ * pbulk's `BUILD_STATUS` values.
+ *
+ * [`Display`]: fmt::Display
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BuildStatus {
+    /// Waiting for dependencies or to be taken with [`Scheduler::pop`].
+    Pending,
+    /// Taken with [`Scheduler::pop`] and not yet reported.
+    Building,
+    /// Built successfully.
+    Done,
+    /// Failed to build.
+    Failed,
+    /// Not built due to `PKG_SKIP_REASON` or `PKG_FAIL_REASON`.
+    Prefailed,
+    /// Not built because a dependency failed.
+    IndirectFailed,
+    /// Not built because a dependency was prefailed.
+    IndirectPrefailed,
+}
+
+impl BuildStatus {
+    /**
+     * Return true if the package will not change state again.
+     */
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, BuildStatus::Pending | BuildStatus::Building)
+    }
+}
+
+impl fmt::Display for BuildStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BuildStatus::Pending => "pending",
+            BuildStatus::Building => "building",
+            BuildStatus::Done => "done",
+            BuildStatus::Failed => "failed",
+            BuildStatus::Prefailed => "prefailed",
+            BuildStatus::IndirectFailed => "indirect-failed",
+            BuildStatus::IndirectPrefailed => "indirect-prefailed",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/**
+ * A package returned by [`Scheduler::pop`] that is ready to build.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Job {
+    id: usize,
+    make_jobs_safe: bool,
+}
+
+impl Job {
+    /**
+     * Return the package id.
+     */
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /**
+     * Return false if the package must not be built with parallel make
+     * jobs.  Schedulers may use this to give such packages a single job
+     * slot.
+     */
+    #[must_use]
+    pub fn make_jobs_safe(&self) -> bool {
+        self.make_jobs_safe
+    }
+}
+
+/**
+ * Tracks the progress of a build over a [`BuildGraph`].  Created by
+ * [`BuildGraph::scheduler`].
+ */
+#[derive(Clone, Debug)]
+pub struct Scheduler<'a> {
+    graph: &'a BuildGraph,
+    status: Vec<BuildStatus>,
+    remaining: Vec<usize>,
+    ready: BinaryHeap<(u64, Reverse<usize>)>,
+    building: usize,
+}
+
+impl<'a> Scheduler<'a> {
+    fn new(graph: &'a BuildGraph) -> Self {
+        let mut sched = Self {
+            graph,
+            status: vec![BuildStatus::Pending; graph.len()],
+            remaining: graph.deps.iter().map(Vec::len).collect(),
+            ready: BinaryHeap::new(),
+            building: 0,
+        };
+        let prefailed: Vec<usize> = (0..graph.len())
+            .filter(|&id| graph.is_prefailed(id))
+            .collect();
+        for &id in &prefailed {
+            sched.status[id] = BuildStatus::Prefailed;
+        }
+        for id in prefailed {
+            sched.propagate(id, BuildStatus::IndirectPrefailed);
+        }
+        for id in 0..graph.len() {
+            if sched.status[id] == BuildStatus::Pending
+                && sched.remaining[id] == 0
+            {
+                sched.ready.push((graph.priority(id), Reverse(id)));
+            }
+        }
+        sched
+    }
+
+    /*
+     * Mark every pending transitive dependent of id with status, returning
+     * the ids that changed.
+     */
+    fn propagate(&mut self, id: usize, status: BuildStatus) -> Vec<usize> {
+        let mut changed = Vec::new();
+        let mut queue = VecDeque::from([id]);
+        while let Some(node) = queue.pop_front() {
+            for &r in self.graph.dependents(node) {
+                if self.status[r] == BuildStatus::Pending {
+                    self.status[r] = status;
+                    changed.push(r);
+                    queue.push_back(r);
+                }
+            }
+        }
+        changed
+    }
+
+    /**
+     * Return the current status of a package.
+     */
+    #[must_use]
+    pub fn status(&self, id: usize) -> BuildStatus {
+        self.status[id]
+    }
+
+    /**
+     * Return the ids of all packages ready to build, highest priority
+     * first.
+     */
+    #[must_use]
+    pub fn ready(&self) -> Vec<usize> {
+        let mut ready: Vec<_> = self.ready.iter().copied().collect();
+        ready.sort_unstable_by(|a, b| b.cmp(a));
+        ready.into_iter().map(|(_, Reverse(id))| id).collect()
+    }
+
+    /**
+     * Take the highest priority package that is ready to build, marking it
+     * as [`BuildStatus::Building`].  Returns [`None`] if nothing is ready,
+     * which may be because packages are still building.
+     */
+    pub fn pop(&mut self) -> Option<Job> {
+        let (_, Reverse(id)) = self.ready.pop()?;
+        self.status[id] = BuildStatus::Building;
+        self.building += 1;
+        Some(Job {
+            id,
+            make_jobs_safe: self.graph.make_jobs_safe(id),
+        })
+    }
+
+    /**
+     * Record that a package built successfully, making any dependents
+     * whose dependencies are now all built ready.  Does nothing if the
+     * package is not [`BuildStatus::Building`].
+     */
+    pub fn succeeded(&mut self, id: usize) {
+        if self.status[id] != BuildStatus::Building {
+            return;
+        }
+        self.status[id] = BuildStatus::Done;
+        self.building -= 1;
+        for &r in self.graph.dependents(id) {
+            self.remaining[r] -= 1;
+            if self.remaining[r] == 0 && self.status[r] == BuildStatus::Pending
+            {
+                self.ready.push((self.graph.priority(r), Reverse(r)));
+            }
+        }
+    }
+
+    /**
+     * Record that a package failed to build, marking every package that
+     * depends on it as [`BuildStatus::IndirectFailed`].  Returns the ids of
+     * the packages that were marked.  Does nothing if the package is not
+     * [`BuildStatus::Building`].
+     */
+    pub fn failed(&mut self, id: usize) -> Vec<usize> {
+        if self.status[id] != BuildStatus::Building {
+            return Vec::new();
+        }
+        self.status[id] = BuildStatus::Failed;
+        self.building -= 1;
+        self.propagate(id, BuildStatus::IndirectFailed)
+    }
+
+    /**
+     * Return the number of packages currently building.
+     */
+    #[must_use]
+    pub fn building(&self) -> usize {
+        self.building
+    }
+
+    /**
+     * Return true when no packages are ready or building.
+     */
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.ready.is_empty() && self.building == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(s: &str) -> Result<BuildGraph, GraphError> {
+        let pkgs: Vec<ScanIndex> = ScanIndex::from_reader(s.as_bytes())
+            .collect::<Result<_, _>>()
+            .expect("valid scan");
+        BuildGraph::new(&pkgs)
+    }
+
+    #[test]
+    fn cycle() {
+        let err = graph(
+            "PKGNAME=a-1\nDEPENDS=b-1\n\
+             PKGNAME=b-1\nDEPENDS=c-1\n\
+             PKGNAME=c-1\nDEPENDS=b-1\n",
+        )
+        .expect_err("cycle");
+        assert_eq!(err.to_string(), "Dependency cycle: b-1 -> c-1 -> b-1");
+        assert!(matches!(
+            graph("PKGNAME=a-1\nDEPENDS=z-1\n"),
+            Err(GraphError::UnknownDepend { .. })
+        ));
+    }
+
+    #[test]
+    fn priority_order() -> Result<(), GraphError> {
+        /*
+         * b has the heavier dependent chain so is ordered before a, even
+         * though a appears first.
+         */
+        let g = graph(
+            "PKGNAME=a-1\n\
+             PKGNAME=b-1\n\
+             PKGNAME=c-1\nDEPENDS=b-1\nPBULK_WEIGHT=500\n\
+             PKGNAME=d-1\nDEPENDS=a-1 c-1\n",
+        )?;
+        assert_eq!(g.priority(1), 700);
+        assert_eq!(g.priority(0), 200);
+        assert_eq!(g.order(), [1, 2, 0, 3]);
+        assert_eq!(g.depths(), [2, 3, 2, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn schedule() -> Result<(), GraphError> {
+        let g = graph(
+            "PKGNAME=a-1\n\
+             PKGNAME=b-1\nDEPENDS=a-1\n\
+             PKGNAME=c-1\nDEPENDS=b-1\n\
+             PKGNAME=d-1\nPKG_SKIP_REASON=skip\n\
+             PKGNAME=e-1\nDEPENDS=d-1\n\
+             PKGNAME=f-1\n",
+        )?;
+        let mut s = g.scheduler();
+        assert_eq!(s.status(4), BuildStatus::IndirectPrefailed);
+        assert_eq!(s.ready(), [0, 5]);
+        let a = s.pop().map(|j| j.id());
+        assert_eq!(a, Some(0));
+        s.succeeded(0);
+        assert_eq!(s.ready(), [1, 5]);
+        assert_eq!(s.pop().map(|j| j.id()), Some(1));
+        assert_eq!(s.failed(1), [2]);
+        assert_eq!(s.status(2).to_string(), "indirect-failed");
+        assert_eq!(s.pop().map(|j| j.id()), Some(5));
+        assert!(!s.is_finished());
+        s.succeeded(5);
+        assert!(s.is_finished());
+        Ok(())
+    }
+}
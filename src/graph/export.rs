@@ -0,0 +1,632 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::packages::PackageGraph;
+use crate::summary::Summary;
+use crate::{DependType, PatternError, PkgDB, PkgName, ScanIndex};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+
+/**
+ * The build state of a package in a [`DependGraph`].
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum NodeStatus {
+    /// No known problems.
+    #[default]
+    Ok,
+    /// The package has a `PKG_SKIP_REASON`.
+    Skipped,
+    /// The package has a `PKG_FAIL_REASON`.
+    Failed,
+}
+
+impl fmt::Display for NodeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            NodeStatus::Ok => "ok",
+            NodeStatus::Skipped => "skipped",
+            NodeStatus::Failed => "failed",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/**
+ * How [`DependGraph::dot`] colours its output.
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ColorBy {
+    /// No colours.
+    #[default]
+    None,
+    /// Fill skipped packages grey and failed packages red.
+    Status,
+    /// Colour edges by their [`DependType`], and outline each package in
+    /// the colour of its incoming edges when they all share one type.
+    /// Edges with no known type are left uncoloured.
+    DependType,
+}
+
+fn type_name(kind: Option<DependType>) -> &'static str {
+    match kind {
+        Some(DependType::Full) => "\"full\"",
+        Some(DependType::Build) => "\"build\"",
+        Some(DependType::Bootstrap) => "\"bootstrap\"",
+        Some(DependType::Tool) => "\"tool\"",
+        Some(DependType::Test) => "\"test\"",
+        None => "null",
+    }
+}
+
+fn type_color(kind: DependType) -> &'static str {
+    match kind {
+        DependType::Full => "black",
+        DependType::Build => "blue",
+        DependType::Bootstrap => "purple",
+        DependType::Tool => "darkgreen",
+        DependType::Test => "orange",
+    }
+}
+
+/**
+ * A package dependency graph for visualisation.
+ *
+ * Graphs can be constructed from resolved scan output, a binary package
+ * repository, or an installed package database, optionally limited to the
+ * dependencies of selected packages with [`DependGraph::closure`] and
+ * simplified with [`DependGraph::reduce`], then rendered as Graphviz DOT
+ * with [`DependGraph::dot`] or as a JSON node and edge list with
+ * [`DependGraph::json`].
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::ScanIndex;
+ * use pkgsrc::graph::{ColorBy, DependGraph};
+ *
+ * let scan = "PKGNAME=gettext-lib-0.22\n\
+ *             PKGNAME=glib2-2.82.0\n\
+ *             DEPENDS=gettext-lib-0.22\n\
+ *             PKGNAME=gtk3-3.24.43\n\
+ *             DEPENDS=glib2-2.82.0 gettext-lib-0.22\n\
+ *             PKGNAME=unrelated-1.0\n";
+ * let pkgs = ScanIndex::from_reader(scan.as_bytes())
+ *     .collect::<Result<Vec<_>, _>>()?;
+ *
+ * let mut graph = DependGraph::from_scan(&pkgs).closure(["gtk3-3.24.43"]);
+ * graph.reduce();
+ * assert_eq!(
+ *     graph.dot(ColorBy::None).to_string(),
+ *     "digraph pkgsrc {\n\
+ *     \t\"gettext-lib-0.22\";\n\
+ *     \t\"glib2-2.82.0\";\n\
+ *     \t\"gtk3-3.24.43\";\n\
+ *     \t\"glib2-2.82.0\" -> \"gettext-lib-0.22\";\n\
+ *     \t\"gtk3-3.24.43\" -> \"glib2-2.82.0\";\n\
+ *     }\n"
+ * );
+ * # Ok::<(), std::io::Error>(())
+ * ```
+ */
+#[derive(Clone, Debug, Default)]
+pub struct DependGraph(PackageGraph<NodeStatus>);
+
+impl DependGraph {
+    /**
+     * Create an empty graph.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Construct a graph from resolved scan records, using
+     * [`resolved_depends`] for the edges.  Scan output does not record the
+     * type of each dependency, so every edge has no type, and is left
+     * uncoloured by [`ColorBy::DependType`].  Dependencies that are not in
+     * `packages` are ignored.
+     *
+     * [`resolved_depends`]: ScanIndex::resolved_depends
+     */
+    #[must_use]
+    pub fn from_scan(packages: &[ScanIndex]) -> Self {
+        let has_reason =
+            |r: &Option<String>| r.as_deref().is_some_and(|s| !s.is_empty());
+        Self(PackageGraph::from_scan(packages, |p| {
+            if has_reason(&p.pkg_skip_reason) {
+                NodeStatus::Skipped
+            } else if has_reason(&p.pkg_fail_reason) {
+                NodeStatus::Failed
+            } else {
+                NodeStatus::Ok
+            }
+        }))
+    }
+
+    /**
+     * Construct a graph from a binary package repository.  Each `DEPENDS`
+     * pattern is resolved to its best match within `summaries` and added as
+     * a [`DependType::Full`] edge.  Patterns that match nothing are ignored.
+     *
+     * # Errors
+     *
+     * Returns [`PatternError`] if a `DEPENDS` pattern is invalid, or if a
+     * matching package version cannot be parsed.
+     */
+    pub fn from_summaries(summaries: &[Summary]) -> Result<Self, PatternError> {
+        PackageGraph::from_summaries(summaries, |_| NodeStatus::Ok).map(Self)
+    }
+
+    /**
+     * Construct a graph from an installed package database.  `@pkgdep`
+     * entries are added as [`DependType::Full`] edges, and `@blddep`
+     * entries for packages that are still installed as
+     * [`DependType::Build`] edges.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the package database cannot be read, or
+     * with [`io::ErrorKind::InvalidData`] if a `+CONTENTS` file or a
+     * dependency pattern is invalid.
+     */
+    pub fn from_pkgdb(pkgdb: PkgDB) -> io::Result<Self> {
+        PackageGraph::from_pkgdb(pkgdb, |_| NodeStatus::Ok).map(Self)
+    }
+
+    /**
+     * Add a package, returning its id.  If a package with the same
+     * `PKGNAME` already exists its id is returned unchanged.
+     */
+    pub fn add_package(
+        &mut self,
+        pkgname: PkgName,
+        pkgpath: Option<&str>,
+        status: NodeStatus,
+    ) -> usize {
+        self.0.add_package(pkgname, pkgpath, status)
+    }
+
+    /**
+     * Record that package `pkg` depends on package `depend`.  Adding the
+     * same edge with the same type more than once has no effect.
+     *
+     * # Panics
+     *
+     * Panics if either id is out of range.
+     */
+    pub fn add_depend(&mut self, pkg: usize, depend: usize, kind: DependType) {
+        self.0.add_depend(pkg, depend, Some(kind));
+    }
+
+    /**
+     * Return the number of packages in the graph.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /**
+     * Return true if the graph contains no packages.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
+
+    /**
+     * Return the id of a package by its `PKGNAME`.
+     */
+    #[must_use]
+    pub fn id(&self, pkgname: &str) -> Option<usize> {
+        self.0.id(pkgname)
+    }
+
+    /**
+     * Return the `PKGNAME` of a package.
+     *
+     * # Panics
+     *
+     * Panics if `id` is out of range.  The same applies to all other
+     * methods that take a package id.
+     */
+    #[must_use]
+    pub fn pkgname(&self, id: usize) -> &PkgName {
+        &self.0.node(id).pkgname
+    }
+
+    /**
+     * Return the `PKGPATH` of a package, if known.
+     */
+    #[must_use]
+    pub fn pkgpath(&self, id: usize) -> Option<&str> {
+        self.0.node(id).pkgpath.as_deref()
+    }
+
+    /**
+     * Return the status of a package.
+     */
+    #[must_use]
+    pub fn status(&self, id: usize) -> NodeStatus {
+        self.0.node(id).data
+    }
+
+    /**
+     * Return the dependencies of a package and their types, if known.
+     */
+    #[must_use]
+    pub fn depends(&self, id: usize) -> &[(usize, Option<DependType>)] {
+        self.0.depends(id)
+    }
+
+    /**
+     * Return the number of edges in the graph.
+     */
+    #[must_use]
+    pub fn edge_count(&self) -> usize {
+        self.0.edge_count()
+    }
+
+    /**
+     * Return a new graph containing only the packages matching `targets`,
+     * each of which may be a `PKGNAME` or `PKGPATH`, and everything they
+     * depend on directly or indirectly.  Unknown targets are ignored.
+     */
+    #[must_use]
+    pub fn closure<I, S>(&self, targets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut keep = vec![false; self.len()];
+        let mut queue = VecDeque::new();
+        for target in targets {
+            for id in self.0.find(target.as_ref()) {
+                if !keep[id] {
+                    keep[id] = true;
+                    queue.push_back(id);
+                }
+            }
+        }
+        while let Some(id) = queue.pop_front() {
+            for &(dep, _) in self.depends(id) {
+                if !keep[dep] {
+                    keep[dep] = true;
+                    queue.push_back(dep);
+                }
+            }
+        }
+
+        let mut graph = Self::new();
+        let mut map = vec![usize::MAX; self.len()];
+        for (id, node) in self.0.nodes().iter().enumerate() {
+            if keep[id] {
+                map[id] = graph.add_package(
+                    node.pkgname.clone(),
+                    node.pkgpath.as_deref(),
+                    node.data,
+                );
+            }
+        }
+        for id in 0..self.len() {
+            if keep[id] {
+                for &(dep, kind) in self.depends(id) {
+                    graph.0.add_depend(map[id], map[dep], kind);
+                }
+            }
+        }
+        graph
+    }
+
+    /**
+     * Remove edges that are implied by other paths, so that `a -> c` is
+     * dropped when `a -> b -> c` also exists.  Reachability between
+     * packages is unchanged, although the types of removed edges are lost.
+     *
+     * This is intended for graphs that have been limited with
+     * [`DependGraph::closure`], as the cost grows with the product of the
+     * number of packages and edges.
+     */
+    pub fn reduce(&mut self) {
+        let mut seen = vec![0usize; self.len()];
+        let mut stack = Vec::new();
+        let mut epoch = 0;
+        for id in 0..self.len() {
+            /*
+             * Edges are considered one at a time against the current graph,
+             * so that two packages in a cycle cannot each justify removing
+             * the edge to the other.
+             */
+            let mut i = 0;
+            while i < self.depends(id).len() {
+                let target = self.depends(id)[i].0;
+                epoch += 1;
+                seen[id] = epoch;
+                stack.clear();
+                stack.extend(
+                    self.depends(id)
+                        .iter()
+                        .map(|&(d, _)| d)
+                        .filter(|&d| d != target),
+                );
+                while let Some(n) = stack.pop() {
+                    if seen[n] == epoch {
+                        continue;
+                    }
+                    seen[n] = epoch;
+                    stack.extend(self.depends(n).iter().map(|&(d, _)| d));
+                }
+                if target != id && seen[target] == epoch {
+                    self.0.remove_depend(id, i);
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /**
+     * Return a [`Display`] wrapper that renders the graph in Graphviz DOT
+     * format, with packages and edges in id order.
+     *
+     * [`Display`]: fmt::Display
+     */
+    #[must_use]
+    pub fn dot(&self, color: ColorBy) -> Dot<'_> {
+        Dot { graph: self, color }
+    }
+
+    /**
+     * Return a [`Display`] wrapper that renders the graph as a JSON object
+     * with `nodes` and `edges` arrays.  Each node has an `id`, `pkgname`,
+     * `pkgpath` (or `null`) and `status`, and each edge has `from` and
+     * `to` node ids and a `type` (or `null` if unknown).
+     *
+     * [`Display`]: fmt::Display
+     */
+    #[must_use]
+    pub fn json(&self) -> Json<'_> {
+        Json(self)
+    }
+}
+
+/**
+ * [`Display`](fmt::Display) wrapper for Graphviz DOT output.
+ *
+ * Created by [`DependGraph::dot`].
+ */
+pub struct Dot<'a> {
+    graph: &'a DependGraph,
+    color: ColorBy,
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl fmt::Display for Dot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let g = self.graph;
+        /*
+         * The single type shared by all incoming edges of each package, or
+         * Some(None) if they differ or any is unknown.
+         */
+        let mut incoming: Vec<Option<Option<DependType>>> = vec![None; g.len()];
+        for id in 0..g.len() {
+            for &(d, kind) in g.depends(id) {
+                incoming[d] = match incoming[d] {
+                    None => Some(kind),
+                    Some(k) if k == kind => Some(k),
+                    Some(_) => Some(None),
+                };
+            }
+        }
+
+        writeln!(f, "digraph pkgsrc {{")?;
+        for (id, node) in g.0.nodes().iter().enumerate() {
+            let name = dot_quote(node.pkgname.pkgname());
+            let attrs = match (self.color, node.data) {
+                (ColorBy::Status, NodeStatus::Skipped) => {
+                    Some("style=filled, fillcolor=lightgrey".to_string())
+                }
+                (ColorBy::Status, NodeStatus::Failed) => {
+                    Some("style=filled, fillcolor=salmon".to_string())
+                }
+                (ColorBy::DependType, _) => incoming[id]
+                    .flatten()
+                    .map(|k| format!("color={}", type_color(k))),
+                _ => None,
+            };
+            match attrs {
+                Some(attrs) => writeln!(f, "\t{name} [{attrs}];")?,
+                None => writeln!(f, "\t{name};")?,
+            }
+        }
+        for id in 0..g.len() {
+            let from = dot_quote(g.pkgname(id).pkgname());
+            for &(d, kind) in g.depends(id) {
+                let to = dot_quote(g.pkgname(d).pkgname());
+                match (self.color, kind) {
+                    (ColorBy::DependType, Some(kind)) => {
+                        let color = type_color(kind);
+                        writeln!(f, "\t{from} -> {to} [color={color}];")?;
+                    }
+                    _ => writeln!(f, "\t{from} -> {to};")?,
+                }
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/**
+ * [`Display`](fmt::Display) wrapper for JSON output.
+ *
+ * Created by [`DependGraph::json`].
+ */
+pub struct Json<'a>(&'a DependGraph);
+
+fn json_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", u32::from(c)));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl fmt::Display for Json<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let g = self.0;
+        writeln!(f, "{{")?;
+        writeln!(f, "  \"nodes\": [")?;
+        for (id, node) in g.0.nodes().iter().enumerate() {
+            let sep = if id + 1 < g.len() { "," } else { "" };
+            let pkgpath = node
+                .pkgpath
+                .as_deref()
+                .map_or_else(|| "null".to_string(), json_quote);
+            writeln!(
+                f,
+                "    {{\"id\": {id}, \"pkgname\": {}, \"pkgpath\": {pkgpath}, \
+                 \"status\": \"{}\"}}{sep}",
+                json_quote(node.pkgname.pkgname()),
+                node.data,
+            )?;
+        }
+        writeln!(f, "  ],")?;
+        writeln!(f, "  \"edges\": [")?;
+        let total = g.edge_count();
+        let edges = (0..g.len())
+            .flat_map(|id| g.depends(id).iter().map(move |&(d, k)| (id, d, k)));
+        for (i, (from, to, kind)) in edges.enumerate() {
+            let sep = if i + 1 < total { "," } else { "" };
+            writeln!(
+                f,
+                "    {{\"from\": {from}, \"to\": {to}, \"type\": {}}}{sep}",
+                type_name(kind),
+            )?;
+        }
+        writeln!(f, "  ]")?;
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> DependGraph {
+        let mut g = DependGraph::new();
+        let a = g.add_package(PkgName::new("a-1"), Some("x/a"), NodeStatus::Ok);
+        let b = g.add_package(PkgName::new("b-1"), None, NodeStatus::Failed);
+        let c = g.add_package(PkgName::new("c-1"), None, NodeStatus::Skipped);
+        let d = g.add_package(PkgName::new("d\"1"), None, NodeStatus::Ok);
+        g.add_depend(a, b, DependType::Full);
+        g.add_depend(a, c, DependType::Build);
+        g.add_depend(b, c, DependType::Full);
+        g.add_depend(c, b, DependType::Full);
+        g.add_depend(d, a, DependType::Tool);
+        g
+    }
+
+    #[test]
+    fn closure_reduce() {
+        let g = graph();
+        let sub = g.closure(["x/a", "unknown"]);
+        assert_eq!(sub.len(), 3);
+        assert_eq!(sub.edge_count(), 4);
+        let mut sub = sub;
+        sub.reduce();
+        /* The b <-> c cycle must not remove both of a's edges. */
+        assert_eq!(sub.edge_count(), 3);
+        assert_eq!(sub.depends(0).len(), 1);
+    }
+
+    #[test]
+    fn dot() {
+        let g = graph().closure(["d\"1"]);
+        let dot = g.dot(ColorBy::Status).to_string();
+        assert!(dot.contains("\t\"b-1\" [style=filled, fillcolor=salmon];\n"));
+        assert!(dot.contains("\t\"d\\\"1\";\n"));
+        let dot = g.dot(ColorBy::DependType).to_string();
+        assert!(dot.contains("\t\"a-1\" [color=darkgreen];\n"));
+        assert!(dot.contains("\t\"a-1\" -> \"c-1\" [color=blue];\n"));
+    }
+
+    #[test]
+    fn scan_untyped() -> io::Result<()> {
+        let scan = "PKGNAME=a-1\nDEPENDS=b-1\nPKGNAME=b-1\n";
+        let pkgs = ScanIndex::from_reader(scan.as_bytes())
+            .collect::<Result<Vec<_>, _>>()?;
+        let g = DependGraph::from_scan(&pkgs);
+        assert_eq!(g.depends(0), [(1, None)]);
+        let dot = g.dot(ColorBy::DependType).to_string();
+        assert!(dot.contains("\t\"b-1\";\n"));
+        assert!(dot.contains("\t\"a-1\" -> \"b-1\";\n"));
+        assert!(g.json().to_string().contains("\"type\": null}"));
+        Ok(())
+    }
+
+    #[test]
+    fn pkgdb() -> io::Result<()> {
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/pkgdb-graph");
+        let g = DependGraph::from_pkgdb(PkgDB::open(path)?)?;
+        let id = |name| g.id(name).ok_or(io::ErrorKind::NotFound);
+        let deps = g.depends(id("mktool-1.4.2")?);
+        for edge in [
+            (id("cwrappers-20150314")?, Some(DependType::Build)),
+            (id("gettext-lib-0.22")?, Some(DependType::Full)),
+            (id("gettext-lib-0.22")?, Some(DependType::Build)),
+        ] {
+            assert!(deps.contains(&edge));
+        }
+        assert_eq!(g.edge_count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn json() {
+        let mut g = DependGraph::new();
+        let a = g.add_package(PkgName::new("a-1"), Some("x/a"), NodeStatus::Ok);
+        let b = g.add_package(PkgName::new("b\"1"), None, NodeStatus::Failed);
+        g.add_depend(a, b, DependType::Build);
+        assert_eq!(
+            g.json().to_string(),
+            "{\n  \"nodes\": [\n    \
+             {\"id\": 0, \"pkgname\": \"a-1\", \"pkgpath\": \"x/a\", \
+             \"status\": \"ok\"},\n    \
+             {\"id\": 1, \"pkgname\": \"b\\\"1\", \"pkgpath\": null, \
+             \"status\": \"failed\"}\n  ],\n  \"edges\": [\n    \
+             {\"from\": 0, \"to\": 1, \"type\": \"build\"}\n  ]\n}\n"
+        );
+    }
+}
@@ -0,0 +1,456 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::DEFAULT_WEIGHT;
+use super::packages::PackageGraph;
+use crate::summary::Summary;
+use crate::{DependType, PatternError, PkgDB, PkgName, ScanIndex};
+use std::io;
+
+/**
+ * A reverse dependency graph for answering "what would need rebuilding if
+ * this package changed?".
+ *
+ * Unlike [`BuildGraph`], cycles are permitted and each edge may record the
+ * [`DependType`] of the dependency, so that impact can be limited to, for
+ * example, runtime dependencies only.
+ *
+ * Graphs can be constructed from resolved scan output with
+ * [`ImpactGraph::from_scan`], from a binary package repository with
+ * [`ImpactGraph::from_summaries`], from an installed package database with
+ * [`ImpactGraph::from_pkgdb`], or built up by hand.  Only the package
+ * database and hand-built graphs distinguish between dependency types.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::ScanIndex;
+ * use pkgsrc::graph::ImpactGraph;
+ *
+ * let scan = "PKGNAME=gettext-lib-0.22\n\
+ *             PKG_LOCATION=devel/gettext-lib\n\
+ *             PKGNAME=glib2-2.82.0\n\
+ *             PKG_LOCATION=devel/glib2\n\
+ *             DEPENDS=gettext-lib-0.22\n\
+ *             PBULK_WEIGHT=500\n\
+ *             PKGNAME=gtk3-3.24.43\n\
+ *             PKG_LOCATION=x11/gtk3\n\
+ *             DEPENDS=glib2-2.82.0 gettext-lib-0.22\n";
+ * let pkgs = ScanIndex::from_reader(scan.as_bytes())
+ *     .collect::<Result<Vec<_>, _>>()?;
+ * let graph = ImpactGraph::from_scan(&pkgs);
+ *
+ * let impact = graph.impact(["devel/gettext-lib"]);
+ * assert_eq!(impact.levels().len(), 2);
+ * assert_eq!(impact.len(), 3);
+ * assert_eq!(impact.weight(), 700);
+ * for (depth, level) in impact.levels().iter().enumerate() {
+ *     for &id in level {
+ *         println!("{depth}: {}", graph.pkgname(id));
+ *     }
+ * }
+ * # Ok::<(), std::io::Error>(())
+ * ```
+ *
+ * [`BuildGraph`]: super::BuildGraph
+ */
+#[derive(Clone, Debug, Default)]
+pub struct ImpactGraph(PackageGraph<u32>);
+
+impl ImpactGraph {
+    /**
+     * Create an empty graph.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Construct a graph from resolved scan records, in the same way as
+     * [`DependGraph::from_scan`], weighting each package by its
+     * `PBULK_WEIGHT`.
+     *
+     * Scan output does not record dependency types, so every edge has no
+     * type and is followed by [`ImpactGraph::impact_filtered`] regardless
+     * of the types requested.
+     *
+     * [`DependGraph::from_scan`]: super::DependGraph::from_scan
+     */
+    #[must_use]
+    pub fn from_scan(packages: &[ScanIndex]) -> Self {
+        Self(PackageGraph::from_scan(packages, |p| {
+            p.pbulk_weight.unwrap_or(DEFAULT_WEIGHT)
+        }))
+    }
+
+    /**
+     * Construct a graph from a binary package repository, in the same way
+     * as [`DependGraph::from_summaries`].  All packages are given
+     * [`DEFAULT_WEIGHT`].
+     *
+     * Every edge is [`DependType::Full`], so
+     * [`ImpactGraph::impact_filtered`] either follows all edges or none.
+     *
+     * # Errors
+     *
+     * Returns [`PatternError`] if a `DEPENDS` pattern is invalid, or if a
+     * matching package version cannot be parsed.
+     *
+     * [`DependGraph::from_summaries`]: super::DependGraph::from_summaries
+     */
+    pub fn from_summaries(summaries: &[Summary]) -> Result<Self, PatternError> {
+        PackageGraph::from_summaries(summaries, |_| DEFAULT_WEIGHT).map(Self)
+    }
+
+    /**
+     * Construct a graph from an installed package database, in the same
+     * way as [`DependGraph::from_pkgdb`].  `@pkgdep` entries are
+     * [`DependType::Full`] edges and `@blddep` entries are
+     * [`DependType::Build`] edges.  All packages are given
+     * [`DEFAULT_WEIGHT`].
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the package database cannot be read, or
+     * with [`io::ErrorKind::InvalidData`] if a `+CONTENTS` file or a
+     * dependency pattern is invalid.
+     *
+     * [`DependGraph::from_pkgdb`]: super::DependGraph::from_pkgdb
+     */
+    pub fn from_pkgdb(pkgdb: PkgDB) -> io::Result<Self> {
+        PackageGraph::from_pkgdb(pkgdb, |_| DEFAULT_WEIGHT).map(Self)
+    }
+
+    /**
+     * Add a package, returning its id.  If a package with the same
+     * `PKGNAME` already exists its id is returned unchanged.
+     */
+    pub fn add_package(
+        &mut self,
+        pkgname: PkgName,
+        pkgpath: Option<&str>,
+        weight: u32,
+    ) -> usize {
+        self.0.add_package(pkgname, pkgpath, weight)
+    }
+
+    /**
+     * Record that package `pkg` depends on package `depend`.
+     *
+     * # Panics
+     *
+     * Panics if either id is out of range.
+     */
+    pub fn add_depend(&mut self, pkg: usize, depend: usize, kind: DependType) {
+        self.0.add_depend(pkg, depend, Some(kind));
+    }
+
+    /**
+     * Return the number of packages in the graph.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /**
+     * Return true if the graph contains no packages.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
+
+    /**
+     * Return the id of a package by its `PKGNAME`.
+     */
+    #[must_use]
+    pub fn id(&self, pkgname: &str) -> Option<usize> {
+        self.0.id(pkgname)
+    }
+
+    /**
+     * Return the ids of every package matching `target`, which may be
+     * either a `PKGNAME` or a `PKGPATH`.  A `PKGPATH` may match more than
+     * one package when `MULTI_VERSION` is in use.
+     */
+    #[must_use]
+    pub fn find(&self, target: &str) -> Vec<usize> {
+        self.0.find(target)
+    }
+
+    /**
+     * Return the `PKGNAME` of a package.
+     *
+     * # Panics
+     *
+     * Panics if `id` is out of range.  The same applies to
+     * [`ImpactGraph::pkgpath`] and [`ImpactGraph::weight`].
+     */
+    #[must_use]
+    pub fn pkgname(&self, id: usize) -> &PkgName {
+        &self.0.node(id).pkgname
+    }
+
+    /**
+     * Return the `PKGPATH` of a package, if known.
+     */
+    #[must_use]
+    pub fn pkgpath(&self, id: usize) -> Option<&str> {
+        self.0.node(id).pkgpath.as_deref()
+    }
+
+    /**
+     * Return the weight of a package.
+     */
+    #[must_use]
+    pub fn weight(&self, id: usize) -> u32 {
+        self.0.node(id).data
+    }
+
+    /**
+     * Return the transitive reverse dependencies of every package matching
+     * the given `PKGNAME`s or `PKGPATH`s, following all dependency types.
+     */
+    #[must_use]
+    pub fn impact<I, S>(&self, targets: I) -> Impact
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.walk(targets, |_| true)
+    }
+
+    /**
+     * Identical to [`ImpactGraph::impact`] except that only edges whose
+     * [`DependType`] is in `types` are followed.  Edges with no known type
+     * are always followed, as they may be of any type.
+     */
+    #[must_use]
+    pub fn impact_filtered<I, S>(
+        &self,
+        targets: I,
+        types: &[DependType],
+    ) -> Impact
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.walk(targets, |t| t.is_none_or(|t| types.contains(&t)))
+    }
+
+    fn walk<I, S, F>(&self, targets: I, follow: F) -> Impact
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+        F: Fn(Option<DependType>) -> bool,
+    {
+        let mut seen = vec![false; self.len()];
+        let mut unknown = Vec::new();
+        let mut level = Vec::new();
+        for target in targets {
+            let ids = self.find(target.as_ref());
+            if ids.is_empty() {
+                unknown.push(target.as_ref().to_string());
+            }
+            for id in ids {
+                if !seen[id] {
+                    seen[id] = true;
+                    level.push(id);
+                }
+            }
+        }
+
+        /*
+         * Breadth-first, so each package is placed at the shortest distance
+         * from any target.
+         */
+        let mut levels = Vec::new();
+        while !level.is_empty() {
+            let mut next = Vec::new();
+            for &id in &level {
+                for &(r, kind) in self.0.rdepends(id) {
+                    if !seen[r] && follow(kind) {
+                        seen[r] = true;
+                        next.push(r);
+                    }
+                }
+            }
+            levels.push(level);
+            level = next;
+        }
+
+        let weight = levels
+            .iter()
+            .flatten()
+            .map(|&id| u64::from(self.weight(id)))
+            .sum();
+        Impact {
+            levels,
+            weight,
+            unknown,
+        }
+    }
+}
+
+/**
+ * The result of an [`ImpactGraph::impact`] query.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Impact {
+    levels: Vec<Vec<usize>>,
+    weight: u64,
+    unknown: Vec<String>,
+}
+
+impl Impact {
+    /**
+     * Return the affected package ids grouped by depth.  Level 0 contains
+     * the target packages themselves, level 1 their direct dependents, and
+     * so on.
+     */
+    #[must_use]
+    pub fn levels(&self) -> &[Vec<usize>] {
+        &self.levels
+    }
+
+    /**
+     * Return the target package ids.
+     */
+    #[must_use]
+    pub fn targets(&self) -> &[usize] {
+        self.levels.first().map_or(&[], Vec::as_slice)
+    }
+
+    /**
+     * Return an iterator over the ids of the dependents of the targets,
+     * excluding the targets themselves, in depth order.
+     */
+    pub fn dependents(&self) -> impl Iterator<Item = usize> + '_ {
+        self.levels.iter().skip(1).flatten().copied()
+    }
+
+    /**
+     * Return the total number of affected packages, including the targets.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.levels.iter().map(Vec::len).sum()
+    }
+
+    /**
+     * Return true if no targets were found.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /**
+     * Return the summed weight of every affected package, including the
+     * targets.
+     */
+    #[must_use]
+    pub fn weight(&self) -> u64 {
+        self.weight
+    }
+
+    /**
+     * Return any targets that did not match a `PKGNAME` or `PKGPATH`.
+     */
+    #[must_use]
+    pub fn unknown(&self) -> &[String] {
+        &self.unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> ImpactGraph {
+        /*
+         * a <- b (full) <- c (build) <- d (full), and a <- d (build).  The
+         * c <-> e cycle must not loop forever.
+         */
+        let mut g = ImpactGraph::new();
+        let ids: Vec<usize> = ["a-1", "b-1", "c-1", "d-1", "e-1"]
+            .iter()
+            .map(|n| g.add_package(PkgName::new(*n), Some("cat/x"), 10))
+            .collect();
+        g.add_depend(ids[1], ids[0], DependType::Full);
+        g.add_depend(ids[2], ids[1], DependType::Build);
+        g.add_depend(ids[3], ids[2], DependType::Full);
+        g.add_depend(ids[3], ids[0], DependType::Build);
+        g.add_depend(ids[4], ids[2], DependType::Full);
+        g.add_depend(ids[2], ids[4], DependType::Full);
+        g
+    }
+
+    #[test]
+    fn levels() {
+        let g = graph();
+        let impact = g.impact(["a-1", "nonexistent"]);
+        assert_eq!(impact.levels(), [vec![0], vec![1, 3], vec![2], vec![4]]);
+        assert_eq!(impact.weight(), 50);
+        assert_eq!(impact.dependents().count(), 4);
+        assert_eq!(impact.unknown(), ["nonexistent"]);
+        assert_eq!(g.find("cat/x").len(), 5);
+    }
+
+    #[test]
+    fn filtered() {
+        let g = graph();
+        let impact = g.impact_filtered(["a-1"], &[DependType::Full]);
+        assert_eq!(impact.levels(), [vec![0], vec![1]]);
+        let impact = g.impact_filtered(["b-1"], &[]);
+        assert_eq!(impact.len(), 1);
+    }
+
+    #[test]
+    fn filtered_sources() -> io::Result<()> {
+        /* Untyped scan edges are followed whatever the filter. */
+        let scan = "PKGNAME=a-1\nPKGNAME=b-1\nDEPENDS=a-1\n";
+        let pkgs = ScanIndex::from_reader(scan.as_bytes())
+            .collect::<Result<Vec<_>, _>>()?;
+        let g = ImpactGraph::from_scan(&pkgs);
+        assert_eq!(g.impact_filtered(["a-1"], &[DependType::Full]).len(), 2);
+        assert_eq!(g.impact_filtered(["a-1"], &[]).len(), 2);
+
+        let summaries = [
+            crate::summary::test_entry("a-1", &[]),
+            crate::summary::test_entry("b-1", &["DEPENDS=a>=1"]),
+        ];
+        let g = ImpactGraph::from_summaries(&summaries)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        assert_eq!(g.impact_filtered(["a-1"], &[DependType::Full]).len(), 2);
+        assert_eq!(g.impact_filtered(["a-1"], &[DependType::Build]).len(), 1);
+
+        let path =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/pkgdb-graph");
+        let g = ImpactGraph::from_pkgdb(PkgDB::open(path)?)?;
+        let full =
+            g.impact_filtered(["cwrappers-20150314"], &[DependType::Full]);
+        assert_eq!(full.len(), 1);
+        let build =
+            g.impact_filtered(["cwrappers-20150314"], &[DependType::Build]);
+        assert_eq!(build.len(), 2);
+        let full = g.impact_filtered(["gettext-lib-0.22"], &[DependType::Full]);
+        assert_eq!(full.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*
+ * The package and dependency store shared by ImpactGraph and DependGraph.
+ */
+
+use crate::metadata::FileRead;
+use crate::plist::{self, PlistEntry};
+use crate::summary::Summary;
+use crate::{DependType, PatternError, PatternSet, PkgDB, PkgName, ScanIndex};
+use hashbrown::HashMap;
+use std::io;
+
+#[derive(Clone, Debug)]
+pub(super) struct Node<T> {
+    pub(super) pkgname: PkgName,
+    pub(super) pkgpath: Option<String>,
+    pub(super) data: T,
+}
+
+/*
+ * Packages indexed by PKGNAME and PKGPATH, with dependency edges stored in
+ * both directions.  Cycles are permitted.  An edge type of None means the
+ * source did not record one.  Each view stores its own per-package data in
+ * T.
+ */
+#[derive(Clone, Debug)]
+pub(super) struct PackageGraph<T> {
+    nodes: Vec<Node<T>>,
+    by_name: HashMap<String, usize>,
+    by_path: HashMap<String, Vec<usize>>,
+    deps: Vec<Vec<(usize, Option<DependType>)>>,
+    rdeps: Vec<Vec<(usize, Option<DependType>)>>,
+}
+
+impl<T> Default for PackageGraph<T> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            by_name: HashMap::new(),
+            by_path: HashMap::new(),
+            deps: Vec::new(),
+            rdeps: Vec::new(),
+        }
+    }
+}
+
+impl<T> PackageGraph<T> {
+    /*
+     * See DependGraph::from_scan, DependGraph::from_summaries and
+     * DependGraph::from_pkgdb for the public descriptions of these
+     * constructors.
+     */
+    pub(super) fn from_scan(
+        packages: &[ScanIndex],
+        data: impl Fn(&ScanIndex) -> T,
+    ) -> Self {
+        let mut graph = Self::default();
+        let ids: Vec<usize> = packages
+            .iter()
+            .map(|p| {
+                graph.add_package(
+                    p.pkgname.clone(),
+                    p.pkg_location.as_ref().map(|l| l.as_str()),
+                    data(p),
+                )
+            })
+            .collect();
+        for (pkg, &id) in packages.iter().zip(&ids) {
+            for dep in pkg.depends() {
+                if let Some(dep_id) = graph.id(dep.pkgname()) {
+                    graph.add_depend(id, dep_id, None);
+                }
+            }
+        }
+        graph
+    }
+
+    pub(super) fn from_summaries(
+        summaries: &[Summary],
+        data: impl Fn(&Summary) -> T,
+    ) -> Result<Self, PatternError> {
+        let mut graph = Self::default();
+        let mut set = PatternSet::new();
+        let mut patterns: Vec<(usize, usize)> = Vec::new();
+        for pkg in summaries {
+            let id = graph.add_package(
+                pkg.pkgname().clone(),
+                Some(pkg.pkgpath()),
+                data(pkg),
+            );
+            for dep in pkg.depends().unwrap_or_default() {
+                patterns.push((id, set.insert(dep)?));
+            }
+        }
+        let best =
+            set.best_matches(summaries.iter().map(|p| p.pkgname().pkgname()))?;
+        for (id, pattern) in patterns {
+            if let Some(dep_id) = best[pattern].and_then(|m| graph.id(m)) {
+                graph.add_depend(id, dep_id, Some(DependType::Full));
+            }
+        }
+        Ok(graph)
+    }
+
+    pub(super) fn from_pkgdb(
+        pkgdb: PkgDB,
+        data: impl Fn(&Summary) -> T,
+    ) -> io::Result<Self> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        let mut summaries = Vec::new();
+        let mut blddeps = Vec::new();
+        for pkg in pkgdb {
+            let pkg = pkg?;
+            let mut deps = Vec::new();
+            for entry in plist::parse(pkg.contents()?.as_bytes()) {
+                let entry =
+                    entry.map_err(|e| invalid(format!("+CONTENTS: {e}")))?;
+                if let PlistEntry::BldDep(s) = entry {
+                    deps.push(s.into_owned());
+                }
+            }
+            let summary = pkg.to_summary()?;
+            blddeps.push((summary.pkgname().pkgname().to_string(), deps));
+            summaries.push(summary);
+        }
+        let mut graph = Self::from_summaries(&summaries, data)
+            .map_err(|e| invalid(e.to_string()))?;
+        for (pkgname, deps) in &blddeps {
+            let Some(id) = graph.id(pkgname) else {
+                continue;
+            };
+            for dep in deps {
+                if let Some(dep_id) = graph.id(dep) {
+                    graph.add_depend(id, dep_id, Some(DependType::Build));
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    pub(super) fn add_package(
+        &mut self,
+        pkgname: PkgName,
+        pkgpath: Option<&str>,
+        data: T,
+    ) -> usize {
+        if let Some(id) = self.id(pkgname.pkgname()) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.by_name.insert(pkgname.pkgname().to_string(), id);
+        if let Some(path) = pkgpath {
+            self.by_path.entry_ref(path).or_default().push(id);
+        }
+        self.nodes.push(Node {
+            pkgname,
+            pkgpath: pkgpath.map(str::to_string),
+            data,
+        });
+        self.deps.push(Vec::new());
+        self.rdeps.push(Vec::new());
+        id
+    }
+
+    /*
+     * Adding the same edge with the same type more than once has no effect.
+     */
+    pub(super) fn add_depend(
+        &mut self,
+        pkg: usize,
+        depend: usize,
+        kind: Option<DependType>,
+    ) {
+        assert!(
+            pkg < self.nodes.len() && depend < self.nodes.len(),
+            "invalid package id"
+        );
+        if !self.deps[pkg].contains(&(depend, kind)) {
+            self.deps[pkg].push((depend, kind));
+            self.rdeps[depend].push((pkg, kind));
+        }
+    }
+
+    /*
+     * Remove the dependency at index i of the dependencies of pkg.
+     */
+    pub(super) fn remove_depend(&mut self, pkg: usize, i: usize) {
+        let edge = self.deps[pkg].remove(i);
+        let rdeps = &mut self.rdeps[edge.0];
+        if let Some(j) = rdeps.iter().position(|&r| r == (pkg, edge.1)) {
+            rdeps.remove(j);
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub(super) fn id(&self, pkgname: &str) -> Option<usize> {
+        self.by_name.get(pkgname).copied()
+    }
+
+    /*
+     * Return the ids of every package matching target, which may be either
+     * a PKGNAME or a PKGPATH.
+     */
+    pub(super) fn find(&self, target: &str) -> Vec<usize> {
+        if let Some(id) = self.id(target) {
+            return vec![id];
+        }
+        self.by_path.get(target).cloned().unwrap_or_default()
+    }
+
+    pub(super) fn nodes(&self) -> &[Node<T>] {
+        &self.nodes
+    }
+
+    pub(super) fn node(&self, id: usize) -> &Node<T> {
+        &self.nodes[id]
+    }
+
+    pub(super) fn depends(&self, id: usize) -> &[(usize, Option<DependType>)] {
+        &self.deps[id]
+    }
+
+    pub(super) fn rdepends(&self, id: usize) -> &[(usize, Option<DependType>)] {
+        &self.rdeps[id]
+    }
+
+    pub(super) fn edge_count(&self) -> usize {
+        self.deps.iter().map(Vec::len).sum()
+    }
+}
@@ -299,6 +299,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn derive_lenient_warning() {
+        assert_eq!(LenientPackage::LENIENT_VARIABLES, ["WEIGHT"]);
+        let span = Span { offset: 7, len: 3 };
+        let w = LenientPackage::lenient_warning("WEIGHT", "bad", span);
+        assert_eq!(
+            w,
+            Some(KvWarning {
+                variable: "WEIGHT".to_string(),
+                value: "bad".to_string(),
+                span,
+            })
+        );
+        assert_eq!(LenientPackage::lenient_warning("WEIGHT", "5", span), None);
+        assert_eq!(LenientPackage::lenient_warning("PKGNAME", "x", span), None);
+    }
+
     #[test]
     fn derive_parse_discards_warnings() -> Result<()> {
         /*
@@ -435,6 +452,24 @@ mod tests {
         Ok(())
     }
 
+    #[derive(Kv, Debug, PartialEq)]
+    #[kv(allow_unknown)]
+    struct WithSkip {
+        pkgname: String,
+        #[kv(skip)]
+        cache: Option<Vec<String>>,
+    }
+
+    #[test]
+    fn derive_skip() -> Result<()> {
+        /* A key matching the field name must not populate it. */
+        let input = "PKGNAME=mktool-1.4.2\nCACHE=ignored\n";
+        let pkg = WithSkip::parse(input)?;
+        assert_eq!(pkg.pkgname, "mktool-1.4.2");
+        assert_eq!(pkg.cache, None);
+        Ok(())
+    }
+
     /*
      * Exercises the generated serde impls across every field kind: a
      * required value, a present and an absent `Option`, a single-line `Vec`,
@@ -33,19 +33,29 @@
  * | Module | Purpose |
  * |--------|---------|
  * | [`archive`] | Read and create binary package archives |
+ * | [`buildlink3`] | Parse `buildlink3.mk` dependency files |
  * | [`depend`] | Parse and match package dependencies |
  * | [`dewey`] | Dewey decimal version comparisons |
  * | [`digest`] | Cryptographic hash functions for file verification |
  * | [`distinfo`] | Parse and verify distinfo files |
+ * | [`graph`] | Schedule package builds from resolved scan output |
  * | [`kv`] | Parse KEY=VALUE formatted data |
+ * | [`license`] | Parse and evaluate `LICENSE` expressions |
+ * | [`makefile`] | Evaluate variables in pkgsrc Makefiles without bmake |
  * | [`metadata`] | Read package metadata from `+*` files |
  * | [`pattern`] | Match packages against glob, dewey, and alternate patterns |
  * | [`pkgdb`] | Access the installed package database |
  * | [`pkgname`] | Parse package names into name and version components |
  * | [`pkgpath`] | Parse pkgsrc package paths (category/package) |
+ * | [`plan`] | Plan binary package install and upgrade transactions |
  * | [`plist`] | Parse packing list (PLIST) files |
+ * | [`report`] | Generate pbulk-compatible bulk build reports |
  * | [`scanindex`] | Parse pbulk-index scan output |
+ * | `sqlite` | Export package data to a SQLite database (`sqlite` feature) |
  * | [`summary`] | Parse [`pkg_summary(5)`] files |
+ * | [`tree`] | Enumerate the packages in a pkgsrc tree |
+ * | [`version`] | Package versions with pkg_install ordering |
+ * | [`vulnerabilities`] | Parse `pkg-vulnerabilities` and audit packages |
  *
  * ## Examples
  *
@@ -107,6 +117,8 @@
  *
  * - `serde`: Enable serialization and deserialization support via
  *   [serde](https://serde.rs/) for various types.
+ * - `sqlite`: Enable the `sqlite` module for exporting `pkg_summary` and
+ *   pkgdb data to a SQLite database.
  *
  * [`pkg_summary(5)`]: https://man.netbsd.org/pkg_summary.5
  */
@@ -116,19 +128,30 @@
 extern crate self as pkgsrc;
 
 pub mod archive;
+pub mod buildlink3;
 pub mod depend;
 pub mod dewey;
 pub mod digest;
 pub mod distinfo;
+pub mod graph;
 pub mod kv;
+pub mod license;
+pub mod makefile;
 pub mod metadata;
 pub mod pattern;
 pub mod pkgdb;
 pub mod pkgname;
 pub mod pkgpath;
+pub mod plan;
 pub mod plist;
+pub mod report;
 pub mod scanindex;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod summary;
+pub mod tree;
+pub mod version;
+pub mod vulnerabilities;
 
 pub use crate::archive::{Archive, ArchiveError};
 pub use crate::depend::{Depend, DependError, DependType};
@@ -137,13 +160,17 @@ pub use crate::digest::Digest;
 pub use crate::distinfo::Distinfo;
 pub use crate::kv::KvError;
 pub use crate::metadata::{Metadata, MetadataError};
-pub use crate::pattern::{BestMatch, Pattern, PatternCache, PatternError};
+pub use crate::pattern::{
+    BestMatch, Decidable, Pattern, PatternCache, PatternError, PatternSet,
+};
 pub use crate::pkgdb::{DBType, PkgDB};
 pub use crate::pkgname::PkgName;
 pub use crate::pkgpath::{PkgPath, PkgPathError};
 pub use crate::plist::Plist;
 pub use crate::scanindex::{
-    AllDepends, BootstrapPkg, MakeJobsSafe, Presolve, Pscan, RawDepend, Report,
-    ScanDepends, ScanIndex, ScanIndexIter,
+    AllDepends, BootstrapPkg, MakeJobsSafe, PbulkIndex, Presolve, Pscan,
+    RawDepend, Report, ScanDepends, ScanIndex, ScanIndexIter, ScanIndexRef,
+    ScanIndexRefIter,
 };
 pub use crate::summary::{Summary, SummaryError};
+pub use crate::version::Version;
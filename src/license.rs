@@ -0,0 +1,642 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Parse `LICENSE` expressions and evaluate them against
+ * `ACCEPTABLE_LICENSES`.
+ *
+ * A package `LICENSE` is a boolean expression of license names combined with
+ * `AND`, `OR`, and parentheses, for example `gnu-gpl-v2 OR mit`.  A package
+ * may be built or installed if the expression evaluates to true when every
+ * license in `ACCEPTABLE_LICENSES` is true and every other license is false.
+ *
+ * Two parsers are provided:
+ *
+ * - [`License::new`] (also used by [`FromStr`]) is lenient and gives `AND`
+ *   a higher precedence than `OR`, so `a OR b AND c` is `a OR (b AND c)`.
+ * - [`License::new_strict`] follows the rules enforced by `pkg_install`,
+ *   which reject mixing `AND` and `OR` without parentheses.
+ *
+ * Errors carry a [`Span`] locating the problem within the input.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::license::{AcceptableLicenses, License};
+ *
+ * let license = License::new("gnu-gpl-v2 OR (mit AND apache-2.0)")?;
+ * let acceptable: AcceptableLicenses = "mit apache-2.0".parse().unwrap();
+ * assert!(acceptable.accepts(&license));
+ *
+ * let acceptable: AcceptableLicenses = "mit".parse().unwrap();
+ * assert!(!acceptable.accepts(&license));
+ * assert_eq!(acceptable.missing(&license), vec!["apache-2.0"]);
+ * # Ok::<(), pkgsrc::license::LicenseError>(())
+ * ```
+ */
+
+use crate::kv::Span;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/**
+ * Errors that can occur when parsing a `LICENSE` expression.
+ */
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[non_exhaustive]
+pub enum LicenseError {
+    /**
+     * The expression is empty.
+     */
+    #[error("empty license expression")]
+    Empty,
+    /**
+     * A character that is not valid in a license name.
+     */
+    #[error("invalid character {ch:?} at offset {}", span.offset)]
+    InvalidChar {
+        /** The invalid character. */
+        ch: char,
+        /** Location of the character. */
+        span: Span,
+    },
+    /**
+     * A token appeared where it is not permitted, for example two license
+     * names without an operator between them.
+     */
+    #[error("unexpected {token:?} at offset {}", span.offset)]
+    UnexpectedToken {
+        /** The unexpected token. */
+        token: String,
+        /** Location of the token. */
+        span: Span,
+    },
+    /**
+     * The expression ended where a license name or `(` was expected.
+     */
+    #[error("unexpected end of expression at offset {}", span.offset)]
+    UnexpectedEnd {
+        /** Location of the end of input. */
+        span: Span,
+    },
+    /**
+     * A `(` without a matching `)`.
+     */
+    #[error("unclosed parenthesis at offset {}", span.offset)]
+    UnclosedParen {
+        /** Location of the opening parenthesis. */
+        span: Span,
+    },
+    /**
+     * `AND` and `OR` were mixed without parentheses.  Only returned by
+     * [`License::new_strict`].
+     */
+    #[error("mixed AND and OR without parentheses at offset {}", span.offset)]
+    MixedOperators {
+        /** Location of the offending operator. */
+        span: Span,
+    },
+}
+
+/**
+ * A parsed `LICENSE` expression.
+ */
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum License {
+    /**
+     * A single license name, for example `mit`.
+     */
+    Name(String),
+    /**
+     * All of the contained expressions must be acceptable.
+     */
+    And(Vec<License>),
+    /**
+     * Any of the contained expressions must be acceptable.
+     */
+    Or(Vec<License>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Op {
+    And,
+    Or,
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+    Open,
+    Close,
+    Op(Op),
+    Name(&'a str),
+}
+
+fn is_license_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '+')
+}
+
+fn tokenize(s: &str) -> Result<Vec<(Token<'_>, Span)>, LicenseError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let span = Span {
+            offset: start,
+            len: c.len_utf8(),
+        };
+        match c {
+            c if c.is_ascii_whitespace() => {}
+            '(' => tokens.push((Token::Open, span)),
+            ')' => tokens.push((Token::Close, span)),
+            c if is_license_char(c) => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !is_license_char(c) {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                let word = &s[start..end];
+                let token = match word {
+                    "AND" => Token::Op(Op::And),
+                    "OR" => Token::Op(Op::Or),
+                    _ => Token::Name(word),
+                };
+                tokens.push((
+                    token,
+                    Span {
+                        offset: start,
+                        len: end - start,
+                    },
+                ));
+            }
+            ch => return Err(LicenseError::InvalidChar { ch, span }),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(Token<'a>, Span)>,
+    pos: usize,
+    strict: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn end_span(&self) -> Span {
+        Span {
+            offset: self.input.len(),
+            len: 0,
+        }
+    }
+
+    fn unexpected(&self, span: Span) -> LicenseError {
+        LicenseError::UnexpectedToken {
+            token: self.input[std::ops::Range::from(span)].to_string(),
+            span,
+        }
+    }
+
+    fn peek_op(&self) -> Option<(Op, Span)> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Op(op), span)) => Some((*op, *span)),
+            _ => None,
+        }
+    }
+
+    fn primary(&mut self) -> Result<License, LicenseError> {
+        let Some((token, span)) = self.tokens.get(self.pos) else {
+            return Err(LicenseError::UnexpectedEnd {
+                span: self.end_span(),
+            });
+        };
+        let span = *span;
+        match token {
+            Token::Name(name) => {
+                self.pos += 1;
+                Ok(License::Name(name.to_string()))
+            }
+            Token::Open => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                match self.tokens.get(self.pos) {
+                    Some((Token::Close, _)) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    Some((_, s)) => Err(self.unexpected(*s)),
+                    None => Err(LicenseError::UnclosedParen { span }),
+                }
+            }
+            Token::Close | Token::Op(_) => Err(self.unexpected(span)),
+        }
+    }
+
+    /*
+     * Lenient mode parses OR of ANDs.  Strict mode accepts a single operator
+     * per parenthesised group.
+     */
+    fn expr(&mut self) -> Result<License, LicenseError> {
+        if self.strict {
+            let first = self.primary()?;
+            let Some((op, _)) = self.peek_op() else {
+                return Ok(first);
+            };
+            let mut terms = vec![first];
+            while let Some((next, span)) = self.peek_op() {
+                if next != op {
+                    return Err(LicenseError::MixedOperators { span });
+                }
+                self.pos += 1;
+                terms.push(self.primary()?);
+            }
+            Ok(make(op, terms))
+        } else {
+            let mut terms = vec![self.and_expr()?];
+            while let Some((Op::Or, _)) = self.peek_op() {
+                self.pos += 1;
+                terms.push(self.and_expr()?);
+            }
+            Ok(make(Op::Or, terms))
+        }
+    }
+
+    fn and_expr(&mut self) -> Result<License, LicenseError> {
+        let mut terms = vec![self.primary()?];
+        while let Some((Op::And, _)) = self.peek_op() {
+            self.pos += 1;
+            terms.push(self.primary()?);
+        }
+        Ok(make(Op::And, terms))
+    }
+}
+
+fn make(op: Op, mut terms: Vec<License>) -> License {
+    if terms.len() == 1 {
+        return terms.remove(0);
+    }
+    match op {
+        Op::And => License::And(terms),
+        Op::Or => License::Or(terms),
+    }
+}
+
+impl License {
+    /**
+     * Parse a `LICENSE` expression, giving `AND` a higher precedence than
+     * `OR`.
+     *
+     * ```
+     * use pkgsrc::license::License;
+     *
+     * let license = License::new("a OR b AND c")?;
+     * assert_eq!(license.to_string(), "a OR (b AND c)");
+     * # Ok::<(), pkgsrc::license::LicenseError>(())
+     * ```
+     */
+    pub fn new(s: &str) -> Result<Self, LicenseError> {
+        Self::parse(s, false)
+    }
+
+    /**
+     * Parse a `LICENSE` expression using the same rules as `pkg_install`,
+     * where `AND` and `OR` may not be mixed without parentheses.
+     *
+     * ```
+     * use pkgsrc::license::{License, LicenseError};
+     *
+     * assert!(License::new_strict("a OR (b AND c)").is_ok());
+     * assert!(matches!(
+     *     License::new_strict("a OR b AND c"),
+     *     Err(LicenseError::MixedOperators { .. })
+     * ));
+     * ```
+     */
+    pub fn new_strict(s: &str) -> Result<Self, LicenseError> {
+        Self::parse(s, true)
+    }
+
+    fn parse(s: &str, strict: bool) -> Result<Self, LicenseError> {
+        let tokens = tokenize(s)?;
+        if tokens.is_empty() {
+            return Err(LicenseError::Empty);
+        }
+        let mut parser = Parser {
+            input: s,
+            tokens,
+            pos: 0,
+            strict,
+        };
+        let expr = parser.expr()?;
+        if let Some((_, span)) = parser.tokens.get(parser.pos) {
+            return Err(parser.unexpected(*span));
+        }
+        Ok(expr)
+    }
+
+    /**
+     * Return every license name referenced by this expression, sorted and
+     * without duplicates.
+     */
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = BTreeSet::new();
+        self.collect_names(&mut names);
+        names.into_iter().collect()
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut BTreeSet<&'a str>) {
+        match self {
+            License::Name(n) => {
+                names.insert(n);
+            }
+            License::And(v) | License::Or(v) => {
+                for l in v {
+                    l.collect_names(names);
+                }
+            }
+        }
+    }
+
+    /**
+     * Evaluate this expression, treating each license name as true if
+     * `accept` returns true for it.
+     */
+    pub fn eval<F>(&self, accept: &F) -> bool
+    where
+        F: Fn(&str) -> bool,
+    {
+        match self {
+            License::Name(n) => accept(n),
+            License::And(v) => v.iter().all(|l| l.eval(accept)),
+            License::Or(v) => v.iter().any(|l| l.eval(accept)),
+        }
+    }
+
+    /*
+     * Return every minimal set of unacceptable licenses that would need to
+     * be added for this expression to evaluate to true.  Supersets of other
+     * candidates are pruned as they are built so that the result stays
+     * small for realistic expressions.
+     */
+    fn missing_sets<'a, F>(&'a self, accept: &F) -> Vec<BTreeSet<&'a str>>
+    where
+        F: Fn(&str) -> bool,
+    {
+        match self {
+            License::Name(n) if accept(n) => vec![BTreeSet::new()],
+            License::Name(n) => vec![BTreeSet::from([n.as_str()])],
+            License::Or(v) => {
+                prune(v.iter().flat_map(|l| l.missing_sets(accept)).collect())
+            }
+            License::And(v) => {
+                let mut acc = vec![BTreeSet::new()];
+                for l in v {
+                    let sets = l.missing_sets(accept);
+                    let mut next = Vec::new();
+                    for a in &acc {
+                        for s in &sets {
+                            next.push(a.union(s).copied().collect());
+                        }
+                    }
+                    acc = prune(next);
+                }
+                acc
+            }
+        }
+    }
+}
+
+fn prune(mut sets: Vec<BTreeSet<&str>>) -> Vec<BTreeSet<&str>> {
+    sets.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    let mut out: Vec<BTreeSet<&str>> = Vec::new();
+    for s in sets {
+        if !out.iter().any(|o| o.is_subset(&s)) {
+            out.push(s);
+        }
+    }
+    out
+}
+
+impl FromStr for License {
+    type Err = LicenseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        License::new(s)
+    }
+}
+
+impl fmt::Display for License {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (v, op) = match self {
+            License::Name(n) => return write!(f, "{n}"),
+            License::And(v) => (v, " AND "),
+            License::Or(v) => (v, " OR "),
+        };
+        for (i, l) in v.iter().enumerate() {
+            if i > 0 {
+                f.write_str(op)?;
+            }
+            match l {
+                License::Name(_) => write!(f, "{l}")?,
+                _ => write!(f, "({l})")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/**
+ * A set of acceptable licenses, as configured by `ACCEPTABLE_LICENSES`.
+ *
+ * [`FromStr`] parses a whitespace-separated list as found in `mk.conf`.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AcceptableLicenses {
+    licenses: HashSet<String>,
+}
+
+impl AcceptableLicenses {
+    /**
+     * Create an empty set.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Add a license to the set.  Returns whether it was newly inserted.
+     */
+    pub fn insert(&mut self, license: impl Into<String>) -> bool {
+        self.licenses.insert(license.into())
+    }
+
+    /**
+     * Return whether `license` is in the set.
+     */
+    #[must_use]
+    pub fn contains(&self, license: &str) -> bool {
+        self.licenses.contains(license)
+    }
+
+    /**
+     * Return whether `license` evaluates to true against this set.
+     */
+    #[must_use]
+    pub fn accepts(&self, license: &License) -> bool {
+        license.eval(&|n| self.contains(n))
+    }
+
+    /**
+     * Return the smallest set of licenses that would need to be added for
+     * `license` to be accepted, sorted by name.  The result is empty if
+     * `license` is already accepted.  Where several sets of the same size
+     * would suffice, the lexicographically first is returned.
+     */
+    #[must_use]
+    pub fn missing<'a>(&self, license: &'a License) -> Vec<&'a str> {
+        license
+            .missing_sets(&|n| self.contains(n))
+            .into_iter()
+            .next()
+            .map(|s| s.into_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for AcceptableLicenses {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.split_whitespace().collect())
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for AcceptableLicenses {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        AcceptableLicenses {
+            licenses: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<S: Into<String>> Extend<S> for AcceptableLicenses {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        self.licenses.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(s: &str) -> License {
+        License::Name(s.to_string())
+    }
+
+    #[test]
+    fn test_parse() -> Result<(), LicenseError> {
+        assert_eq!(License::new("mit")?, name("mit"));
+        assert_eq!(
+            License::new("gnu-gpl-v2 OR mit AND (apache-2.0)")?,
+            License::Or(vec![
+                name("gnu-gpl-v2"),
+                License::And(vec![name("mit"), name("apache-2.0")]),
+            ])
+        );
+        assert_eq!(
+            License::new("(a OR b) AND c")?,
+            License::And(vec![
+                License::Or(vec![name("a"), name("b")]),
+                name("c")
+            ])
+        );
+        assert_eq!(
+            License::new_strict("a AND b AND c")?,
+            License::And(vec![name("a"), name("b"), name("c")])
+        );
+        let l = License::new("(gnu-gpl-v2 OR mit) AND  2-clause-bsd")?;
+        assert_eq!(l.to_string(), "(gnu-gpl-v2 OR mit) AND 2-clause-bsd");
+        assert_eq!(l.names(), vec!["2-clause-bsd", "gnu-gpl-v2", "mit"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let span = |offset, len| Span { offset, len };
+        assert_eq!(License::new("  "), Err(LicenseError::Empty));
+        assert_eq!(
+            License::new("mit, isc"),
+            Err(LicenseError::InvalidChar {
+                ch: ',',
+                span: span(3, 1)
+            })
+        );
+        assert_eq!(
+            License::new("mit isc"),
+            Err(LicenseError::UnexpectedToken {
+                token: "isc".to_string(),
+                span: span(4, 3)
+            })
+        );
+        assert_eq!(
+            License::new("mit OR"),
+            Err(LicenseError::UnexpectedEnd { span: span(6, 0) })
+        );
+        assert_eq!(
+            License::new("a AND (b OR c"),
+            Err(LicenseError::UnclosedParen { span: span(6, 1) })
+        );
+        assert_eq!(
+            License::new("a)"),
+            Err(LicenseError::UnexpectedToken {
+                token: ")".to_string(),
+                span: span(1, 1)
+            })
+        );
+        assert_eq!(
+            License::new_strict("a OR b AND c"),
+            Err(LicenseError::MixedOperators { span: span(7, 3) })
+        );
+    }
+
+    #[test]
+    fn test_missing() -> Result<(), LicenseError> {
+        let acceptable: AcceptableLicenses =
+            ["mit", "isc"].into_iter().collect();
+
+        let l = License::new("mit OR gnu-gpl-v3")?;
+        assert!(acceptable.accepts(&l));
+        assert!(acceptable.missing(&l).is_empty());
+
+        let l = License::new("gnu-gpl-v2 AND (mit OR apache-2.0)")?;
+        assert!(!acceptable.accepts(&l));
+        assert_eq!(acceptable.missing(&l), vec!["gnu-gpl-v2"]);
+
+        /* A shared license satisfies both terms. */
+        let l = License::new("(a OR b) AND (b OR c)")?;
+        assert_eq!(acceptable.missing(&l), vec!["b"]);
+
+        /* Prefer the smaller alternative. */
+        let l = License::new("(x AND y) OR z")?;
+        assert_eq!(acceptable.missing(&l), vec!["z"]);
+        Ok(())
+    }
+}
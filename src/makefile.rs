@@ -0,0 +1,1276 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Read-only evaluation of pkgsrc Makefiles.
+ *
+ * Variables such as `PKGNAME`, `MASTER_SITES` or `DEPENDS` normally
+ * require running `bmake` in a bootstrapped pkgsrc tree.  This module
+ * instead parses the subset of bmake syntax used by package `Makefile`s,
+ * `*.mk` fragments and `buildlink3.mk` files:
+ *
+ * - The `=`, `+=`, `?=` and `:=` assignment operators.
+ * - `.include`, `.sinclude` and `.-include`, relative to the including
+ *   file or, for `<file>`, to the configured include directories.
+ * - `.if`, `.ifdef`, `.ifndef`, `.elif`, `.else` and `.endif`, with
+ *   `defined()`, `empty()`, `exists()`, comparisons, `!`, `&&` and `||`.
+ * - `.for` loops, `.undef`, and `.error`.
+ * - The `:M`, `:N`, `:S`, `:U`, `:D`, `:tl`, `:tu`, `:ts`, `:T`, `:H`,
+ *   `:E`, `:R`, `:O`, `:u`, `:Q`, `:[n]`, `:@var@...@` and `:from=to`
+ *   modifiers.
+ *
+ * Anything else, such as `!=` shell assignments, the `:C` and `:sh`
+ * modifiers, or `make()` conditions, is reported as
+ * [`MakefileError::Unsupported`] with the file and line where it was found
+ * rather than being guessed at.  Constructs in conditional branches that
+ * are not taken are never evaluated, and `=` assignments are only expanded
+ * when their value is requested, so unsupported syntax is only an error if
+ * it would affect the result.
+ *
+ * Variables that a real build would inherit from the pkgsrc infrastructure
+ * are supplied by the caller with [`Evaluator::with_var`], and includes
+ * that would pull in the infrastructure itself can be skipped with
+ * [`Evaluator::with_skip_include`].  Every assignment records a [`Span`],
+ * so the origin of each value can be reported.
+ *
+ * Rules and their shell commands are skipped.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::makefile::Evaluator;
+ *
+ * let mk = "\
+ * DISTNAME=\tfoo-1.0
+ * PKGNAME=\t${DISTNAME:S/foo/py-foo/}
+ * .if ${OPSYS} == \"NetBSD\"
+ * CONFIGURE_ARGS+=\t--enable-kqueue
+ * .endif
+ * ";
+ * let mf = Evaluator::new()
+ *     .with_var("OPSYS", "NetBSD")
+ *     .evaluate_str(mk, "Makefile")?;
+ * assert_eq!(mf.get("PKGNAME")?.as_deref(), Some("py-foo-1.0"));
+ * assert_eq!(mf.get("CONFIGURE_ARGS")?.as_deref(), Some("--enable-kqueue"));
+ * assert_eq!(mf.origins("CONFIGURE_ARGS")[0].line, 4);
+ * # Ok::<(), pkgsrc::makefile::MakefileError>(())
+ * ```
+ */
+
+mod cond;
+mod expand;
+
+use expand::{ExpandError, Expander};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+/*
+ * Maximum .include nesting, to catch files that include themselves.
+ */
+const MAX_INCLUDE_DEPTH: usize = 64;
+
+/*
+ * Directives recognised after a leading '.'.  Anything else starting with
+ * a '.' is a special target or variable such as .PHONY or .CURDIR.
+ */
+const DIRECTIVES: &[&str] = &[
+    "if",
+    "ifdef",
+    "ifndef",
+    "ifmake",
+    "ifnmake",
+    "elif",
+    "elifdef",
+    "elifndef",
+    "elifmake",
+    "elifnmake",
+    "else",
+    "endif",
+    "for",
+    "endfor",
+    "include",
+    "sinclude",
+    "-include",
+    "dinclude",
+    "undef",
+    "error",
+    "warning",
+    "info",
+    "export",
+    "export-env",
+    "export-literal",
+    "unexport",
+    "unexport-env",
+    "break",
+];
+
+/**
+ * A location in a Makefile.
+ */
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Span {
+    /** The file, as it was opened. */
+    pub file: Arc<Path>,
+    /** The line number, starting from 1. */
+    pub line: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+/*
+ * Format an optional span as an error message prefix.
+ */
+fn at(span: Option<&Span>) -> String {
+    span.map_or_else(String::new, |s| format!("{s}: "))
+}
+
+/**
+ * Errors that can occur when evaluating a Makefile.
+ */
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum MakefileError {
+    /**
+     * A file could not be read.
+     */
+    #[error("{}: {source}", path.display())]
+    Io {
+        /** The file that could not be read. */
+        path: PathBuf,
+        /** The underlying error. */
+        #[source]
+        source: io::Error,
+    },
+    /**
+     * A construct that this parser does not implement.
+     */
+    #[error("{}unsupported {construct}", at(span.as_ref()))]
+    Unsupported {
+        /** Where it was found, if known. */
+        span: Option<Span>,
+        /** A description of the construct. */
+        construct: String,
+    },
+    /**
+     * Invalid syntax.
+     */
+    #[error("{}{message}", at(span.as_ref()))]
+    Syntax {
+        /** Where it was found, if known. */
+        span: Option<Span>,
+        /** A description of the problem. */
+        message: String,
+    },
+    /**
+     * An undefined variable was referenced in strict mode.
+     */
+    #[error("{}undefined variable {name}", at(span.as_ref()))]
+    Undefined {
+        /** Where it was referenced, if known. */
+        span: Option<Span>,
+        /** The variable name. */
+        name: String,
+    },
+    /**
+     * An `.error` directive was reached.
+     */
+    #[error("{span}: .error: {message}")]
+    Error {
+        /** The location of the directive. */
+        span: Span,
+        /** The expanded message. */
+        message: String,
+    },
+}
+
+impl MakefileError {
+    fn from_expand(e: ExpandError, span: Option<&Span>) -> Self {
+        let span = span.cloned();
+        match e {
+            ExpandError::Unsupported(construct) => {
+                Self::Unsupported { span, construct }
+            }
+            ExpandError::Syntax(message) => Self::Syntax { span, message },
+            ExpandError::Undefined(name) => Self::Undefined { span, name },
+        }
+    }
+
+    fn unsupported(span: &Span, construct: impl Into<String>) -> Self {
+        Self::Unsupported {
+            span: Some(span.clone()),
+            construct: construct.into(),
+        }
+    }
+
+    fn syntax(span: &Span, message: impl Into<String>) -> Self {
+        Self::Syntax {
+            span: Some(span.clone()),
+            message: message.into(),
+        }
+    }
+
+    /**
+     * Return where the error occurred, if known.
+     */
+    #[must_use]
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Self::Io { .. } => None,
+            Self::Unsupported { span, .. }
+            | Self::Syntax { span, .. }
+            | Self::Undefined { span, .. } => span.as_ref(),
+            Self::Error { span, .. } => Some(span),
+        }
+    }
+}
+
+/**
+ * A variable and the assignments that produced its value.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Variable {
+    value: String,
+    origins: Vec<Span>,
+}
+
+impl Variable {
+    /**
+     * Return the value before expansion.
+     */
+    #[must_use]
+    pub fn raw(&self) -> &str {
+        &self.value
+    }
+
+    /**
+     * Return the location of each assignment contributing to the value, in
+     * order.  An `=` or `:=` assignment replaces any earlier origins, while
+     * `+=` adds to them.  This is empty for a caller-supplied default that
+     * was never reassigned.
+     */
+    #[must_use]
+    pub fn origins(&self) -> &[Span] {
+        &self.origins
+    }
+}
+
+/**
+ * The assignment operators.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Op {
+    Set,
+    Append,
+    Default,
+    Immediate,
+    Shell,
+}
+
+/*
+ * A logical line, with continuations joined.
+ */
+#[derive(Clone, Debug)]
+struct Line {
+    text: String,
+    span: Span,
+}
+
+fn split_lines(contents: &str, file: &Arc<Path>) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut text = String::new();
+    let mut start: Option<usize> = None;
+    for (n, raw) in contents.lines().enumerate() {
+        /*
+         * A backslash-newline and the whitespace around it become a
+         * single space.
+         */
+        let raw = if start.is_some() {
+            raw.trim_start()
+        } else {
+            raw
+        };
+        let line = *start.get_or_insert(n + 1);
+        let trailing = raw.len() - raw.trim_end_matches('\\').len();
+        if trailing % 2 == 1 {
+            text.push_str(raw[..raw.len() - 1].trim_end());
+            text.push(' ');
+            continue;
+        }
+        text.push_str(raw);
+        start = None;
+        lines.push(Line {
+            text: std::mem::take(&mut text),
+            span: Span {
+                file: Arc::clone(file),
+                line,
+            },
+        });
+    }
+    if let Some(line) = start {
+        lines.push(Line {
+            text,
+            span: Span {
+                file: Arc::clone(file),
+                line,
+            },
+        });
+    }
+    lines
+}
+
+/*
+ * Remove a trailing comment, honouring "\#" escapes.  A '#' inside a
+ * variable expression such as ${VAR:[#]} does not start a comment.
+ */
+fn strip_comment(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut depth = 0usize;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'#') => {
+                out.push('#');
+                chars.next();
+                continue;
+            }
+            '#' if depth == 0 => break,
+            '{' | '(' if out.ends_with('$') => depth += 1,
+            '}' | ')' if depth > 0 => depth -= 1,
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+/*
+ * If line is a directive return its name and arguments.
+ */
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_start().strip_prefix('.')?.trim_start();
+    let len = rest
+        .find(|c: char| !(c.is_ascii_alphabetic() || c == '-'))
+        .unwrap_or(rest.len());
+    let name = &rest[..len];
+    DIRECTIVES
+        .contains(&name)
+        .then(|| (name, rest[len..].trim()))
+}
+
+enum Statement<'a> {
+    Assign {
+        name: &'a str,
+        op: Op,
+        value: &'a str,
+    },
+    Rule,
+}
+
+/*
+ * Classify a non-directive line as an assignment or a dependency rule by
+ * whichever of '=' or ':' appears first outside a variable expression.
+ */
+fn statement(line: &str) -> Option<Statement<'_>> {
+    let bytes = line.as_bytes();
+    let mut depth = 0usize;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'{' | b'(' => depth += 1,
+            b'}' | b')' => depth = depth.saturating_sub(1),
+            b'=' if depth == 0 => {
+                let (op, start) = match i.checked_sub(1).map(|p| bytes[p]) {
+                    Some(b'+') => (Op::Append, i - 1),
+                    Some(b'?') => (Op::Default, i - 1),
+                    Some(b'!') => (Op::Shell, i - 1),
+                    _ => (Op::Set, i),
+                };
+                return Some(Statement::Assign {
+                    name: line[..start].trim(),
+                    op,
+                    value: line[i + 1..].trim(),
+                });
+            }
+            b':' if depth == 0 => {
+                if bytes.get(i + 1) == Some(&b'=') {
+                    return Some(Statement::Assign {
+                        name: line[..i].trim(),
+                        op: Op::Immediate,
+                        value: line[i + 2..].trim(),
+                    });
+                }
+                return Some(Statement::Rule);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/*
+ * Conditional state for one .if ... .endif block.
+ */
+struct CondFrame {
+    parent_active: bool,
+    taken: bool,
+    active: bool,
+    else_seen: bool,
+    span: Span,
+}
+
+/**
+ * Evaluate Makefiles with a fixed configuration.
+ *
+ * See the [module documentation](self) for an example.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Evaluator {
+    defaults: Vec<(String, String)>,
+    include_dirs: Vec<PathBuf>,
+    skip: Vec<PathBuf>,
+    skip_all: bool,
+    strict: bool,
+}
+
+impl Evaluator {
+    /**
+     * Create a new evaluator with no default variables.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Set a variable before evaluation begins, as the pkgsrc
+     * infrastructure would.  The Makefile may override it, and `?=` will
+     * not.
+     */
+    #[must_use]
+    pub fn with_var(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.defaults.push((name.into(), value.into()));
+        self
+    }
+
+    /**
+     * Add a directory to search for `.include <file>`.
+     */
+    #[must_use]
+    pub fn with_include_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+
+    /**
+     * Skip any included file whose path ends with `path`, for example
+     * `mk/bsd.pkg.mk`.  Skipped files are listed by
+     * [`Makefile::skipped`].
+     */
+    #[must_use]
+    pub fn with_skip_include(mut self, path: impl Into<PathBuf>) -> Self {
+        self.skip.push(path.into());
+        self
+    }
+
+    /**
+     * Whether to read included files, the default.  If disabled, every
+     * `.include` is resolved but only recorded in [`Makefile::skipped`],
+     * which is useful to examine a single file such as a `buildlink3.mk`
+     * in isolation.
+     */
+    #[must_use]
+    pub fn with_follow_includes(mut self, follow: bool) -> Self {
+        self.skip_all = !follow;
+        self
+    }
+
+    /**
+     * Treat references to undefined variables as errors, other than in
+     * `defined()` and `empty()` conditions or with the `:U` modifier.  By
+     * default, as with bmake, they expand to an empty string.
+     */
+    #[must_use]
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /**
+     * Read and evaluate a Makefile.
+     *
+     * # Errors
+     *
+     * Returns [`MakefileError`] if a file cannot be read, or if the
+     * evaluation reaches invalid or unsupported syntax.
+     */
+    pub fn evaluate(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Makefile, MakefileError> {
+        let path = path.as_ref();
+        let contents =
+            fs::read_to_string(path).map_err(|source| MakefileError::Io {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        self.evaluate_str(&contents, path)
+    }
+
+    /**
+     * Evaluate Makefile contents as if read from `path`, which is used for
+     * spans and to resolve relative includes.
+     *
+     * # Errors
+     *
+     * Returns [`MakefileError`] if an included file cannot be read, or if
+     * the evaluation reaches invalid or unsupported syntax.
+     */
+    pub fn evaluate_str(
+        &self,
+        contents: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<Makefile, MakefileError> {
+        let path = path.as_ref();
+        let curdir = parent_dir(path);
+        let mut vars = BTreeMap::new();
+        for (name, value) in &self.defaults {
+            vars.insert(
+                name.clone(),
+                Variable {
+                    value: value.clone(),
+                    origins: Vec::new(),
+                },
+            );
+        }
+        vars.insert(
+            ".CURDIR".to_string(),
+            Variable {
+                value: curdir.display().to_string(),
+                origins: Vec::new(),
+            },
+        );
+        let mut state = State {
+            config: self,
+            vars,
+            curdir,
+            includes: vec![path.to_path_buf()],
+            skipped: Vec::new(),
+            cond: Vec::new(),
+            depth: 0,
+        };
+        state.file(contents, path)?;
+        Ok(Makefile {
+            vars: state.vars,
+            includes: state.includes,
+            skipped: state.skipped,
+            strict: self.strict,
+        })
+    }
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+struct State<'a> {
+    config: &'a Evaluator,
+    vars: BTreeMap<String, Variable>,
+    curdir: PathBuf,
+    includes: Vec<PathBuf>,
+    skipped: Vec<PathBuf>,
+    cond: Vec<CondFrame>,
+    depth: usize,
+}
+
+impl State<'_> {
+    fn active(&self) -> bool {
+        self.cond.last().is_none_or(|f| f.active)
+    }
+
+    fn expand(
+        &self,
+        text: &str,
+        keep_dollars: bool,
+        span: &Span,
+    ) -> Result<String, MakefileError> {
+        Expander::new(&self.vars, self.config.strict)
+            .expand(text, keep_dollars)
+            .map_err(|e| MakefileError::from_expand(e, Some(span)))
+    }
+
+    fn set_builtin(&mut self, name: &str, value: String) -> Option<Variable> {
+        self.vars.insert(
+            name.to_string(),
+            Variable {
+                value,
+                origins: Vec::new(),
+            },
+        )
+    }
+
+    fn restore_builtin(&mut self, name: &str, old: Option<Variable>) {
+        match old {
+            Some(v) => self.vars.insert(name.to_string(), v),
+            None => self.vars.remove(name),
+        };
+    }
+
+    fn file(
+        &mut self,
+        contents: &str,
+        path: &Path,
+    ) -> Result<(), MakefileError> {
+        let file: Arc<Path> = Arc::from(path);
+        let lines = split_lines(contents, &file);
+        let parsedir = parent_dir(path).display().to_string();
+        let parsefile = path
+            .file_name()
+            .map_or_else(String::new, |f| f.to_string_lossy().into_owned());
+        let old_dir = self.set_builtin(".PARSEDIR", parsedir);
+        let old_file = self.set_builtin(".PARSEFILE", parsefile);
+
+        let depth = self.cond.len();
+        let result = self.lines(&lines);
+        self.restore_builtin(".PARSEDIR", old_dir);
+        self.restore_builtin(".PARSEFILE", old_file);
+        result?;
+        if self.cond.len() > depth {
+            let frame = self.cond.pop().expect("conditional frame");
+            self.cond.truncate(depth);
+            return Err(MakefileError::syntax(&frame.span, "missing .endif"));
+        }
+        Ok(())
+    }
+
+    fn lines(&mut self, lines: &[Line]) -> Result<(), MakefileError> {
+        let mut in_rule = false;
+        let mut i = 0;
+        while i < lines.len() {
+            let line = &lines[i];
+            i += 1;
+            if in_rule && line.text.starts_with('\t') {
+                continue;
+            }
+            let text = strip_comment(&line.text);
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            in_rule = false;
+            if let Some((name, args)) = directive(text) {
+                if name == "for" && self.active() {
+                    let end = for_end(lines, i).ok_or_else(|| {
+                        MakefileError::syntax(&line.span, "missing .endfor")
+                    })?;
+                    self.for_loop(args, &lines[i..end], &line.span)?;
+                    i = end + 1;
+                } else {
+                    self.directive(name, args, &line.span)?;
+                }
+                continue;
+            }
+            if !self.active() {
+                continue;
+            }
+            match statement(text) {
+                Some(Statement::Assign { name, op, value }) => {
+                    self.assign(name, op, value, &line.span)?;
+                }
+                Some(Statement::Rule) => in_rule = true,
+                None => {
+                    return Err(MakefileError::syntax(
+                        &line.span,
+                        format!("unrecognised line \"{text}\""),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(
+        &mut self,
+        name: &str,
+        op: Op,
+        value: &str,
+        span: &Span,
+    ) -> Result<(), MakefileError> {
+        let name = self.expand(name, false, span)?;
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(MakefileError::syntax(
+                span,
+                format!("invalid variable name \"{name}\""),
+            ));
+        }
+        let value = match op {
+            Op::Shell => {
+                return Err(MakefileError::unsupported(
+                    span,
+                    format!("!= shell assignment to {name}"),
+                ));
+            }
+            Op::Default if self.vars.contains_key(&name) => return Ok(()),
+            Op::Immediate => self.expand(value, true, span)?,
+            _ => value.to_string(),
+        };
+        match (op, self.vars.get_mut(&name)) {
+            (Op::Append, Some(var)) => {
+                if !var.value.is_empty() && !value.is_empty() {
+                    var.value.push(' ');
+                }
+                var.value.push_str(&value);
+                var.origins.push(span.clone());
+            }
+            _ => {
+                self.vars.insert(
+                    name,
+                    Variable {
+                        value,
+                        origins: vec![span.clone()],
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn condition(
+        &self,
+        name: &str,
+        args: &str,
+        span: &Span,
+    ) -> Result<bool, MakefileError> {
+        let mut ex = Expander::new(&self.vars, self.config.strict);
+        let kind = name.trim_start_matches("el").trim_start_matches("if");
+        let result = match kind {
+            "" => cond::eval(args, &mut ex, &self.curdir),
+            "def" | "ndef" => {
+                let expr: Vec<String> = args
+                    .split_ascii_whitespace()
+                    .map(|word| match word {
+                        "&&" | "||" | "!" | "(" | ")" => word.to_string(),
+                        _ => format!("defined({word})"),
+                    })
+                    .collect();
+                cond::eval(&expr.join(" "), &mut ex, &self.curdir)
+                    .map(|b| b == (kind == "def"))
+            }
+            _ => {
+                return Err(MakefileError::unsupported(
+                    span,
+                    format!(".{name}"),
+                ));
+            }
+        };
+        result.map_err(|e| MakefileError::from_expand(e, Some(span)))
+    }
+
+    fn directive(
+        &mut self,
+        name: &str,
+        args: &str,
+        span: &Span,
+    ) -> Result<(), MakefileError> {
+        match name {
+            "if" | "ifdef" | "ifndef" | "ifmake" | "ifnmake" => {
+                let parent_active = self.active();
+                let value =
+                    parent_active && self.condition(name, args, span)?;
+                self.cond.push(CondFrame {
+                    parent_active,
+                    taken: value || !parent_active,
+                    active: value,
+                    else_seen: false,
+                    span: span.clone(),
+                });
+            }
+            "elif" | "elifdef" | "elifndef" | "elifmake" | "elifnmake" => {
+                let Some(frame) = self.cond.last() else {
+                    return Err(MakefileError::syntax(
+                        span,
+                        format!(".{name} without .if"),
+                    ));
+                };
+                if frame.else_seen {
+                    return Err(MakefileError::syntax(
+                        span,
+                        format!(".{name} after .else"),
+                    ));
+                }
+                let value = !frame.taken && self.condition(name, args, span)?;
+                let frame = self.cond.last_mut().expect("conditional frame");
+                frame.active = value;
+                frame.taken |= value;
+            }
+            "else" => {
+                let Some(frame) = self.cond.last_mut() else {
+                    return Err(MakefileError::syntax(
+                        span,
+                        ".else without .if",
+                    ));
+                };
+                if frame.else_seen {
+                    return Err(MakefileError::syntax(span, "duplicate .else"));
+                }
+                frame.active = frame.parent_active && !frame.taken;
+                frame.taken = true;
+                frame.else_seen = true;
+            }
+            "endif" => {
+                if self.cond.pop().is_none() {
+                    return Err(MakefileError::syntax(
+                        span,
+                        ".endif without .if",
+                    ));
+                }
+            }
+            _ if !self.active() => {}
+            "endfor" => {
+                return Err(MakefileError::syntax(
+                    span,
+                    ".endfor without .for",
+                ));
+            }
+            "include" | "sinclude" | "-include" | "dinclude" => {
+                self.include(args, name != "include", span)?;
+            }
+            "undef" => {
+                for var in self.expand(args, false, span)?.split_whitespace() {
+                    self.vars.remove(var);
+                }
+            }
+            "error" => {
+                return Err(MakefileError::Error {
+                    span: span.clone(),
+                    message: self.expand(args, false, span)?,
+                });
+            }
+            "warning" | "info" | "export" | "export-env" | "export-literal"
+            | "unexport" | "unexport-env" => {}
+            _ => {
+                return Err(MakefileError::unsupported(
+                    span,
+                    format!(".{name}"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn for_loop(
+        &mut self,
+        args: &str,
+        body: &[Line],
+        span: &Span,
+    ) -> Result<(), MakefileError> {
+        let words: Vec<&str> = args.split_ascii_whitespace().collect();
+        let Some(in_pos) = words.iter().position(|&w| w == "in") else {
+            return Err(MakefileError::syntax(span, "missing \"in\" in .for"));
+        };
+        let names = &words[..in_pos];
+        let list = args.split_once(" in ").map_or("", |(_, l)| l);
+        let list = self.expand(list, false, span)?;
+        let values: Vec<&str> = list.split_ascii_whitespace().collect();
+        if names.is_empty() || !values.len().is_multiple_of(names.len()) {
+            return Err(MakefileError::syntax(
+                span,
+                "wrong number of words in .for",
+            ));
+        }
+        for chunk in values.chunks(names.len()) {
+            let mut lines = body.to_vec();
+            for (name, value) in names.iter().zip(chunk) {
+                if value.contains([':', '}', ')', '$']) {
+                    return Err(MakefileError::unsupported(
+                        span,
+                        format!(".for value \"{value}\""),
+                    ));
+                }
+                for line in &mut lines {
+                    line.text = line
+                        .text
+                        .replace(&format!("${{{name}}}"), value)
+                        .replace(&format!("$({name})"), value)
+                        .replace(
+                            &format!("${{{name}:"),
+                            &format!("${{:U{value}:"),
+                        )
+                        .replace(
+                            &format!("$({name}:"),
+                            &format!("$(:U{value}:"),
+                        );
+                }
+            }
+            self.lines(&lines)?;
+        }
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        args: &str,
+        optional: bool,
+        span: &Span,
+    ) -> Result<(), MakefileError> {
+        let (target, system) = if let Some(rest) = args.strip_prefix('"') {
+            (rest.strip_suffix('"'), false)
+        } else if let Some(rest) = args.strip_prefix('<') {
+            (rest.strip_suffix('>'), true)
+        } else {
+            (None, false)
+        };
+        let Some(target) = target else {
+            return Err(MakefileError::syntax(
+                span,
+                format!("malformed .include {args}"),
+            ));
+        };
+        let target = PathBuf::from(self.expand(target, false, span)?);
+        let path = if target.is_absolute() {
+            Some(target)
+        } else if system {
+            self.config
+                .include_dirs
+                .iter()
+                .map(|d| d.join(&target))
+                .find(|p| p.exists())
+                .or_else(|| self.config.skip_all.then_some(target))
+        } else {
+            Some(parent_dir(&span.file).join(target))
+        };
+        let Some(path) = path else {
+            if optional {
+                return Ok(());
+            }
+            return Err(MakefileError::syntax(
+                span,
+                format!("cannot find include file {args}"),
+            ));
+        };
+        if self.config.skip_all
+            || self.config.skip.iter().any(|s| path.ends_with(s))
+        {
+            self.skipped.push(path);
+            return Ok(());
+        }
+        if self.depth >= MAX_INCLUDE_DEPTH {
+            return Err(MakefileError::syntax(
+                span,
+                "includes nested too deeply",
+            ));
+        }
+        let contents = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) if optional && e.kind() == io::ErrorKind::NotFound => {
+                return Ok(());
+            }
+            Err(source) => return Err(MakefileError::Io { path, source }),
+        };
+        self.includes.push(path.clone());
+        self.depth += 1;
+        let result = self.file(&contents, &path);
+        self.depth -= 1;
+        result
+    }
+}
+
+/*
+ * Find the .endfor matching a .for whose body starts at lines[start].
+ */
+fn for_end(lines: &[Line], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        match directive(&strip_comment(&line.text)).map(|(n, _)| n) {
+            Some("for") => depth += 1,
+            Some("endfor") if depth == 0 => return Some(i),
+            Some("endfor") => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/**
+ * The variables defined by an evaluated Makefile.
+ */
+#[derive(Clone, Debug)]
+pub struct Makefile {
+    vars: BTreeMap<String, Variable>,
+    includes: Vec<PathBuf>,
+    skipped: Vec<PathBuf>,
+    strict: bool,
+}
+
+impl Makefile {
+    /**
+     * Return the fully expanded value of a variable, or `None` if it is not
+     * defined.
+     *
+     * # Errors
+     *
+     * Returns [`MakefileError`] if the value uses invalid or unsupported
+     * syntax.  The span is that of the last assignment to the variable.
+     */
+    pub fn get(&self, name: &str) -> Result<Option<String>, MakefileError> {
+        let Some(var) = self.vars.get(name) else {
+            return Ok(None);
+        };
+        Expander::new(&self.vars, self.strict)
+            .expand(&var.value, false)
+            .map(Some)
+            .map_err(|e| MakefileError::from_expand(e, var.origins.last()))
+    }
+
+    /**
+     * Expand arbitrary text, such as `${PKGNAME:S/-[^-]*$//}`, using the
+     * variables defined by this Makefile.
+     *
+     * # Errors
+     *
+     * Returns [`MakefileError`] if the text uses invalid or unsupported
+     * syntax.
+     */
+    pub fn expand(&self, text: &str) -> Result<String, MakefileError> {
+        Expander::new(&self.vars, self.strict)
+            .expand(text, false)
+            .map_err(|e| MakefileError::from_expand(e, None))
+    }
+
+    /**
+     * Return a variable and its origins.
+     */
+    #[must_use]
+    pub fn variable(&self, name: &str) -> Option<&Variable> {
+        self.vars.get(name)
+    }
+
+    /**
+     * Return the locations of the assignments that produced a variable's
+     * value, or an empty slice if it is not set in any file.
+     */
+    #[must_use]
+    pub fn origins(&self, name: &str) -> &[Span] {
+        self.vars.get(name).map_or(&[], |v| v.origins())
+    }
+
+    /**
+     * Return an iterator over all defined variables, sorted by name.
+     */
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &Variable)> {
+        self.vars.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /**
+     * Return every file that was read, starting with the top-level
+     * Makefile, in the order they were included.
+     */
+    #[must_use]
+    pub fn includes(&self) -> &[PathBuf] {
+        &self.includes
+    }
+
+    /**
+     * Return the included files that were skipped by
+     * [`Evaluator::with_skip_include`] or
+     * [`Evaluator::with_follow_includes`].
+     */
+    #[must_use]
+    pub fn skipped(&self) -> &[PathBuf] {
+        &self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(mk: &str) -> Makefile {
+        Evaluator::new()
+            .with_var("OPSYS", "NetBSD")
+            .evaluate_str(mk, "Makefile")
+            .expect("valid Makefile")
+    }
+
+    fn get(mf: &Makefile, name: &str) -> String {
+        mf.get(name).expect("valid value").unwrap_or_default()
+    }
+
+    #[test]
+    fn assignments() {
+        let mf = eval(
+            "A=\t1\nB=\t${A}\nC:=\t${A}\nA=\t2\n\
+             D?=\tx\nD?=\ty\nE+=\ta \\\n\tb\nE+=\tc # comment\n\
+             F=\ta\\#b\nOPSYS?=\tLinux\n",
+        );
+        assert_eq!(get(&mf, "B"), "2");
+        assert_eq!(get(&mf, "C"), "1");
+        assert_eq!(get(&mf, "D"), "x");
+        assert_eq!(get(&mf, "E"), "a b c");
+        assert_eq!(get(&mf, "F"), "a#b");
+        assert_eq!(get(&mf, "OPSYS"), "NetBSD");
+        let lines: Vec<_> = mf.origins("E").iter().map(|s| s.line).collect();
+        assert_eq!(lines, [7, 9]);
+        assert!(mf.origins("OPSYS").is_empty());
+    }
+
+    #[test]
+    fn modifiers() {
+        let mf = eval(
+            "L=\tfoo.c bar.h baz.c foo.c\n\
+             P=\t/usr/pkg/lib/libfoo.so.1\n\
+             N=\tfoo-1.0\n\
+             E=\t${L:M*.c:O:u}\n\
+             S=\t${N:S/-/ /:[2]}\n\
+             G=\t${L:S,foo,qux,g:N*.h:[#]}\n\
+             A=\t${N:S/^foo/bar/}${N:S/0$/1/}\n\
+             H=\t${P:H} ${P:T} ${P:R:E} ${P:E}\n\
+             U=\t${UNDEF:Udefault:tu} ${N:Dset}\n\
+             V=\t${L:O:u:ts,} ${L:.c=.o} ${L:%.h=lib%.a}\n\
+             W=\t${L:@f@<${f:R}>@:[1]}\n\
+             Q=\t${:Ua b:Q}\n",
+        );
+        assert_eq!(get(&mf, "E"), "baz.c foo.c");
+        assert_eq!(get(&mf, "S"), "1.0");
+        assert_eq!(get(&mf, "G"), "3");
+        assert_eq!(get(&mf, "A"), "bar-1.0foo-1.1");
+        assert_eq!(get(&mf, "H"), "/usr/pkg/lib libfoo.so.1 so 1");
+        assert_eq!(get(&mf, "U"), "DEFAULT set");
+        assert_eq!(
+            get(&mf, "V"),
+            "bar.h,baz.c,foo.c foo.o bar.h baz.o foo.o foo.c libbar.a baz.c foo.c"
+        );
+        assert_eq!(get(&mf, "W"), "<foo>");
+        assert_eq!(get(&mf, "Q"), "a\\ b");
+    }
+
+    #[test]
+    fn conditionals_and_loops() {
+        let mf = eval(
+            ".if ${OPSYS} == \"NetBSD\" && !defined(NOPE)\n\
+             A=\tyes\n\
+             .elif ${OPSYS:C/x//}\n\
+             .else\n\
+             A=\tno\n\
+             .endif\n\
+             .  if empty(A:Mno) && (1 > 0 || ${UNDEF})\n\
+             B=\tyes\n\
+             .  endif\n\
+             .ifndef A\n\
+             .error unreachable\n\
+             .endif\n\
+             .for v n in a 1 b 2\n\
+             L+=\t${v:tu}${n}\n\
+             .endfor\n\
+             install:\n\
+             \tcp foo ${DESTDIR} # A=oops\n",
+        );
+        assert_eq!(get(&mf, "A"), "yes");
+        assert_eq!(get(&mf, "B"), "yes");
+        assert_eq!(get(&mf, "L"), "A1 B2");
+
+        /* Only decimal and hex numbers compare numerically. */
+        let mf = eval(
+            "X=\tnan\n\
+             .if ${X} == \"nan\" && !inf && 0x10 == 16 && -1.5 < .5\n\
+             A=\tyes\n\
+             .endif\n",
+        );
+        assert_eq!(get(&mf, "A"), "yes");
+    }
+
+    #[test]
+    fn short_circuit() {
+        let input = ".if defined(X) && ${X} == \"y\"\n\
+                     .error unreachable\n\
+                     .endif\n\
+                     .if !defined(X) || ${X:C/a/b/}\n\
+                     A=\tyes\n\
+                     .endif\n\
+                     .if (defined(X) && ${X}) || defined(A)\n\
+                     B=\tyes\n\
+                     .endif\n";
+        let mf = Evaluator::new()
+            .with_strict(true)
+            .evaluate_str(input, "Makefile")
+            .expect("skipped operands are not expanded");
+        assert_eq!(get(&mf, "A"), "yes");
+        assert_eq!(get(&mf, "B"), "yes");
+
+        /* Skipped operands must still parse. */
+        let err = Evaluator::new()
+            .evaluate_str(".if defined(X) && \"oops\n.endif\n", "Makefile")
+            .expect_err("unterminated string");
+        assert!(matches!(err, MakefileError::Syntax { .. }));
+    }
+
+    #[test]
+    fn unsupported() {
+        let err = Evaluator::new()
+            .evaluate_str("A=\tok\nB!=\techo hi\n", "Makefile")
+            .expect_err("shell assignment");
+        assert!(matches!(err, MakefileError::Unsupported { .. }));
+        assert_eq!(err.span().map(|s| s.line), Some(2));
+        assert_eq!(
+            err.to_string(),
+            "Makefile:2: unsupported != shell assignment to B"
+        );
+
+        /* Lazy values only fail when they are requested. */
+        let mf = eval("A=\t${B:C/a/b/}\nC=\tok\n");
+        assert_eq!(get(&mf, "C"), "ok");
+        let err = mf.get("A").expect_err("regex modifier");
+        assert_eq!(
+            err.to_string(),
+            "Makefile:1: unsupported modifier :C (regular expressions)"
+        );
+
+        let err = Evaluator::new()
+            .with_strict(true)
+            .evaluate_str(".if ${UNDEF} == 1\n.endif\n", "Makefile")
+            .expect_err("undefined");
+        assert!(matches!(err, MakefileError::Undefined { .. }));
+    }
+
+    #[test]
+    fn includes() {
+        let root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/pkgsrc");
+        let mf = Evaluator::new()
+            .with_skip_include("mk/bsd.pkg.mk")
+            .evaluate(Path::new(root).join("devel/bar/Makefile"))
+            .expect("valid Makefile");
+        assert_eq!(get(&mf, "PKGNAME"), "bar-1.0nb2");
+        assert_eq!(get(&mf, "BUILDLINK_API_DEPENDS.libfoo"), "libfoo>=1.0");
+        let origin = &mf.origins("BUILDLINK_API_DEPENDS.libfoo")[0];
+        assert!(origin.file.ends_with("devel/libfoo/buildlink3.mk"));
+        assert_eq!(mf.includes().len(), 2);
+        assert_eq!(mf.skipped().len(), 1);
+    }
+}
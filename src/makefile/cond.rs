@@ -0,0 +1,308 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*
+ * Evaluation of .if and .elif conditions.
+ */
+
+use super::expand::{ExpandError, Expander};
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, ExpandError>;
+
+/*
+ * A term on either side of a comparison.  Bare words are kept separate as
+ * on their own they mean defined(word).
+ */
+enum Term {
+    Word(String),
+    Value(String),
+}
+
+impl Term {
+    fn into_string(self) -> String {
+        match self {
+            Term::Word(s) | Term::Value(s) => s,
+        }
+    }
+}
+
+struct Cond<'a, 'b> {
+    s: &'a str,
+    pos: usize,
+    ex: &'a mut Expander<'b>,
+    curdir: &'a Path,
+}
+
+pub(super) fn eval(
+    expr: &str,
+    ex: &mut Expander<'_>,
+    curdir: &Path,
+) -> Result<bool> {
+    let mut c = Cond {
+        s: expr,
+        pos: 0,
+        ex,
+        curdir,
+    };
+    let value = c.or(true)?;
+    c.skip_ws();
+    if c.pos < c.s.len() {
+        return Err(c.syntax());
+    }
+    Ok(value)
+}
+
+/*
+ * Parse a number as bmake does: optionally signed decimal digits with an
+ * optional fraction, or 0x followed by hex digits.  Words such as "nan" and
+ * "inf" that str::parse would also accept are not numbers.
+ */
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix("0x") {
+        if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        return u64::from_str_radix(hex, 16).ok().map(|n| n as f64);
+    }
+    let unsigned = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if (int.is_empty() && frac.is_empty())
+        || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    s.parse().ok()
+}
+
+impl Cond<'_, '_> {
+    fn syntax(&self) -> ExpandError {
+        ExpandError::Syntax(format!("malformed conditional \"{}\"", self.s))
+    }
+
+    fn rest(&self) -> &str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /*
+     * As in bmake, the operands after the deciding operand of || and && are
+     * parsed, so that syntax errors are still reported, but with eval set
+     * to false so that nothing in them is expanded.  This allows idioms
+     * such as "defined(X) && ${X} == yes" in strict mode.
+     */
+    fn or(&mut self, eval: bool) -> Result<bool> {
+        let mut value = self.and(eval)?;
+        while self.eat("||") {
+            let rhs = self.and(eval && !value)?;
+            value = value || rhs;
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self, eval: bool) -> Result<bool> {
+        let mut value = self.unary(eval)?;
+        while self.eat("&&") {
+            let rhs = self.unary(eval && value)?;
+            value = value && rhs;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self, eval: bool) -> Result<bool> {
+        if self.eat("!") {
+            return Ok(!self.unary(eval)?);
+        }
+        if self.eat("(") {
+            let value = self.or(eval)?;
+            if !self.eat(")") {
+                return Err(self.syntax());
+            }
+            return Ok(value);
+        }
+        self.skip_ws();
+        let ident_len = self
+            .rest()
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.rest().len());
+        if ident_len > 0 && self.rest()[ident_len..].starts_with('(') {
+            let func = self.rest()[..ident_len].to_string();
+            self.pos += ident_len + 1;
+            return self.function(&func, eval);
+        }
+        self.comparison(eval)
+    }
+
+    fn function(&mut self, func: &str, eval: bool) -> Result<bool> {
+        let rest = self.rest();
+        let mut depth = 0;
+        let mut end = None;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '(' | '{' => depth += 1,
+                ')' if depth == 0 => {
+                    end = Some(i);
+                    break;
+                }
+                ')' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        let end = end.ok_or_else(|| self.syntax())?;
+        let arg = rest[..end].trim().to_string();
+        self.pos += end + 1;
+        if !eval {
+            return Ok(false);
+        }
+        match func {
+            "defined" => {
+                let name = self.ex.expand(&arg, false)?;
+                Ok(self.ex.is_defined(&name))
+            }
+            "empty" => {
+                let strict = self.ex.set_strict(false);
+                let value = self.ex.expand_expr(&arg);
+                self.ex.set_strict(strict);
+                Ok(value?.trim().is_empty())
+            }
+            "exists" => {
+                let path = self.ex.expand(&arg, false)?;
+                Ok(self.curdir.join(path).exists())
+            }
+            _ => Err(ExpandError::Unsupported(format!(
+                "{func}() in conditional"
+            ))),
+        }
+    }
+
+    fn term(&mut self, eval: bool) -> Result<Term> {
+        self.skip_ws();
+        let rest = self.rest();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut escaped = false;
+            let end = quoted
+                .char_indices()
+                .find(|&(_, c)| {
+                    let close = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    close
+                })
+                .map(|(i, _)| i)
+                .ok_or_else(|| self.syntax())?;
+            let text = quoted[..end].replace("\\\"", "\"");
+            self.pos += end + 2;
+            if !eval {
+                return Ok(Term::Value(text));
+            }
+            return Ok(Term::Value(self.ex.expand(&text, false)?));
+        }
+        if rest.starts_with("${") || rest.starts_with("$(") {
+            let mut depth = 0;
+            let mut end = None;
+            for (i, c) in rest.char_indices().skip(1) {
+                match c {
+                    '{' | '(' => depth += 1,
+                    '}' | ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(i);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let end = end.ok_or_else(|| self.syntax())?;
+            let inner = rest[2..end].to_string();
+            self.pos += end + 1;
+            if !eval {
+                return Ok(Term::Value(inner));
+            }
+            return Ok(Term::Value(self.ex.expand_expr(&inner)?));
+        }
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "!=<>()&|\"$".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.syntax());
+        }
+        let word = rest[..len].to_string();
+        self.pos += len;
+        Ok(Term::Word(word))
+    }
+
+    fn comparison(&mut self, eval: bool) -> Result<bool> {
+        let lhs = self.term(eval)?;
+        self.skip_ws();
+        let op = ["==", "!=", "<=", ">=", "<", ">"]
+            .into_iter()
+            .find(|op| self.rest().starts_with(op));
+        let Some(op) = op else {
+            if !eval {
+                return Ok(false);
+            }
+            return Ok(match lhs {
+                Term::Word(w) => match parse_number(&w) {
+                    Some(n) => n != 0.0,
+                    None => self.ex.is_defined(&w),
+                },
+                Term::Value(v) => match parse_number(&v) {
+                    Some(n) => n != 0.0,
+                    None => !v.trim().is_empty(),
+                },
+            });
+        };
+        self.pos += op.len();
+        let lhs = lhs.into_string();
+        let rhs = self.term(eval)?.into_string();
+        if !eval {
+            return Ok(false);
+        }
+        if let (Some(l), Some(r)) = (parse_number(&lhs), parse_number(&rhs)) {
+            return Ok(match op {
+                "==" => l == r,
+                "!=" => l != r,
+                "<=" => l <= r,
+                ">=" => l >= r,
+                "<" => l < r,
+                _ => l > r,
+            });
+        }
+        match op {
+            "==" => Ok(lhs == rhs),
+            "!=" => Ok(lhs != rhs),
+            _ => Err(ExpandError::Syntax(format!(
+                "non-numeric comparison \"{}\"",
+                self.s
+            ))),
+        }
+    }
+}
@@ -0,0 +1,638 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*
+ * Variable expansion and modifiers.
+ */
+
+use super::Variable;
+use std::collections::BTreeMap;
+
+/*
+ * Deep enough for any real nesting, shallow enough to catch a variable
+ * that refers to itself.
+ */
+const MAX_DEPTH: usize = 64;
+
+/*
+ * Characters that the :Q modifier escapes.
+ */
+const SHELL_META: &str = " \t\n\\\"'`$&|;<>()*?[]#~=%{}!";
+
+#[derive(Debug)]
+pub(super) enum ExpandError {
+    Unsupported(String),
+    Syntax(String),
+    Undefined(String),
+}
+
+type Result<T> = std::result::Result<T, ExpandError>;
+
+/*
+ * Return the index of the closing delimiter matching the opening one that
+ * precedes s, allowing for nested expressions.
+ */
+fn find_close(s: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/*
+ * Return the index of the first occurrence of any of stop that is not
+ * inside a nested expression or escaped with a backslash, or the length of
+ * s if there is none.
+ */
+fn find_unnested(s: &str, stop: &[char]) -> usize {
+    let mut depth = 0usize;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '{' | '(' => depth += 1,
+            '}' | ')' => depth = depth.saturating_sub(1),
+            c if depth == 0 && stop.contains(&c) => return i,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+fn unescape(s: &str, chars: &[char]) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut it = s.chars().peekable();
+    while let Some(c) = it.next() {
+        if c == '\\'
+            && let Some(&next) = it.peek()
+            && chars.contains(&next)
+        {
+            out.push(next);
+            it.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn words(s: &str) -> Vec<&str> {
+    s.split_ascii_whitespace().collect()
+}
+
+/*
+ * Shell-style pattern matching as used by :M and :N.
+ */
+pub(super) fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    glob_at(&p, &t)
+}
+
+fn glob_at(p: &[char], t: &[char]) -> bool {
+    match p.first() {
+        None => t.is_empty(),
+        Some('*') => (0..=t.len()).any(|i| glob_at(&p[1..], &t[i..])),
+        Some('?') => !t.is_empty() && glob_at(&p[1..], &t[1..]),
+        Some('[') => {
+            let Some(&c) = t.first() else {
+                return false;
+            };
+            let Some(end) = p.iter().skip(2).position(|&c| c == ']') else {
+                return c == '[' && glob_at(&p[1..], &t[1..]);
+            };
+            let class = &p[1..end + 2];
+            let (negate, class) = match class.first() {
+                Some('^' | '!') => (true, &class[1..]),
+                _ => (false, class),
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    matched |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    matched |= class[i] == c;
+                    i += 1;
+                }
+            }
+            matched != negate && glob_at(&p[end + 3..], &t[1..])
+        }
+        Some('\\') if p.len() > 1 => {
+            t.first() == Some(&p[1]) && glob_at(&p[2..], &t[1..])
+        }
+        Some(c) => t.first() == Some(c) && glob_at(&p[1..], &t[1..]),
+    }
+}
+
+pub(super) struct Expander<'a> {
+    vars: &'a BTreeMap<String, Variable>,
+    strict: bool,
+    locals: Vec<(String, String)>,
+    depth: usize,
+}
+
+impl<'a> Expander<'a> {
+    pub(super) fn new(
+        vars: &'a BTreeMap<String, Variable>,
+        strict: bool,
+    ) -> Self {
+        Self {
+            vars,
+            strict,
+            locals: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    pub(super) fn set_strict(&mut self, strict: bool) -> bool {
+        std::mem::replace(&mut self.strict, strict)
+    }
+
+    pub(super) fn is_defined(&self, name: &str) -> bool {
+        self.locals.iter().any(|(n, _)| n == name)
+            || self.vars.contains_key(name)
+    }
+
+    fn lookup(&self, name: &str) -> Option<String> {
+        if let Some((_, v)) = self.locals.iter().rev().find(|(n, _)| n == name)
+        {
+            return Some(v.clone());
+        }
+        self.vars.get(name).map(|v| v.raw().to_string())
+    }
+
+    /*
+     * Expand every variable expression in s.  With keep_dollars, "$$" is
+     * kept as is, as for the := operator.
+     */
+    pub(super) fn expand(
+        &mut self,
+        s: &str,
+        keep_dollars: bool,
+    ) -> Result<String> {
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(pos) = rest.find('$') {
+            out.push_str(&rest[..pos]);
+            let after = &rest[pos + 1..];
+            let mut chars = after.chars();
+            match chars.next() {
+                Some('$') => {
+                    out.push_str(if keep_dollars { "$$" } else { "$" });
+                    rest = &after[1..];
+                }
+                Some(open @ ('{' | '(')) => {
+                    let close = if open == '{' { '}' } else { ')' };
+                    let inner = &after[1..];
+                    let end =
+                        find_close(inner, open, close).ok_or_else(|| {
+                            ExpandError::Syntax(format!(
+                                "unclosed variable expression in \"{s}\""
+                            ))
+                        })?;
+                    out.push_str(&self.expand_expr(&inner[..end])?);
+                    rest = &inner[end + 1..];
+                }
+                Some(c) => {
+                    let mut buf = [0; 4];
+                    out.push_str(&self.expand_expr(c.encode_utf8(&mut buf))?);
+                    rest = &after[c.len_utf8()..];
+                }
+                None => {
+                    out.push('$');
+                    rest = "";
+                }
+            }
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /*
+     * Expand the contents of a single ${...} expression.
+     */
+    pub(super) fn expand_expr(&mut self, inner: &str) -> Result<String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(ExpandError::Syntax(format!(
+                "recursive variable expression ${{{inner}}}"
+            )));
+        }
+        self.depth += 1;
+        let result = self.expand_expr_inner(inner);
+        self.depth -= 1;
+        result
+    }
+
+    fn expand_expr_inner(&mut self, inner: &str) -> Result<String> {
+        let split = find_unnested(inner, &[':']);
+        let name = self.expand(&inner[..split], false)?;
+        let mut value = match self.lookup(&name) {
+            Some(raw) => Some(self.expand(&raw, false)?),
+            None => None,
+        };
+        if split < inner.len() {
+            self.modify(&name, &mut value, &inner[split + 1..])?;
+        }
+        match value {
+            Some(v) => Ok(v),
+            None if self.strict => Err(ExpandError::Undefined(name)),
+            None => Ok(String::new()),
+        }
+    }
+
+    /*
+     * Apply a chain of modifiers, the text after the first ':'.
+     */
+    fn modify(
+        &mut self,
+        name: &str,
+        value: &mut Option<String>,
+        mods: &str,
+    ) -> Result<()> {
+        let mut rest = mods;
+        loop {
+            let consumed = self.modifier(name, value, rest)?;
+            rest = &rest[consumed..];
+            match rest.strip_prefix(':') {
+                Some(next) => rest = next,
+                None if rest.is_empty() => return Ok(()),
+                None => {
+                    return Err(ExpandError::Syntax(format!(
+                        "bad modifier \":{mods}\" for {name}"
+                    )));
+                }
+            }
+        }
+    }
+
+    /*
+     * Apply a single modifier at the start of m, returning the number of
+     * bytes consumed.
+     */
+    fn modifier(
+        &mut self,
+        name: &str,
+        value: &mut Option<String>,
+        m: &str,
+    ) -> Result<usize> {
+        let arg_end = find_unnested(m, &[':']);
+        let unsupported = |what: &str| {
+            Err(ExpandError::Unsupported(format!("modifier :{what}")))
+        };
+        let cur = value.clone().unwrap_or_default();
+        let map_words = |f: &dyn Fn(&str) -> String| {
+            words(&cur)
+                .iter()
+                .map(|w| f(w))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let Some(c) = m.chars().next() else {
+            return Ok(0);
+        };
+        let simple = |len: usize| -> Result<()> {
+            if m.len() > len && !m[len..].starts_with(':') {
+                return Err(ExpandError::Unsupported(format!(
+                    "modifier :{}",
+                    &m[..arg_end]
+                )));
+            }
+            Ok(())
+        };
+        let new = match c {
+            'M' | 'N' => {
+                let pattern = unescape(&m[1..arg_end], &[':']);
+                let pattern = self.expand(&pattern, false)?;
+                let keep = c == 'M';
+                let out: Vec<&str> = words(&cur)
+                    .into_iter()
+                    .filter(|w| glob_match(&pattern, w) == keep)
+                    .collect();
+                set(value, out.join(" "));
+                return Ok(arg_end);
+            }
+            'U' => {
+                if value.is_none() {
+                    *value = Some(self.expand(&m[1..arg_end], false)?);
+                }
+                return Ok(arg_end);
+            }
+            'D' => {
+                let v = if value.is_some() {
+                    self.expand(&m[1..arg_end], false)?
+                } else {
+                    String::new()
+                };
+                *value = Some(v);
+                return Ok(arg_end);
+            }
+            'S' => return self.substitute(value, &cur, m),
+            'C' => return unsupported("C (regular expressions)"),
+            '@' => return self.loop_modifier(value, &cur, m),
+            '[' => {
+                let end = m.find(']').ok_or_else(|| {
+                    ExpandError::Syntax(format!("unclosed :[ in {name}"))
+                })?;
+                let sel = self.expand(&m[1..end], false)?;
+                let w = words(&cur);
+                let out = match sel.as_str() {
+                    "#" => w.len().to_string(),
+                    "*" | "@" | "1..-1" => cur.clone(),
+                    n => {
+                        let n: isize = n.parse().map_err(|_| {
+                            ExpandError::Unsupported(format!(
+                                "modifier :[{sel}]"
+                            ))
+                        })?;
+                        let idx = if n < 0 {
+                            w.len().checked_sub(n.unsigned_abs())
+                        } else {
+                            n.unsigned_abs().checked_sub(1)
+                        };
+                        idx.and_then(|i| w.get(i))
+                            .map(ToString::to_string)
+                            .unwrap_or_default()
+                    }
+                };
+                set(value, out);
+                return Ok(end + 1);
+            }
+            't' => {
+                let out = match m[1..].chars().next() {
+                    Some('l') => {
+                        simple(2)?;
+                        cur.to_lowercase()
+                    }
+                    Some('u') => {
+                        simple(2)?;
+                        cur.to_uppercase()
+                    }
+                    Some('s') => {
+                        let sep = unescape(&m[2..arg_end], &[':']);
+                        let sep = match sep.as_str() {
+                            "\\n" => "\n".to_string(),
+                            "\\t" => "\t".to_string(),
+                            _ => sep,
+                        };
+                        set(value, words(&cur).join(&sep));
+                        return Ok(arg_end);
+                    }
+                    _ => return unsupported(&m[..arg_end]),
+                };
+                set(value, out);
+                return Ok(2);
+            }
+            'T' => {
+                simple(1)?;
+                map_words(&|w| w.rsplit('/').next().unwrap_or(w).to_string())
+            }
+            'H' => {
+                simple(1)?;
+                map_words(&|w| match w.rfind('/') {
+                    Some(0) => "/".to_string(),
+                    Some(i) => w[..i].to_string(),
+                    None => ".".to_string(),
+                })
+            }
+            'E' => {
+                simple(1)?;
+                map_words(&|w| {
+                    let base = w.rsplit('/').next().unwrap_or(w);
+                    match base.rfind('.') {
+                        Some(i) => base[i + 1..].to_string(),
+                        None => String::new(),
+                    }
+                })
+            }
+            'R' => {
+                simple(1)?;
+                map_words(&|w| {
+                    let start = w.rfind('/').map_or(0, |i| i + 1);
+                    match w[start..].rfind('.') {
+                        Some(i) => w[..start + i].to_string(),
+                        None => w.to_string(),
+                    }
+                })
+            }
+            'O' => {
+                simple(1)?;
+                let mut w = words(&cur);
+                w.sort_unstable();
+                w.join(" ")
+            }
+            'u' => {
+                simple(1)?;
+                let mut w = words(&cur);
+                w.dedup();
+                w.join(" ")
+            }
+            'Q' => {
+                simple(1)?;
+                let mut out = String::with_capacity(cur.len());
+                for ch in cur.chars() {
+                    if SHELL_META.contains(ch) {
+                        out.push('\\');
+                    }
+                    out.push(ch);
+                }
+                out
+            }
+            _ => {
+                /*
+                 * System V style :from=to substitution, which extends to
+                 * the end of the expression.
+                 */
+                if let Some(eq) = m.find('=') {
+                    let from = self.expand(&m[..eq], false)?;
+                    let to = self.expand(&m[eq + 1..], false)?;
+                    set(value, sysv_substitute(&cur, &from, &to));
+                    return Ok(m.len());
+                }
+                return unsupported(&m[..arg_end]);
+            }
+        };
+        set(value, new);
+        Ok(m.find(':').unwrap_or(m.len()))
+    }
+
+    /*
+     * :S/old/new/[1g]
+     */
+    fn substitute(
+        &mut self,
+        value: &mut Option<String>,
+        cur: &str,
+        m: &str,
+    ) -> Result<usize> {
+        let bad = || ExpandError::Syntax(format!("bad modifier :{m}"));
+        let delim = m[1..].chars().next().ok_or_else(bad)?;
+        let body = &m[1 + delim.len_utf8()..];
+        let lhs_end = find_unnested(body, &[delim]);
+        if lhs_end == body.len() {
+            return Err(bad());
+        }
+        let after = &body[lhs_end + delim.len_utf8()..];
+        let rhs_end = find_unnested(after, &[delim]);
+        if rhs_end == after.len() {
+            return Err(bad());
+        }
+        let flags_start = rhs_end + delim.len_utf8();
+        let flags_len = after[flags_start..]
+            .find(|c: char| !matches!(c, 'g' | '1' | 'W'))
+            .unwrap_or(after.len() - flags_start);
+        let flags = &after[flags_start..flags_start + flags_len];
+        if flags.contains('W') {
+            return Err(ExpandError::Unsupported("modifier :S with W".into()));
+        }
+        let global = flags.contains('g');
+        let once = flags.contains('1');
+
+        let mut lhs = unescape(&body[..lhs_end], &[delim, '^', '$', '&']);
+        let anchor_start = body.starts_with('^');
+        if anchor_start {
+            lhs.remove(0);
+        }
+        let anchor_end =
+            body[..lhs_end].ends_with('$') && !body[..lhs_end].ends_with("\\$");
+        if anchor_end {
+            lhs.pop();
+        }
+        let lhs = self.expand(&lhs, false)?;
+        /*
+         * An unescaped '&' in the replacement is the matched text.
+         */
+        let rhs = after[..rhs_end].replace("\\&", "\u{1}").replace('&', &lhs);
+        let rhs = unescape(&rhs, &[delim]).replace('\u{1}', "&");
+        let rhs = self.expand(&rhs, false)?;
+
+        let mut done = false;
+        let mut out = Vec::new();
+        for word in words(cur) {
+            if once && done {
+                out.push(word.to_string());
+                continue;
+            }
+            let replaced = match (anchor_start, anchor_end) {
+                (true, true) => (word == lhs).then(|| "\u{0}".to_string()),
+                (true, false) => {
+                    word.strip_prefix(lhs.as_str()).map(|r| format!("\u{0}{r}"))
+                }
+                (false, true) => {
+                    word.strip_suffix(lhs.as_str()).map(|r| format!("{r}\u{0}"))
+                }
+                (false, false) if lhs.is_empty() => None,
+                (false, false) => {
+                    if global {
+                        word.contains(lhs.as_str())
+                            .then(|| word.replace(lhs.as_str(), "\u{0}"))
+                    } else {
+                        word.find(lhs.as_str()).map(|i| {
+                            format!(
+                                "{}\u{0}{}",
+                                &word[..i],
+                                &word[i + lhs.len()..]
+                            )
+                        })
+                    }
+                }
+            };
+            match replaced {
+                Some(marked) => {
+                    out.push(marked.replace('\u{0}', &rhs));
+                    done = true;
+                }
+                None => out.push(word.to_string()),
+            }
+        }
+        set(value, out.join(" "));
+        Ok(1 + delim.len_utf8()
+            + lhs_end
+            + delim.len_utf8()
+            + flags_start
+            + flags_len)
+    }
+
+    /*
+     * :@var@text@
+     */
+    fn loop_modifier(
+        &mut self,
+        value: &mut Option<String>,
+        cur: &str,
+        m: &str,
+    ) -> Result<usize> {
+        let bad = || ExpandError::Syntax(format!("bad modifier :{m}"));
+        let body = &m[1..];
+        let var_end = body.find('@').ok_or_else(bad)?;
+        let var = &body[..var_end];
+        let text_start = var_end + 1;
+        let text_len = find_unnested(&body[text_start..], &['@']);
+        if text_start + text_len == body.len() {
+            return Err(bad());
+        }
+        let text = &body[text_start..text_start + text_len];
+        let mut out = Vec::new();
+        for word in words(cur) {
+            self.locals.push((var.to_string(), word.to_string()));
+            let result = self.expand(text, false);
+            self.locals.pop();
+            out.push(result?);
+        }
+        set(value, out.join(" "));
+        Ok(1 + text_start + text_len + 1)
+    }
+}
+
+fn set(value: &mut Option<String>, new: String) {
+    if let Some(v) = value {
+        *v = new;
+    }
+}
+
+fn sysv_substitute(cur: &str, from: &str, to: &str) -> String {
+    let out: Vec<String> = words(cur)
+        .into_iter()
+        .map(|w| {
+            if let Some((prefix, suffix)) = from.split_once('%') {
+                if let Some(mid) =
+                    w.strip_prefix(prefix).and_then(|r| r.strip_suffix(suffix))
+                {
+                    return to.replacen('%', mid, 1);
+                }
+                return w.to_string();
+            }
+            match w.strip_suffix(from) {
+                Some(stem) => format!("{stem}{to}"),
+                None => w.to_string(),
+            }
+        })
+        .collect();
+    out.join(" ")
+}
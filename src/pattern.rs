@@ -107,6 +107,24 @@
  * When scanning many candidates, [`Pattern::best_matcher`] returns a
  * [`BestMatch`] accumulator that caches the parsed version of the running
  * best rather than re-parsing it on every comparison.
+ *
+ * # Pattern Relationships
+ *
+ * [`Pattern::is_subset`] and [`Pattern::intersects`] compare two patterns
+ * without reference to any package names, returning a [`Decidable`] answer
+ * as not every pair of glob patterns can be compared:
+ *
+ * ```
+ * use pkgsrc::{Decidable, Pattern};
+ *
+ * let p1 = Pattern::new("mktool>=1.4")?;
+ * let p2 = Pattern::new("mktool>=1")?;
+ * assert_eq!(p1.is_subset(&p2), Decidable::Yes);
+ * assert_eq!(p1.intersects(&Pattern::new("mktool<1.0")?), Decidable::No);
+ * let p3 = Pattern::new("mktool-[0-9]*")?;
+ * assert_eq!(p1.is_subset(&p3), Decidable::Unknown);
+ * # Ok::<(), pkgsrc::PatternError>(())
+ * ```
  */
 
 use crate::dewey::{Dewey, DeweyError, DeweyOp, DeweyVersion, dewey_cmp};
@@ -118,6 +136,14 @@ use std::fmt;
 use std::str::FromStr;
 use thiserror::Error;
 
+mod explain;
+mod relation;
+mod set;
+
+pub use explain::Mismatch;
+pub use relation::Decidable;
+pub use set::PatternSet;
+
 /**
  * Characters that indicate the start of a glob pattern.
  */
@@ -0,0 +1,538 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*
+ * Explanations for why a package name does not match a pattern.
+ */
+
+use super::{Pattern, PatternType};
+use crate::dewey::{DeweyOp, DeweyVersion, dewey_cmp};
+use crate::pkgname::{pkgbase, pkgversion};
+use std::fmt;
+
+/**
+ * The reason that a package name does not match a [`Pattern`].
+ *
+ * Returned by [`Pattern::explain`].
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Mismatch {
+    /**
+     * The package name has a different `PKGBASE` to the pattern.
+     */
+    PkgbaseDiffers {
+        /** The `PKGBASE` required by the pattern. */
+        expected: String,
+        /** The `PKGBASE` of the package name. */
+        found: String,
+    },
+    /**
+     * The package name has no version, i.e. contains no `-`.
+     */
+    MissingVersion,
+    /**
+     * The package version could not be parsed, for example because a
+     * numeric component overflows.
+     */
+    InvalidVersion {
+        /** The package version. */
+        version: String,
+    },
+    /**
+     * The package version fails a dewey version constraint.
+     */
+    Constraint {
+        /** The package version. */
+        version: String,
+        /** The comparison operator, for example `>=`. */
+        op: &'static str,
+        /** The version being compared against. */
+        bound: String,
+    },
+    /**
+     * A simple pattern has the same `PKGBASE` as the package name but a
+     * different version.
+     */
+    VersionDiffers {
+        /** The version required by the pattern. */
+        expected: String,
+        /** The package version. */
+        found: String,
+    },
+    /**
+     * A glob pattern failed to match.  As globs may backtrack, this
+     * describes the failure that progressed furthest through the pattern.
+     */
+    Glob {
+        /** Byte offset of the failing glob token within the pattern. */
+        offset: usize,
+        /**
+         * The failing glob token, for example `[0-9]`, or [`None`] if the
+         * pattern ended before the package name.
+         */
+        token: Option<String>,
+        /** Byte offset within the package name where matching failed. */
+        pkg_offset: usize,
+        /**
+         * The package name character that failed to match, or [`None`] if
+         * the package name ended first.
+         */
+        found: Option<char>,
+    },
+    /**
+     * None of the branches of an alternate pattern matched.  Each entry
+     * contains the expanded branch pattern and the reason it failed.
+     */
+    Alternate(Vec<(String, Mismatch)>),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::PkgbaseDiffers { expected, found } => {
+                write!(f, "pkgbase {found} differs from {expected}")
+            }
+            Mismatch::MissingVersion => write!(f, "package has no version"),
+            Mismatch::InvalidVersion { version } => {
+                write!(f, "version {version} could not be parsed")
+            }
+            Mismatch::Constraint { version, op, bound } => {
+                write!(f, "version {version} fails constraint {op}{bound}")
+            }
+            Mismatch::VersionDiffers { expected, found } => {
+                write!(f, "version {found} differs from {expected}")
+            }
+            Mismatch::Glob {
+                offset,
+                token: Some(token),
+                found,
+                ..
+            } => {
+                write!(f, "glob {token} at offset {offset} did not match ")?;
+                match found {
+                    Some(c) => write!(f, "{c:?}"),
+                    None => write!(f, "end of package name"),
+                }
+            }
+            Mismatch::Glob {
+                token: None,
+                pkg_offset,
+                ..
+            } => {
+                write!(f, "pattern ended before offset {pkg_offset}")
+            }
+            Mismatch::Alternate(branches) => {
+                write!(f, "no alternative matched")?;
+                for (i, (pattern, reason)) in branches.iter().enumerate() {
+                    write!(f, "; branch {i} ({pattern}): {reason}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Pattern {
+    /**
+     * Explain why `pkg` does not match this pattern, returning [`None`] if
+     * it does match.
+     *
+     * ```
+     * use pkgsrc::Pattern;
+     * use pkgsrc::pattern::Mismatch;
+     *
+     * let p = Pattern::new("mktool>=1.4")?;
+     * assert_eq!(p.explain("mktool-1.4.2"), None);
+     * assert_eq!(
+     *     p.explain("mktool-1.2").map(|m| m.to_string()),
+     *     Some("version 1.2 fails constraint >=1.4".to_string())
+     * );
+     * assert!(matches!(
+     *     p.explain("mktools-1.4"),
+     *     Some(Mismatch::PkgbaseDiffers { .. })
+     * ));
+     *
+     * let p = Pattern::new("mktool-[0-9]*")?;
+     * assert_eq!(
+     *     p.explain("mktool-git").map(|m| m.to_string()),
+     *     Some("glob [0-9] at offset 7 did not match 'g'".to_string())
+     * );
+     * # Ok::<(), pkgsrc::PatternError>(())
+     * ```
+     */
+    #[must_use]
+    pub fn explain(&self, pkg: &str) -> Option<Mismatch> {
+        if self.matches(pkg) {
+            return None;
+        }
+        Some(match self.matchtype {
+            PatternType::Alternate(ref patterns) => Mismatch::Alternate(
+                patterns
+                    .iter()
+                    .filter_map(|p| {
+                        p.explain(pkg).map(|m| (p.pattern.clone(), m))
+                    })
+                    .collect(),
+            ),
+            PatternType::Dewey(ref dewey) => {
+                explain_dewey(&self.pattern, dewey.pkgbase(), pkg)
+            }
+            PatternType::Glob(_) => explain_glob(&self.pattern, pkg),
+            PatternType::Simple => {
+                let (expected, found) = (pkgbase(&self.pattern), pkgbase(pkg));
+                if expected == found {
+                    Mismatch::VersionDiffers {
+                        expected: pkgversion(&self.pattern).to_string(),
+                        found: pkgversion(pkg).to_string(),
+                    }
+                } else {
+                    Mismatch::PkgbaseDiffers {
+                        expected: expected.to_string(),
+                        found: found.to_string(),
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn explain_dewey(pattern: &str, base: &str, pkg: &str) -> Mismatch {
+    let Some((found, version)) = pkg.rsplit_once('-') else {
+        return Mismatch::MissingVersion;
+    };
+    if found != base {
+        return Mismatch::PkgbaseDiffers {
+            expected: base.to_string(),
+            found: found.to_string(),
+        };
+    }
+    let invalid = || Mismatch::InvalidVersion {
+        version: version.to_string(),
+    };
+    let Ok(pkgver) = DeweyVersion::new(version) else {
+        return invalid();
+    };
+
+    /*
+     * The pattern has already been validated, so it is simply a PKGBASE
+     * followed by one or two operator and version pairs.
+     */
+    let mut rest = &pattern[base.len()..];
+    while !rest.is_empty() {
+        let (op, opstr) = if rest.starts_with(">=") {
+            (DeweyOp::GE, ">=")
+        } else if rest.starts_with("<=") {
+            (DeweyOp::LE, "<=")
+        } else if rest.starts_with('>') {
+            (DeweyOp::GT, ">")
+        } else {
+            (DeweyOp::LT, "<")
+        };
+        rest = &rest[opstr.len()..];
+        let end = rest.find(['<', '>']).unwrap_or(rest.len());
+        let bound = &rest[..end];
+        rest = &rest[end..];
+        let Ok(boundver) = DeweyVersion::new(bound) else {
+            return invalid();
+        };
+        if !dewey_cmp(&pkgver, &op, &boundver) {
+            return Mismatch::Constraint {
+                version: version.to_string(),
+                op: opstr,
+                bound: bound.to_string(),
+            };
+        }
+    }
+    invalid()
+}
+
+#[derive(Debug)]
+enum Token {
+    Literal(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Literal(l) => *l == c,
+            Token::Any => true,
+            Token::Star => unreachable!(),
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c))
+                    != *negated
+            }
+        }
+    }
+}
+
+/*
+ * Split a glob into tokens with their byte offset and length, using the same
+ * syntax as the glob crate.  The pattern has already been validated.
+ */
+fn tokenize(pattern: &str) -> Vec<(usize, usize, Token)> {
+    let chars: Vec<(usize, char)> = pattern.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (offset, c) = chars[i];
+        match c {
+            '*' => {
+                tokens.push((offset, 1, Token::Star));
+                i += 1;
+            }
+            '?' => {
+                tokens.push((offset, 1, Token::Any));
+                i += 1;
+            }
+            '[' => {
+                let mut j = i + 1;
+                let negated = chars.get(j).is_some_and(|(_, c)| *c == '!');
+                if negated {
+                    j += 1;
+                }
+                let mut ranges = Vec::new();
+                let mut first = true;
+                while let Some(&(_, c)) = chars.get(j) {
+                    if c == ']' && !first {
+                        break;
+                    }
+                    first = false;
+                    match (chars.get(j + 1), chars.get(j + 2)) {
+                        (Some((_, '-')), Some(&(_, hi))) if hi != ']' => {
+                            ranges.push((c, hi));
+                            j += 3;
+                        }
+                        _ => {
+                            ranges.push((c, c));
+                            j += 1;
+                        }
+                    }
+                }
+                let end = chars.get(j).map_or(pattern.len(), |(o, _)| o + 1);
+                tokens.push((
+                    offset,
+                    end - offset,
+                    Token::Class { negated, ranges },
+                ));
+                i = j + 1;
+            }
+            c => {
+                tokens.push((offset, c.len_utf8(), Token::Literal(c)));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/*
+ * Backtracking glob matcher that records the failure which got furthest
+ * through the pattern, preferring the one that consumed the most of the
+ * package name on a tie.  A state that has already failed will fail again
+ * and can record nothing new, so failed states are remembered in `failed`,
+ * indexed by (ti, ni), to keep patterns with many `*` tokens from taking
+ * exponential time.
+ */
+fn walk(
+    tokens: &[(usize, usize, Token)],
+    ti: usize,
+    name: &[(usize, char)],
+    ni: usize,
+    furthest: &mut Option<(usize, usize)>,
+    failed: &mut [bool],
+) -> bool {
+    let state = ti * (name.len() + 1) + ni;
+    if failed[state] {
+        return false;
+    }
+    let matched = step(tokens, ti, name, ni, furthest, failed);
+    if !matched {
+        failed[state] = true;
+    }
+    matched
+}
+
+fn step(
+    tokens: &[(usize, usize, Token)],
+    ti: usize,
+    name: &[(usize, char)],
+    ni: usize,
+    furthest: &mut Option<(usize, usize)>,
+    failed: &mut [bool],
+) -> bool {
+    let Some((_, _, token)) = tokens.get(ti) else {
+        if ni == name.len() {
+            return true;
+        }
+        if furthest.is_none_or(|(fni, fti)| (ti, ni) > (fti, fni)) {
+            *furthest = Some((ni, ti));
+        }
+        return false;
+    };
+    if let Token::Star = token {
+        return (ni..=name.len())
+            .any(|k| walk(tokens, ti + 1, name, k, furthest, failed));
+    }
+    match name.get(ni) {
+        Some(&(_, c)) if token.matches(c) => {
+            walk(tokens, ti + 1, name, ni + 1, furthest, failed)
+        }
+        _ => {
+            if furthest.is_none_or(|(fni, fti)| (ti, ni) > (fti, fni)) {
+                *furthest = Some((ni, ti));
+            }
+            false
+        }
+    }
+}
+
+fn explain_glob(pattern: &str, pkg: &str) -> Mismatch {
+    let tokens = tokenize(pattern);
+    let name: Vec<(usize, char)> = pkg.char_indices().collect();
+    let mut furthest = None;
+    let mut failed = vec![false; (tokens.len() + 1) * (name.len() + 1)];
+    walk(&tokens, 0, &name, 0, &mut furthest, &mut failed);
+    let (ni, ti) = furthest.unwrap_or((name.len(), tokens.len()));
+    let (offset, token) = match tokens.get(ti) {
+        Some((offset, len, _)) => {
+            (*offset, Some(pattern[*offset..offset + len].to_string()))
+        }
+        None => (pattern.len(), None),
+    };
+    Mismatch::Glob {
+        offset,
+        token,
+        pkg_offset: name.get(ni).map_or(pkg.len(), |(o, _)| *o),
+        found: name.get(ni).map(|(_, c)| *c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternError;
+
+    fn explain(
+        pattern: &str,
+        pkg: &str,
+    ) -> Result<Option<String>, PatternError> {
+        Ok(Pattern::new(pattern)?.explain(pkg).map(|m| m.to_string()))
+    }
+
+    #[test]
+    fn dewey() -> Result<(), PatternError> {
+        assert_eq!(explain("foo>=1.0<2", "foo-1.5")?, None);
+        assert_eq!(
+            explain("foo>=1.0<2", "foo-2.0nb1")?.as_deref(),
+            Some("version 2.0nb1 fails constraint <2")
+        );
+        assert_eq!(
+            explain("foo>1.0", "foo-1.0")?.as_deref(),
+            Some("version 1.0 fails constraint >1.0")
+        );
+        assert_eq!(
+            explain("foo>=1", "bar-1")?.as_deref(),
+            Some("pkgbase bar differs from foo")
+        );
+        assert_eq!(
+            Pattern::new("foo>=1")?.explain("foo"),
+            Some(Mismatch::MissingVersion)
+        );
+        assert!(matches!(
+            Pattern::new("foo>=1")?.explain("foo-99999999999999999999"),
+            Some(Mismatch::InvalidVersion { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn simple() -> Result<(), PatternError> {
+        assert_eq!(
+            explain("foo-1.0", "foo-1.1")?.as_deref(),
+            Some("version 1.1 differs from 1.0")
+        );
+        assert_eq!(
+            explain("foo-1.0", "bar-1.0")?.as_deref(),
+            Some("pkgbase bar differs from foo")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn glob() -> Result<(), PatternError> {
+        assert_eq!(
+            Pattern::new("foo-[0-9]*")?.explain("foo-bar-1.0"),
+            Some(Mismatch::Glob {
+                offset: 4,
+                token: Some("[0-9]".to_string()),
+                pkg_offset: 4,
+                found: Some('b'),
+            })
+        );
+        assert_eq!(
+            explain("foo-1.?", "foo-1.")?.as_deref(),
+            Some("glob ? at offset 6 did not match end of package name")
+        );
+        assert_eq!(
+            explain("foo-1.[!0-4]*", "foo-1.3")?.as_deref(),
+            Some("glob [!0-4] at offset 6 did not match '3'")
+        );
+        assert_eq!(
+            explain("foo-1.[]a]", "foo-1.b")?.as_deref(),
+            Some("glob []a] at offset 6 did not match 'b'")
+        );
+        assert_eq!(
+            explain("*-1.0", "foo-1.0nb1")?.as_deref(),
+            Some("pattern ended before offset 7")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn glob_many_stars() -> Result<(), PatternError> {
+        /*
+         * Without memoisation this takes exponential time in the number of
+         * stars, as each one can consume any suffix of the name.
+         */
+        let pattern = "*a".repeat(16) + "b";
+        let pkg = "a".repeat(64);
+        assert_eq!(
+            explain(&pattern, &pkg)?.as_deref(),
+            Some("glob b at offset 32 did not match end of package name")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn alternate() -> Result<(), PatternError> {
+        assert_eq!(
+            explain("{foo,bar}>=2", "foo-1")?.as_deref(),
+            Some(
+                "no alternative matched; \
+                 branch 0 (foo>=2): version 1 fails constraint >=2; \
+                 branch 1 (bar>=2): pkgbase foo differs from bar"
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,545 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*
+ * Subsumption and intersection queries between patterns.
+ *
+ * Each pattern is flattened into a union of atoms.  Dewey patterns become
+ * version ranges over a single PKGBASE that can be compared exactly.  Simple
+ * patterns become a single exact package name, which can be tested against
+ * any other pattern directly.  Globs are opaque, even `pkgbase-[0-9]*`, as
+ * it does not match the same names as any Dewey range: "foo-1-bar-2" is
+ * matched by "foo-[0-9]*", and "foo-a1" by "foo>=0" but not the glob.  The
+ * only things that can be said about a glob are whether it is textually
+ * identical to another, or has a literal prefix that no name matched by the
+ * other atom can start with.
+ */
+
+use super::{Pattern, PatternType};
+use crate::dewey::{DeweyOp, DeweyVersion, dewey_order};
+use std::cmp::Ordering;
+
+/**
+ * The result of a query about the relationship between two patterns, which
+ * may not always be decidable.
+ *
+ * Returned by [`Pattern::is_subset`] and [`Pattern::intersects`].
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Decidable {
+    /** The relationship holds. */
+    Yes,
+    /** The relationship does not hold. */
+    No,
+    /** The relationship could not be determined. */
+    Unknown,
+}
+
+impl Decidable {
+    /**
+     * Return true if the answer is [`Decidable::Yes`].
+     */
+    #[must_use]
+    pub fn is_yes(self) -> bool {
+        self == Decidable::Yes
+    }
+
+    /**
+     * Return true if the answer is [`Decidable::No`].
+     */
+    #[must_use]
+    pub fn is_no(self) -> bool {
+        self == Decidable::No
+    }
+
+    /**
+     * Return the answer as a [`bool`], or [`None`] if unknown.
+     */
+    #[must_use]
+    pub fn known(self) -> Option<bool> {
+        match self {
+            Decidable::Yes => Some(true),
+            Decidable::No => Some(false),
+            Decidable::Unknown => None,
+        }
+    }
+}
+
+impl From<bool> for Decidable {
+    fn from(b: bool) -> Self {
+        if b { Decidable::Yes } else { Decidable::No }
+    }
+}
+
+/*
+ * A range bound.  Exclusive lower bounds, and exclusive upper bounds with a
+ * non-zero PKGREVISION, are normalised to inclusive bounds on the adjacent
+ * PKGREVISION, as there are no versions between "1.0nb1" and "1.0nb2".  This
+ * makes bound comparisons exact.
+ */
+#[derive(Clone, Debug)]
+struct Bound {
+    version: DeweyVersion,
+    inclusive: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Range {
+    lower: Option<Bound>,
+    upper: Option<Bound>,
+}
+
+impl Range {
+    fn is_empty(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Some(lo), Some(hi)) => match dewey_order(&lo.version, &hi.version)
+            {
+                Ordering::Less => false,
+                Ordering::Equal => !hi.inclusive,
+                Ordering::Greater => true,
+            },
+            _ => false,
+        }
+    }
+
+    /*
+     * Return whether self is contained within other.  Both ranges must be
+     * non-empty.
+     */
+    fn within(&self, other: &Range) -> bool {
+        lower_le(&other.lower, &self.lower)
+            && upper_ge(&other.upper, &self.upper)
+    }
+
+    fn intersect(&self, other: &Range) -> Range {
+        let lower = if lower_le(&self.lower, &other.lower) {
+            other.lower.clone()
+        } else {
+            self.lower.clone()
+        };
+        let upper = if upper_ge(&self.upper, &other.upper) {
+            other.upper.clone()
+        } else {
+            self.upper.clone()
+        };
+        Range { lower, upper }
+    }
+}
+
+/*
+ * Return whether lower bound a admits everything that lower bound b does.
+ * Lower bounds are always inclusive after normalisation.
+ */
+fn lower_le(a: &Option<Bound>, b: &Option<Bound>) -> bool {
+    match (a, b) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(a), Some(b)) => {
+            dewey_order(&a.version, &b.version) != Ordering::Greater
+        }
+    }
+}
+
+/*
+ * Return whether upper bound a admits everything that upper bound b does.
+ */
+fn upper_ge(a: &Option<Bound>, b: &Option<Bound>) -> bool {
+    match (a, b) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(a), Some(b)) => match dewey_order(&a.version, &b.version) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => a.inclusive || !b.inclusive,
+        },
+    }
+}
+
+/*
+ * Each atom retains the (possibly expanded) pattern it was created from, so
+ * that exact package names can be tested with the real matching rules.
+ */
+#[derive(Debug)]
+enum Atom<'a> {
+    /* A Simple pattern matching a single package name. */
+    Exact(&'a Pattern),
+    /* A version range over a single PKGBASE. */
+    Range(&'a Pattern, &'a str, Range),
+    /* A glob, with its literal prefix. */
+    Glob(&'a Pattern, &'a str),
+}
+
+impl Atom<'_> {
+    fn pattern(&self) -> &Pattern {
+        match self {
+            Atom::Exact(p) | Atom::Range(p, _, _) | Atom::Glob(p, _) => p,
+        }
+    }
+
+    fn pkgbase(&self) -> Option<&str> {
+        match self {
+            Atom::Exact(p) => p.pattern.rsplit_once('-').map(|(b, _)| b),
+            Atom::Range(_, base, _) => Some(base),
+            Atom::Glob(..) => None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Atom::Range(_, _, r) if r.is_empty())
+    }
+}
+
+fn atoms<'a>(pattern: &'a Pattern, out: &mut Vec<Atom<'a>>) {
+    match pattern.matchtype {
+        PatternType::Alternate(ref patterns) => {
+            for p in patterns {
+                atoms(p, out);
+            }
+        }
+        PatternType::Simple => out.push(Atom::Exact(pattern)),
+        PatternType::Dewey(ref dewey) => {
+            let mut range = Range::default();
+            for (op, version) in dewey.constraints() {
+                match op {
+                    DeweyOp::GE => {
+                        range.lower = Some(Bound {
+                            version: version.clone(),
+                            inclusive: true,
+                        });
+                    }
+                    DeweyOp::GT => {
+                        range.lower = Some(Bound {
+                            version: version
+                                .with_pkgrevision(version.pkgrevision() + 1),
+                            inclusive: true,
+                        });
+                    }
+                    DeweyOp::LE => {
+                        range.upper = Some(Bound {
+                            version: version.clone(),
+                            inclusive: true,
+                        });
+                    }
+                    DeweyOp::LT if version.pkgrevision() > 0 => {
+                        range.upper = Some(Bound {
+                            version: version
+                                .with_pkgrevision(version.pkgrevision() - 1),
+                            inclusive: true,
+                        });
+                    }
+                    DeweyOp::LT => {
+                        range.upper = Some(Bound {
+                            version: version.clone(),
+                            inclusive: false,
+                        });
+                    }
+                }
+            }
+            out.push(Atom::Range(pattern, dewey.pkgbase(), range));
+        }
+        PatternType::Glob(_) => {
+            let end = pattern
+                .pattern
+                .bytes()
+                .position(|b| super::GLOB_START.contains(&b))
+                .unwrap_or(pattern.pattern.len());
+            out.push(Atom::Glob(pattern, &pattern.pattern[..end]));
+        }
+    }
+}
+
+/*
+ * Return whether two atoms are provably disjoint.  Atoms with a PKGBASE only
+ * match names of the form "pkgbase-version", and globs only match names
+ * starting with their literal prefix, so they are disjoint if those
+ * prefixes are incompatible.
+ */
+fn disjoint(a: &Atom<'_>, b: &Atom<'_>) -> bool {
+    match (a, b) {
+        (Atom::Glob(_, p1), Atom::Glob(_, p2)) => {
+            !p1.starts_with(p2) && !p2.starts_with(p1)
+        }
+        (Atom::Glob(_, prefix), atom) | (atom, Atom::Glob(_, prefix)) => {
+            atom.pkgbase().is_some_and(|base| {
+                if prefix.len() > base.len() {
+                    !prefix.starts_with(base)
+                        || prefix.as_bytes()[base.len()] != b'-'
+                } else {
+                    !base.starts_with(prefix)
+                }
+            })
+        }
+        _ => matches!((a.pkgbase(), b.pkgbase()), (Some(x), Some(y)) if x != y),
+    }
+}
+
+fn atom_intersects(a: &Atom<'_>, b: &Atom<'_>) -> Decidable {
+    if a.is_empty() || b.is_empty() {
+        return Decidable::No;
+    }
+    match (a, b) {
+        (Atom::Exact(p), _) => b.pattern().matches(&p.pattern).into(),
+        (_, Atom::Exact(p)) => a.pattern().matches(&p.pattern).into(),
+        _ if disjoint(a, b) => Decidable::No,
+        (Atom::Range(_, _, r1), Atom::Range(_, _, r2)) => {
+            (!r1.intersect(r2).is_empty()).into()
+        }
+        (Atom::Glob(p1, _), Atom::Glob(p2, _)) if p1.pattern == p2.pattern => {
+            Decidable::Yes
+        }
+        _ => Decidable::Unknown,
+    }
+}
+
+/*
+ * Return whether atom a is contained within atom b.
+ */
+fn atom_subset(a: &Atom<'_>, b: &Atom<'_>) -> Decidable {
+    if a.is_empty() {
+        return Decidable::Yes;
+    }
+    if b.is_empty() {
+        return Decidable::No;
+    }
+    match (a, b) {
+        (Atom::Exact(p), _) => b.pattern().matches(&p.pattern).into(),
+        /* A non-empty range or glob matches more than one package name. */
+        (_, Atom::Exact(_)) => Decidable::No,
+        _ if disjoint(a, b) => Decidable::No,
+        (Atom::Range(_, _, r1), Atom::Range(_, _, r2)) => r1.within(r2).into(),
+        (Atom::Glob(p1, _), Atom::Glob(p2, _)) if p1.pattern == p2.pattern => {
+            Decidable::Yes
+        }
+        _ => Decidable::Unknown,
+    }
+}
+
+impl Pattern {
+    /**
+     * Return whether every package matched by this pattern is also matched
+     * by `other`.
+     *
+     * The answer is exact for [`Dewey`] ranges, simple patterns, and
+     * alternates of those.  Globs, including `pkgbase-[0-9]*`, can only be
+     * compared when they are identical or have a literal prefix that rules
+     * out any overlap, and otherwise return [`Decidable::Unknown`].
+     *
+     * This can be used to find `DEPENDS` that are made redundant by a
+     * stricter `DEPENDS` on the same package.
+     *
+     * ```
+     * use pkgsrc::{Decidable, Pattern};
+     *
+     * let p1 = Pattern::new("perl>=5.38<5.40")?;
+     * let p2 = Pattern::new("perl>=5.30")?;
+     * assert_eq!(p1.is_subset(&p2), Decidable::Yes);
+     * assert_eq!(p2.is_subset(&p1), Decidable::No);
+     *
+     * let p3 = Pattern::new("{perl,perl5}>=5")?;
+     * assert_eq!(p2.is_subset(&p3), Decidable::Yes);
+     *
+     * let p4 = Pattern::new("perl-5.3*")?;
+     * assert_eq!(p4.is_subset(&p2), Decidable::Unknown);
+     * # Ok::<(), pkgsrc::PatternError>(())
+     * ```
+     *
+     * [`Dewey`]: crate::Dewey
+     */
+    #[must_use]
+    pub fn is_subset(&self, other: &Pattern) -> Decidable {
+        if self.pattern == other.pattern {
+            return Decidable::Yes;
+        }
+        let mut lhs = Vec::new();
+        let mut rhs = Vec::new();
+        atoms(self, &mut lhs);
+        atoms(other, &mut rhs);
+
+        let mut result = Decidable::Yes;
+        for a in &lhs {
+            let answer = match a {
+                Atom::Exact(p) => other.matches(&p.pattern).into(),
+                _ => union_subset(a, &rhs),
+            };
+            match answer {
+                Decidable::No => return Decidable::No,
+                Decidable::Unknown => result = Decidable::Unknown,
+                Decidable::Yes => {}
+            }
+        }
+        result
+    }
+
+    /**
+     * Return whether there is any package name that is matched by both this
+     * pattern and `other`.
+     *
+     * The same rules as [`Pattern::is_subset`] apply as to which patterns
+     * can be decided.  This can be used to find `CONFLICTS` entries that
+     * can never match the package they are intended to exclude.
+     *
+     * ```
+     * use pkgsrc::{Decidable, Pattern};
+     *
+     * let p1 = Pattern::new("openssl<3")?;
+     * let p2 = Pattern::new("openssl>=3.0.14")?;
+     * assert_eq!(p1.intersects(&p2), Decidable::No);
+     *
+     * let p3 = Pattern::new("{openssl,libressl}>=1")?;
+     * assert_eq!(p1.intersects(&p3), Decidable::Yes);
+     *
+     * let p4 = Pattern::new("openssl-1.*")?;
+     * assert_eq!(p1.intersects(&p4), Decidable::Unknown);
+     * # Ok::<(), pkgsrc::PatternError>(())
+     * ```
+     */
+    #[must_use]
+    pub fn intersects(&self, other: &Pattern) -> Decidable {
+        let mut lhs = Vec::new();
+        let mut rhs = Vec::new();
+        atoms(self, &mut lhs);
+        atoms(other, &mut rhs);
+
+        let mut result = Decidable::No;
+        for a in &lhs {
+            for b in &rhs {
+                match atom_intersects(a, b) {
+                    Decidable::Yes => return Decidable::Yes,
+                    Decidable::Unknown => result = Decidable::Unknown,
+                    Decidable::No => {}
+                }
+            }
+        }
+        result
+    }
+}
+
+/*
+ * Return whether atom a is contained in the union of atoms rhs.  If it is
+ * not contained in any single atom, it is only provably not a subset when
+ * at most one of them overlaps it, as otherwise the union may cover it.
+ */
+fn union_subset(a: &Atom<'_>, rhs: &[Atom<'_>]) -> Decidable {
+    let mut all_no = true;
+    for b in rhs {
+        match atom_subset(a, b) {
+            Decidable::Yes => return Decidable::Yes,
+            Decidable::Unknown => all_no = false,
+            Decidable::No => {}
+        }
+    }
+    if !all_no {
+        return Decidable::Unknown;
+    }
+    let overlapping = rhs
+        .iter()
+        .filter(|b| !atom_intersects(a, b).is_no())
+        .count();
+    if overlapping <= 1 {
+        Decidable::No
+    } else {
+        Decidable::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternError;
+
+    fn subset(a: &str, b: &str) -> Result<Decidable, PatternError> {
+        Ok(Pattern::new(a)?.is_subset(&Pattern::new(b)?))
+    }
+
+    fn intersects(a: &str, b: &str) -> Result<Decidable, PatternError> {
+        Ok(Pattern::new(a)?.intersects(&Pattern::new(b)?))
+    }
+
+    #[test]
+    fn dewey_subset() -> Result<(), PatternError> {
+        use Decidable::*;
+        assert_eq!(subset("foo>=1.2", "foo>=1.0")?, Yes);
+        assert_eq!(subset("foo>=1.0", "foo>=1.2")?, No);
+        assert_eq!(subset("foo>=1.0<2", "foo>=1")?, Yes);
+        assert_eq!(subset("foo>=1.0<2", "foo<2")?, Yes);
+        assert_eq!(subset("foo>=1.0<=2", "foo<2")?, No);
+        assert_eq!(subset("foo>1.0", "foo>=1.0")?, Yes);
+        assert_eq!(subset("foo>=1.0", "foo>1.0")?, No);
+        assert_eq!(subset("foo>=1", "bar>=1")?, No);
+        /* There is nothing between 1.0nb1 and 1.0nb2. */
+        assert_eq!(subset("foo>1.0nb1", "foo>=1.0nb2")?, Yes);
+        assert_eq!(subset("foo<=1.0nb1", "foo<1.0nb2")?, Yes);
+        assert_eq!(subset("foo<1.0", "foo<=1.0alpha")?, No);
+        /* An empty range is a subset of anything. */
+        assert_eq!(subset("foo>2<1", "bar-1.0")?, Yes);
+        Ok(())
+    }
+
+    #[test]
+    fn mixed_subset() -> Result<(), PatternError> {
+        use Decidable::*;
+        /*
+         * "foo-a1" is matched by the Dewey pattern but not the glob, and
+         * "foo-1-bar-2" by the glob but not "foo>=0".
+         */
+        assert_eq!(subset("foo>=0", "foo-[0-9]*")?, Unknown);
+        assert_eq!(subset("foo-[0-9]*", "foo>=0")?, Unknown);
+        assert_eq!(subset("foo>=1", "bar-[0-9]*")?, No);
+        assert_eq!(subset("foo-1.0", "foo>=1<2")?, Yes);
+        assert_eq!(subset("foo-2.0", "foo>=1<2")?, No);
+        assert_eq!(subset("foo>=1", "foo-1.0")?, No);
+        assert_eq!(subset("foo-1.*", "foo-1.*")?, Yes);
+        assert_eq!(subset("foo-1.*", "bar>=1")?, No);
+        assert_eq!(subset("foo-1.*", "foo>=1")?, Unknown);
+        assert_eq!(subset("foo*-1.0", "foo>=1")?, Unknown);
+        assert_eq!(subset("foo-1.0", "foo-1.*")?, Yes);
+        Ok(())
+    }
+
+    #[test]
+    fn alternate_subset() -> Result<(), PatternError> {
+        use Decidable::*;
+        assert_eq!(subset("{foo,bar}>=2", "{bar,foo,baz}>=1")?, Yes);
+        assert_eq!(subset("{foo,bar}>=2", "foo>=1")?, No);
+        assert_eq!(subset("foo>=2", "{foo,bar}>=1")?, Yes);
+        /* Covered only by the union of the alternatives. */
+        assert_eq!(subset("foo<2", "{foo<1,foo>=1<3}")?, Unknown);
+        assert_eq!(subset("foo<2", "{foo<1,bar>=1}")?, No);
+        Ok(())
+    }
+
+    #[test]
+    fn intersection() -> Result<(), PatternError> {
+        use Decidable::*;
+        assert_eq!(intersects("foo>=1<2", "foo>=1.5")?, Yes);
+        assert_eq!(intersects("foo<1", "foo>=1")?, No);
+        assert_eq!(intersects("foo<=1", "foo>=1")?, Yes);
+        assert_eq!(intersects("foo>1.0<1.0nb1", "foo-[0-9]*")?, No);
+        assert_eq!(intersects("foo>=1", "bar>=1")?, No);
+        assert_eq!(intersects("foo-1.0", "foo>=1")?, Yes);
+        assert_eq!(intersects("foo>=2", "foo-1.0")?, No);
+        assert_eq!(intersects("foo-1.*", "foo-1.*")?, Yes);
+        assert_eq!(intersects("foo-1.*", "bar-1.*")?, No);
+        assert_eq!(intersects("foo-1.*", "foo>=2")?, Unknown);
+        assert_eq!(intersects("{foo,bar}<1", "bar>=0.5")?, Yes);
+        /* "foo-1-bar-2" is matched by both. */
+        assert_eq!(intersects("foo-[0-9]*", "foo-1-bar>=1")?, Unknown);
+        assert_eq!(intersects("foo-[0-9]*", "foo-1-bar-2")?, Yes);
+        assert_eq!(intersects("foo-[0-9]*", "foobar>=1")?, No);
+        assert_eq!(intersects("foo-1.*", "foo-1.0-bar>=1")?, Unknown);
+        Ok(())
+    }
+}
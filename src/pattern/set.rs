@@ -0,0 +1,312 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::{BestMatch, Pattern, PatternError};
+use crate::pkgname::pkgbase;
+use hashbrown::HashMap;
+use hashbrown::hash_map::EntryRef;
+
+/**
+ * A set of compiled [`Pattern`]s indexed for matching against many package
+ * names at once.
+ *
+ * Each pattern added to the set is assigned a numeric id, starting at zero
+ * and in insertion order.  Adding the same pattern string twice returns the
+ * existing id.
+ *
+ * Patterns are indexed by their `PKGBASE` where it can be determined (see
+ * [`Pattern::pkgbases`]), otherwise by the literal prefix before the first
+ * glob or brace character.  Matching a package name then only tests the
+ * patterns that could possibly match, rather than every pattern in the set.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::PatternSet;
+ *
+ * let mut set = PatternSet::new();
+ * let mktool = set.insert("mktool>=1.4")?;
+ * let any = set.insert("mktool-[0-9]*")?;
+ * let perl = set.insert("perl>=5.38")?;
+ *
+ * assert_eq!(set.matches("mktool-1.4.2"), vec![mktool, any]);
+ * assert_eq!(set.matches("mktool-1.3"), vec![any]);
+ *
+ * let candidates = ["mktool-1.3", "mktool-1.4.2", "perl-5.36.0"];
+ * let best = set.best_matches_pbulk(candidates)?;
+ * assert_eq!(best[mktool], Some("mktool-1.4.2"));
+ * assert_eq!(best[any], Some("mktool-1.4.2"));
+ * assert_eq!(best[perl], None);
+ * # Ok::<(), pkgsrc::PatternError>(())
+ * ```
+ */
+#[derive(Clone, Debug, Default)]
+pub struct PatternSet {
+    patterns: Vec<Pattern>,
+    ids: HashMap<String, usize>,
+    by_base: HashMap<String, Vec<usize>>,
+    by_prefix: Vec<(String, usize)>,
+}
+
+impl PatternSet {
+    /**
+     * Create an empty set.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Create an empty set with capacity for the given number of unique
+     * patterns.
+     */
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        PatternSet {
+            patterns: Vec::with_capacity(capacity),
+            ids: HashMap::with_capacity(capacity),
+            by_base: HashMap::with_capacity(capacity),
+            by_prefix: Vec::new(),
+        }
+    }
+
+    /**
+     * Compile and add a pattern, returning its id.  If the same pattern
+     * string has already been added, the existing id is returned.
+     *
+     * # Errors
+     *
+     * Returns [`PatternError`] if the pattern is invalid.
+     */
+    pub fn insert(&mut self, pattern: &str) -> Result<usize, PatternError> {
+        match self.ids.entry_ref(pattern) {
+            EntryRef::Occupied(e) => Ok(*e.get()),
+            EntryRef::Vacant(e) => {
+                let p = Pattern::new(pattern)?;
+                let id = self.patterns.len();
+                e.insert(id);
+                self.index(&p, id);
+                self.patterns.push(p);
+                Ok(id)
+            }
+        }
+    }
+
+    fn index(&mut self, pattern: &Pattern, id: usize) {
+        if let Some(bases) = pattern.pkgbases() {
+            for base in bases {
+                self.by_base.entry_ref(base).or_default().push(id);
+            }
+            return;
+        }
+        let end = pattern
+            .pattern()
+            .find(['*', '?', '[', '{', '<', '>'])
+            .unwrap_or(pattern.pattern().len());
+        self.by_prefix
+            .push((pattern.pattern()[..end].to_string(), id));
+    }
+
+    /**
+     * Return the pattern with the given id.
+     */
+    #[must_use]
+    pub fn get(&self, id: usize) -> Option<&Pattern> {
+        self.patterns.get(id)
+    }
+
+    /**
+     * Return the id of a previously inserted pattern string.
+     */
+    #[must_use]
+    pub fn id(&self, pattern: &str) -> Option<usize> {
+        self.ids.get(pattern).copied()
+    }
+
+    /**
+     * Return the number of unique patterns in the set.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /**
+     * Return true if the set is empty.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /**
+     * Return an iterator over all patterns in id order.
+     */
+    pub fn iter(&self) -> std::slice::Iter<'_, Pattern> {
+        self.patterns.iter()
+    }
+
+    /*
+     * Return the ids of every pattern that could possibly match pkgname,
+     * in ascending order.
+     */
+    fn candidates(&self, pkgname: &str) -> Vec<usize> {
+        let mut ids: Vec<usize> = self
+            .by_base
+            .get(pkgbase(pkgname))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .copied()
+            .chain(
+                self.by_prefix
+                    .iter()
+                    .filter(|(prefix, _)| pkgname.starts_with(prefix.as_str()))
+                    .map(|(_, id)| *id),
+            )
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /**
+     * Return the ids of every pattern that matches `pkgname`, in ascending
+     * order.
+     */
+    #[must_use]
+    pub fn matches(&self, pkgname: &str) -> Vec<usize> {
+        let mut ids = self.candidates(pkgname);
+        ids.retain(|&id| self.patterns[id].matches(pkgname));
+        ids
+    }
+
+    /**
+     * Return the best match for every pattern in the set from a list of
+     * candidate package names, indexed by pattern id.  When versions compare
+     * equal the lexicographically smaller name wins, as with
+     * [`Pattern::best_match`].
+     *
+     * # Errors
+     *
+     * Returns [`PatternError::Dewey`] if a matching candidate version cannot
+     * be parsed.
+     */
+    pub fn best_matches<'a, I>(
+        &self,
+        candidates: I,
+    ) -> Result<Vec<Option<&'a str>>, PatternError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.best(candidates, Pattern::best_matcher)
+    }
+
+    /**
+     * Identical to [`PatternSet::best_matches`] except when versions compare
+     * equal the lexicographically greater name wins, matching pbulk, as with
+     * [`Pattern::best_match_pbulk`].
+     *
+     * # Errors
+     *
+     * Returns [`PatternError::Dewey`] if a matching candidate version cannot
+     * be parsed.
+     */
+    pub fn best_matches_pbulk<'a, I>(
+        &self,
+        candidates: I,
+    ) -> Result<Vec<Option<&'a str>>, PatternError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.best(candidates, Pattern::best_matcher_pbulk)
+    }
+
+    fn best<'a, I>(
+        &self,
+        candidates: I,
+        matcher: for<'p> fn(&'p Pattern) -> BestMatch<'p>,
+    ) -> Result<Vec<Option<&'a str>>, PatternError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        /*
+         * The winners are tracked separately so that they retain the
+         * lifetime of the candidates rather than that of the accumulators.
+         */
+        let mut best: Vec<BestMatch<'_>> =
+            self.patterns.iter().map(matcher).collect();
+        let mut winners: Vec<Option<&'a str>> = vec![None; self.len()];
+        for candidate in candidates {
+            for id in self.candidates(candidate) {
+                if best[id].consider(candidate)? {
+                    winners[id] = Some(candidate);
+                }
+            }
+        }
+        Ok(winners)
+    }
+}
+
+impl<'a> IntoIterator for &'a PatternSet {
+    type Item = &'a Pattern;
+    type IntoIter = std::slice::Iter<'a, Pattern>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_dedup() -> Result<(), PatternError> {
+        let mut set = PatternSet::new();
+        assert_eq!(set.insert("foo-[0-9]*")?, 0);
+        assert_eq!(set.insert("bar>=1")?, 1);
+        assert_eq!(set.insert("foo-[0-9]*")?, 0);
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.id("bar>=1"), Some(1));
+        assert_eq!(set.get(1).map(Pattern::pattern), Some("bar>=1"));
+        assert!(set.insert("foo-[0-9").is_err());
+        assert_eq!(set.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn matches_unindexed() -> Result<(), PatternError> {
+        let mut set = PatternSet::new();
+        let glob = set.insert("py*-foo-[0-9]*")?;
+        let alt = set.insert("{py311,py312}-foo>=1")?;
+        let any = set.insert("*")?;
+        assert_eq!(set.matches("py311-foo-1.0"), vec![glob, alt, any]);
+        assert_eq!(set.matches("py310-foo-1.0"), vec![glob, any]);
+        assert_eq!(set.matches("bar-1.0"), vec![any]);
+        Ok(())
+    }
+
+    #[test]
+    fn best_tiebreak() -> Result<(), PatternError> {
+        let mut set = PatternSet::new();
+        let id = set.insert("{foo,bar}-[0-9]*")?;
+        let names = ["foo-1.0", "bar-1.0", "bar-0.9"];
+        assert_eq!(set.best_matches(names)?[id], Some("bar-1.0"));
+        assert_eq!(set.best_matches_pbulk(names)?[id], Some("foo-1.0"));
+        Ok(())
+    }
+}
@@ -69,9 +69,31 @@
  *
  * These are accessed via the [`FileRead`] trait methods.
  *
+ * # Summary Output
+ *
+ * An [`InstalledPackage`] can be converted to a [`Summary`] in the same way
+ * as `pkg_info -X`, allowing an entire pkgdb to be written out as a
+ * `pkg_summary` file:
+ *
+ * ```no_run
+ * use pkgsrc::pkgdb::PkgDB;
+ * use std::io;
+ *
+ * fn main() -> io::Result<()> {
+ *     for result in PkgDB::open("/var/db/pkg")? {
+ *         println!("{}\n", result?.to_summary()?);
+ *     }
+ *     Ok(())
+ * }
+ * ```
+ *
  * [`FileRead`]: crate::metadata::FileRead
  */
+use crate::PkgName;
 use crate::metadata::{Entry, FileRead};
+use crate::plist::{self, PlistEntry};
+use crate::summary::Summary;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::ReadDir;
 use std::io;
@@ -211,6 +233,94 @@ impl InstalledPackage {
         &self.path
     }
 
+    /**
+     * Convert this installed package to a [`Summary`] entry, equivalent to
+     * the output of `pkg_info -X`.
+     *
+     * `DEPENDS` and `CONFLICTS` are taken from the `@pkgdep` and `@pkgcfl`
+     * lines in `+CONTENTS`, and all other variables from `+BUILD_INFO`,
+     * `+COMMENT`, `+DESC` and `+SIZE_PKG`.  Installed packages have no
+     * `FILE_NAME`, `FILE_SIZE` or `FILE_CKSUM`.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if any metadata file cannot be read, or with
+     * [`io::ErrorKind::InvalidData`] if `+CONTENTS` or `+SIZE_PKG` are
+     * malformed.
+     */
+    pub fn to_summary(&self) -> io::Result<Summary> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        let mut conflicts: Vec<String> = Vec::new();
+        let mut depends: Vec<String> = Vec::new();
+        let contents = self.contents()?;
+        for entry in plist::parse(contents.as_bytes()) {
+            match entry.map_err(|e| invalid(format!("+CONTENTS: {e}")))? {
+                PlistEntry::PkgCfl(s) => conflicts.push(s.into_owned()),
+                PlistEntry::PkgDep(s) => depends.push(s.into_owned()),
+                _ => {}
+            }
+        }
+
+        let build_info = self.build_info()?.unwrap_or_default();
+        let mut vars: HashMap<&str, Vec<&str>> = HashMap::new();
+        for line in build_info.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                vars.entry(key).or_default().push(value);
+            }
+        }
+        let first = |key: &str| vars.get(key).and_then(|v| v.first()).copied();
+        let value = |key: &str| first(key).unwrap_or("").to_string();
+        let optional = |key: &str| first(key).map(String::from);
+        let non_empty = |key: &str| {
+            first(key)
+                .filter(|s| !s.trim().is_empty())
+                .map(String::from)
+        };
+        let values = |key: &str| {
+            vars.get(key)
+                .map(|v| v.iter().map(|s| (*s).to_string()).collect())
+        };
+
+        let size_pkg = match self.size_pkg()? {
+            Some(s) => s
+                .trim()
+                .parse()
+                .map_err(|e| invalid(format!("+SIZE_PKG: {e}")))?,
+            None => 0,
+        };
+
+        Ok(Summary::new(
+            PkgName::new(self.pkgname.as_str()),
+            self.comment()?,
+            size_pkg,
+            value("BUILD_DATE"),
+            first("CATEGORIES")
+                .unwrap_or("")
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            value("MACHINE_ARCH"),
+            value("OPSYS"),
+            value("OS_VERSION"),
+            value("PKGPATH"),
+            value("PKGTOOLS_VERSION"),
+            self.desc()?.lines().map(String::from).collect(),
+            Some(conflicts).filter(|v| !v.is_empty()),
+            Some(depends).filter(|v| !v.is_empty()),
+            non_empty("HOMEPAGE"),
+            optional("LICENSE"),
+            optional("PKG_OPTIONS"),
+            non_empty("PREV_PKGPATH"),
+            values("PROVIDES"),
+            values("REQUIRES"),
+            values("SUPERSEDES"),
+            None,
+            None,
+            None,
+        ))
+    }
+
     /**
      * Read an optional metadata file.
      *
@@ -289,6 +399,14 @@ impl FileRead for InstalledPackage {
     }
 }
 
+impl TryFrom<&InstalledPackage> for Summary {
+    type Error = io::Error;
+
+    fn try_from(pkg: &InstalledPackage) -> io::Result<Self> {
+        pkg.to_summary()
+    }
+}
+
 /**
  * An iterator over the entries of a package database, returning either a
  * valid `InstalledPackage` handle, an `io::Error`, or None.
@@ -342,3 +460,41 @@ impl Iterator for PkgDB {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_summary() -> io::Result<()> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/pkgdb");
+        let mut db = PkgDB::open(path)?;
+        let pkg = db.next().ok_or(io::ErrorKind::NotFound)??;
+        let summary = Summary::try_from(&pkg)?;
+
+        /*
+         * Should be identical to the pkg_summary entry for the same package,
+         * other than the FILE_* variables and the additional REQUIRES,
+         * DEPENDS and CONFLICTS.
+         */
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/summary/mktool.txt"
+        );
+        let expected: Summary = fs::read_to_string(path)?
+            .lines()
+            .filter(|l| !l.starts_with("FILE_"))
+            .chain([
+                "CONFLICTS=mktool<1.0",
+                "DEPENDS=cwrappers>=20150314",
+                "REQUIRES=/usr/lib/libSystem.B.dylib",
+                "REQUIRES=/usr/lib/libiconv.2.dylib",
+            ])
+            .collect::<Vec<_>>()
+            .join("\n")
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        assert_eq!(summary, expected);
+        Ok(())
+    }
+}
@@ -73,6 +73,8 @@
  * [`dewey`]: crate::dewey
  */
 
+use crate::dewey::DeweyError;
+use crate::version::Version;
 use std::borrow::Borrow;
 use std::hash::{Hash, Hasher};
 use std::str::FromStr;
@@ -251,6 +253,26 @@ impl PkgName {
     pub fn pkgrevision(&self) -> Option<i64> {
         pkgrevision(self.pkgversion())
     }
+
+    /**
+     * Parse the `PKGVERSION` into a [`Version`] that can be ordered.
+     *
+     * ```
+     * use pkgsrc::PkgName;
+     *
+     * let old = PkgName::new("mktool-1.4.2");
+     * let new = PkgName::new("mktool-1.4.2nb1");
+     * assert!(old.version()? < new.version()?);
+     * # Ok::<(), pkgsrc::DeweyError>(())
+     * ```
+     *
+     * # Errors
+     *
+     * Returns [`DeweyError`] if a numeric component overflows [`i64`].
+     */
+    pub fn version(&self) -> Result<Version, DeweyError> {
+        Version::new(self.pkgversion())
+    }
 }
 
 impl From<&str> for PkgName {
@@ -0,0 +1,1063 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Plan binary package install, upgrade and removal transactions.
+ *
+ * A [`Planner`] holds the set of installed packages, usually read from a
+ * [`PkgDB`], and the set of packages available from a repository.  Given a
+ * [`Request`] it computes a [`Plan`]: an ordered list of [`Operation`]s
+ * that takes the system from the installed set to a consistent new set, or
+ * a [`PlanError`] explaining why that is not possible.
+ *
+ * As with `pkg_install`, only one version of each `PKGBASE` may be
+ * installed at a time.  The following are taken into account:
+ *
+ * * `DEPENDS` are resolved to the best available match, preferring
+ *   packages that are already installed or selected.
+ * * `REQUIRES` entries are satisfied by packages that `PROVIDES` them.
+ *   Paths that no known package provides are assumed to be provided by the
+ *   operating system.
+ * * `CONFLICTS` between any new package and any other package in the final
+ *   set are an error.
+ * * `SUPERSEDES` patterns cause matching installed packages to be removed
+ *   when the superseding package is installed.
+ * * When upgrading a package whose `PKGBASE` is no longer available, an
+ *   available package with a matching `PREV_PKGPATH` or `SUPERSEDES` is
+ *   used as its replacement.
+ *
+ * Installed packages that depend on a package being upgraded or replaced
+ * are themselves upgraded if their existing dependency would no longer be
+ * satisfied.  Removing a package also removes everything that depends on
+ * it.
+ *
+ * The planner is greedy: each dependency is resolved once, to the best
+ * match available at that point, and is not revisited.  This is the same
+ * approach taken by `pkgin`, and is sufficient for repositories built from
+ * a single consistent pkgsrc tree.
+ *
+ * Operations are ordered so that removals come first, with dependents
+ * removed before their dependencies, followed by installs and upgrades with
+ * dependencies before their dependents.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::plan::{Planner, Request};
+ * use pkgsrc::summary::Summary;
+ *
+ * /* The first entry is installed, the rest are available. */
+ * let input = "PKGNAME=gettext-lib-0.21\n\
+ *              \n\
+ *              PKGNAME=gettext-lib-0.22\n\
+ *              \n\
+ *              PKGNAME=glib2-2.82.0\n\
+ *              DEPENDS=gettext-lib>=0.22\n";
+ * let mut installed = Summary::from_reader(input.as_bytes())
+ *     .allow_incomplete(true)
+ *     .collect::<Result<Vec<_>, _>>()?;
+ * let available = installed.split_off(1);
+ * let planner = Planner::new(installed, available);
+ *
+ * let plan = planner.plan(&Request::new().install("glib2"))?;
+ * let ops: Vec<String> = plan.iter().map(|op| op.to_string()).collect();
+ * assert_eq!(
+ *     ops,
+ *     ["upgrade gettext-lib-0.21 to gettext-lib-0.22", "install glib2-2.82.0"]
+ * );
+ *
+ * let err = planner
+ *     .plan(&Request::new().install("gtk3"))
+ *     .expect_err("gtk3 is not available");
+ * assert_eq!(err.to_string(), "No available package matches \"gtk3\"");
+ * # Ok::<(), Box<dyn std::error::Error>>(())
+ * ```
+ */
+
+use crate::summary::Summary;
+use crate::{Pattern, PatternCache, PatternError, PkgDB, PkgName};
+use hashbrown::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use thiserror::Error;
+
+/**
+ * Reasons why a [`Request`] cannot be satisfied.
+ */
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PlanError {
+    /**
+     * No available package matches a requested install.
+     */
+    #[error("No available package matches \"{0}\"")]
+    NotFound(String),
+    /**
+     * No installed package matches a requested removal.
+     */
+    #[error("No installed package matches \"{0}\"")]
+    NotInstalled(String),
+    /**
+     * A dependency of a package to be installed is not available.
+     */
+    #[error("{pkgname} depends on \"{pattern}\", which is not available")]
+    Unsatisfiable {
+        /** The package with the dependency. */
+        pkgname: PkgName,
+        /** The `DEPENDS` pattern. */
+        pattern: String,
+    },
+    /**
+     * A dependency requires a different version of a package that has
+     * already been selected for this transaction.
+     */
+    #[error(
+        "{pkgname} depends on \"{pattern}\", but {selected} has already been selected"
+    )]
+    Incompatible {
+        /** The package with the dependency. */
+        pkgname: PkgName,
+        /** The `DEPENDS` pattern or `REQUIRES` path. */
+        pattern: String,
+        /** The package already selected with the same `PKGBASE`. */
+        selected: PkgName,
+    },
+    /**
+     * Replacing or removing a package would break an installed package that
+     * has no suitable upgrade.
+     */
+    #[error(
+        "{pkgname} depends on \"{pattern}\", which would no longer be satisfied after replacing {replaced}"
+    )]
+    Breaks {
+        /** The installed package that would be broken. */
+        pkgname: PkgName,
+        /** Its `DEPENDS` pattern. */
+        pattern: String,
+        /** The installed package that currently satisfies the pattern. */
+        replaced: PkgName,
+    },
+    /**
+     * A `REQUIRES` entry would no longer be provided by any package.
+     */
+    #[error("{pkgname} requires {path}, which would no longer be provided")]
+    MissingRequire {
+        /** The package with the requirement. */
+        pkgname: PkgName,
+        /** The required path. */
+        path: String,
+    },
+    /**
+     * Two packages in the final set conflict.
+     */
+    #[error("{pkgname} conflicts with {with} (CONFLICTS \"{pattern}\")")]
+    Conflict {
+        /** The package declaring the conflict. */
+        pkgname: PkgName,
+        /** Its `CONFLICTS` pattern. */
+        pattern: String,
+        /** The package it conflicts with. */
+        with: PkgName,
+    },
+    /**
+     * A transparent [`PatternError`], for invalid patterns in requests or
+     * package metadata.
+     */
+    #[error(transparent)]
+    Pattern(#[from] PatternError),
+}
+
+/**
+ * A set of changes to plan.  Requests are applied in the order removals,
+ * upgrades, then installs.
+ *
+ * Install and remove targets may be a full `PKGNAME`, a `PKGBASE`, or any
+ * [`Pattern`].
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Request {
+    install: Vec<String>,
+    remove: Vec<String>,
+    upgrade: bool,
+}
+
+impl Request {
+    /**
+     * Create an empty request.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Install the best available package matching `target`, unless an
+     * installed package already matches.
+     */
+    #[must_use]
+    pub fn install(mut self, target: impl Into<String>) -> Self {
+        self.install.push(target.into());
+        self
+    }
+
+    /**
+     * Remove the installed package matching `target`, along with every
+     * package that depends on it.
+     */
+    #[must_use]
+    pub fn remove(mut self, target: impl Into<String>) -> Self {
+        self.remove.push(target.into());
+        self
+    }
+
+    /**
+     * Upgrade every installed package that has a newer version or a
+     * replacement available.
+     */
+    #[must_use]
+    pub fn upgrade_all(mut self) -> Self {
+        self.upgrade = true;
+        self
+    }
+}
+
+/**
+ * A single step in a [`Plan`].
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation<'a> {
+    /// Remove an installed package.
+    Remove(&'a Summary),
+    /// Install an available package.
+    Install(&'a Summary),
+    /// Replace an installed package with a different version of the same
+    /// `PKGBASE`.
+    Upgrade {
+        /// The installed package.
+        from: &'a Summary,
+        /// The available package replacing it.
+        to: &'a Summary,
+    },
+}
+
+impl fmt::Display for Operation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Remove(s) => write!(f, "remove {}", s.pkgname()),
+            Operation::Install(s) => write!(f, "install {}", s.pkgname()),
+            Operation::Upgrade { from, to } => {
+                write!(f, "upgrade {} to {}", from.pkgname(), to.pkgname())
+            }
+        }
+    }
+}
+
+/**
+ * An ordered list of [`Operation`]s returned by [`Planner::plan`].
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Plan<'a> {
+    ops: Vec<Operation<'a>>,
+}
+
+impl<'a> Plan<'a> {
+    /**
+     * Return the operations in the order they should be performed.
+     */
+    #[must_use]
+    pub fn operations(&self) -> &[Operation<'a>] {
+        &self.ops
+    }
+
+    /**
+     * Return an iterator over the operations.
+     */
+    pub fn iter(&self) -> std::slice::Iter<'_, Operation<'a>> {
+        self.ops.iter()
+    }
+
+    /**
+     * Return the number of operations.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /**
+     * Return true if there is nothing to do.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+impl<'a, 'p> IntoIterator for &'p Plan<'a> {
+    type Item = &'p Operation<'a>;
+    type IntoIter = std::slice::Iter<'p, Operation<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Display for Plan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for op in &self.ops {
+            writeln!(f, "{op}")?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Computes [`Plan`]s from a set of installed and available packages.  See
+ * the [module documentation](self) for details.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Planner {
+    installed: Vec<Summary>,
+    available: Vec<Summary>,
+}
+
+impl Planner {
+    /**
+     * Create a new [`Planner`] from installed and available packages.
+     */
+    #[must_use]
+    pub fn new(
+        installed: impl IntoIterator<Item = Summary>,
+        available: impl IntoIterator<Item = Summary>,
+    ) -> Self {
+        Self {
+            installed: installed.into_iter().collect(),
+            available: available.into_iter().collect(),
+        }
+    }
+
+    /**
+     * Create a new [`Planner`] using the packages installed in `pkgdb`.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the package database cannot be read.  See
+     * [`InstalledPackage::to_summary`].
+     *
+     * [`InstalledPackage::to_summary`]: crate::pkgdb::InstalledPackage::to_summary
+     */
+    pub fn from_pkgdb(
+        pkgdb: PkgDB,
+        available: impl IntoIterator<Item = Summary>,
+    ) -> io::Result<Self> {
+        let mut installed = Vec::new();
+        for pkg in pkgdb {
+            installed.push(pkg?.to_summary()?);
+        }
+        Ok(Self::new(installed, available))
+    }
+
+    /**
+     * Return the installed packages.
+     */
+    #[must_use]
+    pub fn installed(&self) -> &[Summary] {
+        &self.installed
+    }
+
+    /**
+     * Return the available packages.
+     */
+    #[must_use]
+    pub fn available(&self) -> &[Summary] {
+        &self.available
+    }
+
+    /**
+     * Compute a [`Plan`] for `request`.
+     *
+     * # Errors
+     *
+     * Returns a [`PlanError`] describing why the request cannot be
+     * satisfied.
+     */
+    pub fn plan(&self, request: &Request) -> Result<Plan<'_>, PlanError> {
+        let mut solver = Solver::new(self);
+        for target in &request.remove {
+            solver.remove(target)?;
+        }
+        solver.cascade()?;
+        if request.upgrade {
+            solver.upgrade_all()?;
+        }
+        for target in &request.install {
+            solver.install(target)?;
+        }
+        solver.process()?;
+        solver.fixup()?;
+        solver.check_conflicts()?;
+        Ok(solver.into_plan())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Pkg {
+    Installed(usize),
+    Available(usize),
+}
+
+struct Solver<'a> {
+    installed: &'a [Summary],
+    available: &'a [Summary],
+    cache: PatternCache,
+    installed_by_base: HashMap<&'a str, Vec<usize>>,
+    available_by_base: HashMap<&'a str, Vec<usize>>,
+    providers: HashMap<&'a str, Vec<Pkg>>,
+    /// The final set of packages, keyed by `PKGBASE`.
+    chosen: HashMap<&'a str, Pkg>,
+    /// Newly selected available packages whose requirements are unchecked.
+    queue: Vec<usize>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(planner: &'a Planner) -> Self {
+        let mut solver = Self {
+            installed: &planner.installed,
+            available: &planner.available,
+            cache: PatternCache::new(),
+            installed_by_base: HashMap::new(),
+            available_by_base: HashMap::new(),
+            providers: HashMap::new(),
+            chosen: HashMap::new(),
+            queue: Vec::new(),
+        };
+        for (i, s) in planner.installed.iter().enumerate() {
+            let base = s.pkgname().pkgbase();
+            solver.installed_by_base.entry(base).or_default().push(i);
+            solver.chosen.insert(base, Pkg::Installed(i));
+            for path in s.provides().unwrap_or_default() {
+                solver
+                    .providers
+                    .entry(path)
+                    .or_default()
+                    .push(Pkg::Installed(i));
+            }
+        }
+        for (i, s) in planner.available.iter().enumerate() {
+            let base = s.pkgname().pkgbase();
+            solver.available_by_base.entry(base).or_default().push(i);
+            for path in s.provides().unwrap_or_default() {
+                solver
+                    .providers
+                    .entry(path)
+                    .or_default()
+                    .push(Pkg::Available(i));
+            }
+        }
+        solver
+    }
+
+    fn get(&self, pkg: Pkg) -> &'a Summary {
+        match pkg {
+            Pkg::Installed(i) => &self.installed[i],
+            Pkg::Available(i) => &self.available[i],
+        }
+    }
+
+    fn base(&self, pkg: Pkg) -> &'a str {
+        self.get(pkg).pkgname().pkgbase()
+    }
+
+    fn is_chosen(&self, pkg: Pkg) -> bool {
+        self.chosen.get(self.base(pkg)) == Some(&pkg)
+    }
+
+    fn pattern(&mut self, pattern: &str) -> Result<Pattern, PlanError> {
+        Ok(self.cache.compile(pattern)?.clone())
+    }
+
+    /*
+     * Return the chosen packages in PKGNAME order, so that results do not
+     * depend on hash ordering.
+     */
+    fn chosen_sorted(&self) -> Vec<Pkg> {
+        let mut pkgs: Vec<Pkg> = self.chosen.values().copied().collect();
+        pkgs.sort_by(|a, b| self.get(*a).pkgname().cmp(self.get(*b).pkgname()));
+        pkgs
+    }
+
+    /*
+     * Return every chosen package matching pattern.
+     */
+    fn chosen_matches(&self, pattern: &Pattern) -> Vec<Pkg> {
+        match pattern.pkgbases() {
+            Some(bases) => bases
+                .iter()
+                .filter_map(|b| self.chosen.get(b).copied())
+                .filter(|&p| pattern.matches(self.get(p).pkgname().pkgname()))
+                .collect(),
+            None => self
+                .chosen_sorted()
+                .into_iter()
+                .filter(|&p| pattern.matches(self.get(p).pkgname().pkgname()))
+                .collect(),
+        }
+    }
+
+    /*
+     * Return an installed package that matches pattern, regardless of
+     * whether it is still chosen.
+     */
+    fn installed_match(&self, pattern: &Pattern) -> Option<usize> {
+        let matches =
+            |&i: &usize| pattern.matches(self.installed[i].pkgname().pkgname());
+        match pattern.pkgbases() {
+            Some(bases) => bases
+                .iter()
+                .filter_map(|b| self.installed_by_base.get(b))
+                .flatten()
+                .copied()
+                .find(matches),
+            None => (0..self.installed.len()).find(matches),
+        }
+    }
+
+    /*
+     * Return the best available package matching pattern.
+     */
+    fn best_available(
+        &self,
+        pattern: &Pattern,
+    ) -> Result<Option<usize>, PlanError> {
+        let candidates: Box<dyn Iterator<Item = usize>> =
+            match pattern.pkgbases() {
+                Some(bases) => Box::new(
+                    bases
+                        .into_iter()
+                        .filter_map(|b| self.available_by_base.get(b))
+                        .flatten()
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                ),
+                None => Box::new(0..self.available.len()),
+            };
+        let mut best: Option<(&str, usize)> = None;
+        for i in candidates {
+            let name = self.available[i].pkgname().pkgname();
+            let current = best.map(|(n, _)| n);
+            let new = pattern.best_match(current, name)?;
+            if new != current {
+                best = new.map(|n| (n, i));
+            }
+        }
+        Ok(best.map(|(_, i)| i))
+    }
+
+    /*
+     * Resolve a request target to a pattern.  A target that matches nothing
+     * as-is is retried as a PKGBASE, as pkg_install does.
+     */
+    fn target_pattern(
+        &mut self,
+        target: &str,
+        exists: impl Fn(&Self, &Pattern) -> bool,
+    ) -> Result<Option<Pattern>, PlanError> {
+        let pattern = self.pattern(target)?;
+        if exists(self, &pattern) {
+            return Ok(Some(pattern));
+        }
+        let pattern = self.pattern(&format!("{target}-[0-9]*"))?;
+        Ok(exists(self, &pattern).then_some(pattern))
+    }
+
+    /*
+     * Add an available package to the final set, replacing any chosen
+     * package with the same PKGBASE and removing any installed packages it
+     * supersedes.  If requester is set, selecting a different version of an
+     * already selected PKGBASE is an error rather than a replacement.
+     */
+    fn select(
+        &mut self,
+        id: usize,
+        requester: Option<(&'a Summary, &str)>,
+    ) -> Result<(), PlanError> {
+        let pkg = &self.available[id];
+        let base = pkg.pkgname().pkgbase();
+        match self.chosen.get(base) {
+            Some(&Pkg::Available(cur)) if cur == id => return Ok(()),
+            Some(&Pkg::Available(cur)) => {
+                if let Some((by, pattern)) = requester {
+                    return Err(PlanError::Incompatible {
+                        pkgname: by.pkgname().clone(),
+                        pattern: pattern.to_string(),
+                        selected: self.available[cur].pkgname().clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+        self.chosen.insert(base, Pkg::Available(id));
+        self.queue.push(id);
+        for s in pkg.supersedes().unwrap_or_default() {
+            let pattern = self.pattern(s)?;
+            for old in self.chosen_matches(&pattern) {
+                if matches!(old, Pkg::Installed(_)) {
+                    self.chosen.remove(self.base(old));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, target: &str) -> Result<(), PlanError> {
+        let found = |s: &Self, p: &Pattern| s.installed_match(p).is_some();
+        let Some(pattern) = self.target_pattern(target, found)? else {
+            return Err(PlanError::NotInstalled(target.to_string()));
+        };
+        for pkg in self.chosen_matches(&pattern) {
+            self.chosen.remove(self.base(pkg));
+        }
+        Ok(())
+    }
+
+    /*
+     * Remove every installed package whose dependencies were satisfied
+     * before but no longer are, until nothing changes.
+     */
+    fn cascade(&mut self) -> Result<(), PlanError> {
+        loop {
+            let mut changed = false;
+            for pkg in self.chosen_sorted() {
+                if self.broken(pkg)?.is_some() {
+                    self.chosen.remove(self.base(pkg));
+                    changed = true;
+                }
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+    }
+
+    /*
+     * For an installed package, return the first DEPENDS or REQUIRES entry
+     * that was satisfied by the installed set but is not by the final set,
+     * along with the package that used to satisfy it, if any.
+     */
+    fn broken(
+        &mut self,
+        pkg: Pkg,
+    ) -> Result<Option<(&'a str, Option<usize>)>, PlanError> {
+        let summary = self.get(pkg);
+        for dep in summary.depends().unwrap_or_default() {
+            let pattern = self.pattern(dep)?;
+            if !self.chosen_matches(&pattern).is_empty() {
+                continue;
+            }
+            if let Some(orig) = self.installed_match(&pattern) {
+                return Ok(Some((dep.as_str(), Some(orig))));
+            }
+        }
+        for path in summary.requires().unwrap_or_default() {
+            let Some(providers) = self.providers.get(path.as_str()) else {
+                continue;
+            };
+            let was = providers.iter().any(|p| matches!(p, Pkg::Installed(_)));
+            if was && !providers.iter().any(|&p| self.is_chosen(p)) {
+                return Ok(Some((path.as_str(), None)));
+            }
+        }
+        Ok(None)
+    }
+
+    /*
+     * Return the best replacement for an installed package: a newer
+     * version of the same PKGBASE, or if the PKGBASE is no longer available
+     * a package that supersedes it or was moved from its PKGPATH.
+     */
+    fn replacement(&mut self, id: usize) -> Result<Option<usize>, PlanError> {
+        let inst = &self.installed[id];
+        let base = inst.pkgname().pkgbase();
+        if self.available_by_base.contains_key(base) {
+            let pattern = self
+                .pattern(&format!("{base}>{}", inst.pkgname().pkgversion()))?;
+            return self.best_available(&pattern);
+        }
+        let mut best: Option<usize> = None;
+        for (i, avail) in self.available.iter().enumerate() {
+            let moved = avail.prev_pkgpath() == Some(inst.pkgpath());
+            let mut supersedes = false;
+            for s in avail.supersedes().unwrap_or_default() {
+                let pattern = self.cache.compile(s)?;
+                supersedes |= pattern.matches(inst.pkgname().pkgname());
+            }
+            if !moved && !supersedes {
+                continue;
+            }
+            let newer = match best {
+                None => true,
+                Some(b) => {
+                    let cur = self.available[b].pkgname();
+                    avail.pkgname().version().map_err(PatternError::from)?
+                        > cur.version().map_err(PatternError::from)?
+                }
+            };
+            if newer {
+                best = Some(i);
+            }
+        }
+        Ok(best)
+    }
+
+    fn upgrade_all(&mut self) -> Result<(), PlanError> {
+        for pkg in self.chosen_sorted() {
+            let Pkg::Installed(id) = pkg else {
+                continue;
+            };
+            if let Some(new) = self.replacement(id)? {
+                let old = self.base(pkg);
+                if self.available[new].pkgname().pkgbase() != old {
+                    self.chosen.remove(old);
+                }
+                self.select(new, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn install(&mut self, target: &str) -> Result<(), PlanError> {
+        let found = |s: &Self, p: &Pattern| {
+            !s.chosen_matches(p).is_empty()
+                || s.best_available(p).is_ok_and(|b| b.is_some())
+        };
+        let Some(pattern) = self.target_pattern(target, found)? else {
+            return Err(PlanError::NotFound(target.to_string()));
+        };
+        if !self.chosen_matches(&pattern).is_empty() {
+            return Ok(());
+        }
+        match self.best_available(&pattern)? {
+            Some(id) => self.select(id, None),
+            None => Err(PlanError::NotFound(target.to_string())),
+        }
+    }
+
+    /*
+     * Satisfy the DEPENDS and REQUIRES of every newly selected package.
+     */
+    fn process(&mut self) -> Result<(), PlanError> {
+        while let Some(id) = self.queue.pop() {
+            if !self.is_chosen(Pkg::Available(id)) {
+                continue;
+            }
+            let pkg = &self.available[id];
+            for dep in pkg.depends().unwrap_or_default() {
+                let pattern = self.pattern(dep)?;
+                if !self.chosen_matches(&pattern).is_empty() {
+                    continue;
+                }
+                let Some(best) = self.best_available(&pattern)? else {
+                    return Err(PlanError::Unsatisfiable {
+                        pkgname: pkg.pkgname().clone(),
+                        pattern: dep.clone(),
+                    });
+                };
+                self.select(best, Some((pkg, dep)))?;
+            }
+            for path in pkg.requires().unwrap_or_default() {
+                let Some(providers) = self.providers.get(path.as_str()) else {
+                    continue;
+                };
+                if providers.iter().any(|&p| self.is_chosen(p)) {
+                    continue;
+                }
+                let Some(&Pkg::Available(best)) =
+                    providers.iter().find(|p| matches!(p, Pkg::Available(_)))
+                else {
+                    return Err(PlanError::MissingRequire {
+                        pkgname: pkg.pkgname().clone(),
+                        path: path.clone(),
+                    });
+                };
+                self.select(best, Some((pkg, path)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * Upgrade installed packages broken by earlier replacements, until
+     * nothing changes.
+     */
+    fn fixup(&mut self) -> Result<(), PlanError> {
+        loop {
+            let mut changed = false;
+            for pkg in self.chosen_sorted() {
+                let Pkg::Installed(id) = pkg else {
+                    continue;
+                };
+                let Some((entry, orig)) = self.broken(pkg)? else {
+                    continue;
+                };
+                let base = self.base(pkg);
+                let pattern = self.pattern(&format!("{base}-[0-9]*"))?;
+                let upgrade = self.best_available(&pattern)?.filter(|&new| {
+                    self.available[new].pkgname()
+                        != self.installed[id].pkgname()
+                });
+                let Some(new) = upgrade else {
+                    let pkgname = self.installed[id].pkgname().clone();
+                    return Err(match orig {
+                        Some(orig) => PlanError::Breaks {
+                            pkgname,
+                            pattern: entry.to_string(),
+                            replaced: self.installed[orig].pkgname().clone(),
+                        },
+                        None => PlanError::MissingRequire {
+                            pkgname,
+                            path: entry.to_string(),
+                        },
+                    });
+                };
+                self.select(new, None)?;
+                self.process()?;
+                changed = true;
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+    }
+
+    fn check_conflicts(&mut self) -> Result<(), PlanError> {
+        for pkg in self.chosen_sorted() {
+            let summary = self.get(pkg);
+            for c in summary.conflicts().unwrap_or_default() {
+                let pattern = self.pattern(c)?;
+                for other in self.chosen_matches(&pattern) {
+                    let both_installed = matches!(pkg, Pkg::Installed(_))
+                        && matches!(other, Pkg::Installed(_));
+                    if other == pkg || both_installed {
+                        continue;
+                    }
+                    return Err(PlanError::Conflict {
+                        pkgname: summary.pkgname().clone(),
+                        pattern: c.clone(),
+                        with: self.get(other).pkgname().clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * Order pkgs so that each comes after the packages among them that it
+     * depends on.  Cycles are broken arbitrarily.
+     */
+    fn dependency_order(&mut self, pkgs: &[Pkg]) -> Vec<Pkg> {
+        let set: HashSet<Pkg> = pkgs.iter().copied().collect();
+        let mut edges: HashMap<Pkg, Vec<Pkg>> = HashMap::new();
+        for &pkg in pkgs {
+            let mut deps = Vec::new();
+            for dep in self.get(pkg).depends().unwrap_or_default() {
+                let Ok(pattern) = self.pattern(dep) else {
+                    continue;
+                };
+                let targets: Vec<Pkg> = match pkg {
+                    Pkg::Available(_) => self.chosen_matches(&pattern),
+                    Pkg::Installed(_) => self
+                        .installed_match(&pattern)
+                        .map(Pkg::Installed)
+                        .into_iter()
+                        .collect(),
+                };
+                deps.extend(targets.into_iter().filter(|t| set.contains(t)));
+            }
+            for path in self.get(pkg).requires().unwrap_or_default() {
+                if let Some(providers) = self.providers.get(path.as_str()) {
+                    deps.extend(providers.iter().filter(|p| set.contains(*p)));
+                }
+            }
+            edges.insert(pkg, deps);
+        }
+
+        let mut done: HashSet<Pkg> = HashSet::new();
+        let mut order = Vec::with_capacity(pkgs.len());
+        for &root in pkgs {
+            let mut stack = vec![(root, 0)];
+            if !done.insert(root) {
+                continue;
+            }
+            while let Some((pkg, next)) = stack.last_mut() {
+                let pkg = *pkg;
+                match edges[&pkg].get(*next) {
+                    Some(&dep) => {
+                        *next += 1;
+                        if done.insert(dep) {
+                            stack.push((dep, 0));
+                        }
+                    }
+                    None => {
+                        order.push(pkg);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    fn into_plan(mut self) -> Plan<'a> {
+        let mut removed: Vec<Pkg> = (0..self.installed.len())
+            .map(Pkg::Installed)
+            .filter(|&p| !self.chosen.contains_key(self.base(p)))
+            .collect();
+        removed
+            .sort_by(|a, b| self.get(*a).pkgname().cmp(self.get(*b).pkgname()));
+        let added: Vec<Pkg> = self
+            .chosen_sorted()
+            .into_iter()
+            .filter(|p| matches!(p, Pkg::Available(_)))
+            .collect();
+
+        let mut ops = Vec::new();
+        for pkg in self.dependency_order(&removed).into_iter().rev() {
+            ops.push(Operation::Remove(self.get(pkg)));
+        }
+        for pkg in self.dependency_order(&added) {
+            let to = self.get(pkg);
+            let base = to.pkgname().pkgbase();
+            let from = self
+                .installed_by_base
+                .get(base)
+                .and_then(|ids| ids.first())
+                .map(|&i| &self.installed[i]);
+            match from {
+                Some(from) if from.pkgname() == to.pkgname() => {}
+                Some(from) => ops.push(Operation::Upgrade { from, to }),
+                None => ops.push(Operation::Install(to)),
+            }
+        }
+        Plan { ops }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::test_entry;
+
+    fn ops(plan: &Plan<'_>) -> Vec<String> {
+        plan.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn install_deps_order() -> Result<(), PlanError> {
+        let planner = Planner::new(
+            [],
+            [
+                test_entry("a-1", &["DEPENDS=b>=1", "DEPENDS=c-[0-9]*"]),
+                test_entry("b-1", &["DEPENDS=c>=1"]),
+                test_entry("b-2", &["DEPENDS=c>=1"]),
+                test_entry("c-1", &["REQUIRES=/usr/pkg/lib/libd.so"]),
+                test_entry("d-1", &["PROVIDES=/usr/pkg/lib/libd.so"]),
+            ],
+        );
+        let plan = planner.plan(&Request::new().install("a"))?;
+        assert_eq!(
+            ops(&plan),
+            ["install d-1", "install c-1", "install b-2", "install a-1"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn upgrade_and_replace() -> Result<(), PlanError> {
+        let planner = Planner::new(
+            [
+                test_entry("a-1", &[]),
+                test_entry("b-1", &["DEPENDS=a-1"]),
+                test_entry("old-1", &[]),
+                test_entry("gone-1", &[]),
+            ],
+            [
+                test_entry("a-2", &[]),
+                test_entry("b-2", &["DEPENDS=a>=2"]),
+                test_entry("new-2", &["PREV_PKGPATH=devel/old"]),
+            ],
+        );
+        let plan = planner.plan(&Request::new().upgrade_all())?;
+        assert_eq!(
+            ops(&plan),
+            [
+                "remove old-1",
+                "upgrade a-1 to a-2",
+                "upgrade b-1 to b-2",
+                "install new-2",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn breaks_and_conflicts() {
+        let planner = Planner::new(
+            [test_entry("a-1", &[]), test_entry("b-1", &["DEPENDS=a-1"])],
+            [
+                test_entry("a-2", &[]),
+                test_entry("c-1", &["CONFLICTS=b-[0-9]*"]),
+                test_entry("d-1", &["DEPENDS=a>=2"]),
+            ],
+        );
+        let err = planner.plan(&Request::new().install("d")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "b-1 depends on \"a-1\", which would no longer be satisfied \
+             after replacing a-1"
+        );
+        let err = planner.plan(&Request::new().install("c")).unwrap_err();
+        assert!(matches!(err, PlanError::Conflict { .. }));
+        let err = planner.plan(&Request::new().install("e")).unwrap_err();
+        assert!(matches!(err, PlanError::NotFound(_)));
+    }
+
+    #[test]
+    fn remove_and_supersede() -> Result<(), PlanError> {
+        let planner = Planner::new(
+            [
+                test_entry("a-1", &[]),
+                test_entry("b-1", &["DEPENDS=a-[0-9]*"]),
+                test_entry("c-1", &["DEPENDS=b-[0-9]*"]),
+                test_entry("x-1", &[]),
+            ],
+            [test_entry("y-1", &["SUPERSEDES=x-[0-9]*"])],
+        );
+        let plan = planner.plan(&Request::new().remove("a").install("y"))?;
+        assert_eq!(
+            ops(&plan),
+            [
+                "remove x-1",
+                "remove c-1",
+                "remove b-1",
+                "remove a-1",
+                "install y-1"
+            ]
+        );
+        Ok(())
+    }
+}
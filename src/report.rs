@@ -0,0 +1,793 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Generate pbulk-compatible bulk build reports.
+ *
+ * A [`BuildReport`] combines resolved scan output with the result of each
+ * package build, as supplied by any type implementing [`BuildResults`].  It
+ * derives the final pbulk `BUILD_STATUS` of every package, propagating
+ * failures to the packages that depend on them, and renders:
+ *
+ * - the machine-readable `meta/report` file with [`BuildReport::meta`],
+ * - the `report.txt` summary mailed after each bulk build with
+ *   [`BuildReport::text`],
+ * - a static HTML report with a page per package with
+ *   [`BuildReport::write_html`].
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::report::{BuildReport, BuildResult};
+ * use pkgsrc::graph::BuildStatus;
+ * use pkgsrc::ScanIndex;
+ * use std::collections::HashMap;
+ *
+ * let scan = "PKGNAME=gettext-lib-0.22\n\
+ *             PKG_LOCATION=devel/gettext-lib\n\
+ *             MAINTAINER=joe@example.org\n\
+ *             PKGNAME=glib2-2.82.0\n\
+ *             PKG_LOCATION=devel/glib2\n\
+ *             DEPENDS=gettext-lib-0.22\n";
+ * let pkgs = ScanIndex::from_reader(scan.as_bytes())
+ *     .collect::<Result<Vec<_>, _>>()?;
+ *
+ * let results = HashMap::from([(
+ *     "gettext-lib-0.22".to_string(),
+ *     BuildResult::failure().with_log("build.log"),
+ * )]);
+ * let report = BuildReport::new(pkgs, &results)?;
+ * assert_eq!(report.status(0), BuildStatus::Failed);
+ * assert_eq!(report.status(1), BuildStatus::IndirectFailed);
+ * assert_eq!(report.breaks(0), 1);
+ *
+ * let text = report.text().to_string();
+ * assert!(text.contains("  Failed to build:                 1\n"));
+ * assert!(text.contains(
+ *     "devel/gettext-lib                          1 joe@example.org\n"
+ * ));
+ * # Ok::<(), Box<dyn std::error::Error>>(())
+ * ```
+ */
+
+use crate::graph::{BuildGraph, BuildStatus, GraphError};
+use crate::{PkgName, ScanIndex};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::Path;
+
+mod html;
+
+/**
+ * The number of packages listed under "Packages breaking the most other
+ * packages" in the text report.
+ */
+pub const TOP_BREAKERS: usize = 10;
+
+/**
+ * The result of building a single package, as reported by the builder.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BuildResult {
+    failed: bool,
+    logs: Vec<String>,
+}
+
+impl BuildResult {
+    /**
+     * A package that built successfully.
+     */
+    #[must_use]
+    pub fn success() -> Self {
+        Self::default()
+    }
+
+    /**
+     * A package that failed to build.
+     */
+    #[must_use]
+    pub fn failure() -> Self {
+        Self {
+            failed: true,
+            logs: Vec::new(),
+        }
+    }
+
+    /**
+     * Add a log file name, relative to the package's log directory, to be
+     * linked from the HTML report.
+     */
+    #[must_use]
+    pub fn with_log(mut self, name: impl Into<String>) -> Self {
+        self.logs.push(name.into());
+        self
+    }
+
+    /**
+     * Return true if the package failed to build.
+     */
+    #[must_use]
+    pub fn is_failure(&self) -> bool {
+        self.failed
+    }
+
+    /**
+     * Return the log file names.
+     */
+    #[must_use]
+    pub fn logs(&self) -> &[String] {
+        &self.logs
+    }
+}
+
+/**
+ * A source of build results.
+ *
+ * Implemented for [`HashMap`]s keyed by `PKGNAME`, and for closures, so that
+ * a builder can supply results from whatever it uses to track them.
+ * Packages with no result that are not excluded by a failed or prefailed
+ * dependency are reported as [`BuildStatus::Pending`].
+ */
+pub trait BuildResults {
+    /**
+     * Return the build result for a package, if it was built.
+     */
+    fn result(&self, pkgname: &PkgName) -> Option<BuildResult>;
+}
+
+impl<S: BuildHasher> BuildResults for HashMap<String, BuildResult, S> {
+    fn result(&self, pkgname: &PkgName) -> Option<BuildResult> {
+        self.get(pkgname.pkgname()).cloned()
+    }
+}
+
+impl<F> BuildResults for F
+where
+    F: Fn(&PkgName) -> Option<BuildResult>,
+{
+    fn result(&self, pkgname: &PkgName) -> Option<BuildResult> {
+        self(pkgname)
+    }
+}
+
+/**
+ * Failure counts for a single `MAINTAINER`.  Returned by
+ * [`BuildReport::maintainers`].
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MaintainerSummary<'a> {
+    maintainer: &'a str,
+    failed: usize,
+    breaks: usize,
+}
+
+impl<'a> MaintainerSummary<'a> {
+    /**
+     * Return the maintainer address.
+     */
+    #[must_use]
+    pub fn maintainer(&self) -> &'a str {
+        self.maintainer
+    }
+
+    /**
+     * Return the number of packages that failed to build.
+     */
+    #[must_use]
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /**
+     * Return the total number of packages broken by those failures.
+     */
+    #[must_use]
+    pub fn breaks(&self) -> usize {
+        self.breaks
+    }
+}
+
+/**
+ * The results of a bulk build.  See the [module documentation](self) for
+ * details.
+ *
+ * Package ids are indices into the `packages` passed to
+ * [`BuildReport::new`].
+ */
+#[derive(Clone, Debug)]
+pub struct BuildReport {
+    packages: Vec<ScanIndex>,
+    results: Vec<Option<BuildResult>>,
+    status: Vec<BuildStatus>,
+    depth: Vec<usize>,
+    breaks: Vec<usize>,
+    platform: Option<String>,
+    compiler: Option<String>,
+    build_start: Option<String>,
+    build_end: Option<String>,
+    base_url: Option<String>,
+    log_url: Option<String>,
+}
+
+impl BuildReport {
+    /**
+     * Create a report from resolved scan records and their build results.
+     *
+     * Packages with a `PKG_SKIP_REASON` or `PKG_FAIL_REASON` are
+     * [`BuildStatus::Prefailed`], and their dependents
+     * [`BuildStatus::IndirectPrefailed`].  Dependents of packages that
+     * failed to build are [`BuildStatus::IndirectFailed`].  Any result
+     * supplied for such packages is ignored.
+     *
+     * # Errors
+     *
+     * Returns [`GraphError`] if the resolved dependencies are inconsistent,
+     * as for [`BuildGraph::new`].
+     */
+    pub fn new<R>(
+        packages: Vec<ScanIndex>,
+        results: &R,
+    ) -> Result<Self, GraphError>
+    where
+        R: BuildResults + ?Sized,
+    {
+        let graph = BuildGraph::new(&packages)?;
+        let mut status = vec![BuildStatus::Pending; packages.len()];
+        let mut kept = vec![None; packages.len()];
+        for &id in graph.order() {
+            let deps = graph.depends(id).iter().map(|&d| status[d]);
+            status[id] = if graph.is_prefailed(id) {
+                BuildStatus::Prefailed
+            } else if deps.clone().any(|s| {
+                matches!(
+                    s,
+                    BuildStatus::Prefailed | BuildStatus::IndirectPrefailed
+                )
+            }) {
+                BuildStatus::IndirectPrefailed
+            } else if deps.clone().any(|s| {
+                matches!(s, BuildStatus::Failed | BuildStatus::IndirectFailed)
+            }) {
+                BuildStatus::IndirectFailed
+            } else {
+                match results.result(graph.pkgname(id)) {
+                    Some(r) => {
+                        let s = if r.is_failure() {
+                            BuildStatus::Failed
+                        } else {
+                            BuildStatus::Done
+                        };
+                        kept[id] = Some(r);
+                        s
+                    }
+                    None => BuildStatus::Pending,
+                }
+            };
+        }
+
+        /*
+         * A package breaks every transitive dependent that was not built
+         * as a result, whichever failure reached it first.
+         */
+        let mut breaks = vec![0; packages.len()];
+        let mut visited = vec![0u32; packages.len()];
+        let mut queue = VecDeque::new();
+        for (id, epoch) in (0..packages.len()).zip(1u32..) {
+            if !matches!(
+                status[id],
+                BuildStatus::Failed | BuildStatus::Prefailed
+            ) {
+                continue;
+            }
+            visited[id] = epoch;
+            queue.push_back(id);
+            while let Some(node) = queue.pop_front() {
+                for &r in graph.dependents(node) {
+                    if visited[r] != epoch {
+                        visited[r] = epoch;
+                        queue.push_back(r);
+                        breaks[id] += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            depth: graph.depths(),
+            packages,
+            results: kept,
+            status,
+            breaks,
+            platform: None,
+            compiler: None,
+            build_start: None,
+            build_end: None,
+            base_url: None,
+            log_url: None,
+        })
+    }
+
+    /**
+     * Set the platform line of the text report, for example
+     * `NetBSD 10.0/x86_64`.
+     */
+    #[must_use]
+    pub fn with_platform(mut self, platform: impl Into<String>) -> Self {
+        self.platform = Some(platform.into());
+        self
+    }
+
+    /**
+     * Set the compiler shown in the text report.
+     */
+    #[must_use]
+    pub fn with_compiler(mut self, compiler: impl Into<String>) -> Self {
+        self.compiler = Some(compiler.into());
+        self
+    }
+
+    /**
+     * Set the build start and end times shown in the reports.  These are
+     * displayed as given.
+     */
+    #[must_use]
+    pub fn with_build_time(
+        mut self,
+        start: impl Into<String>,
+        end: impl Into<String>,
+    ) -> Self {
+        self.build_start = Some(start.into());
+        self.build_end = Some(end.into());
+        self
+    }
+
+    /**
+     * Set the URL that the report will be published under.  The text
+     * report links to `meta/report.html` and `meta/report.bz2` below it.
+     */
+    #[must_use]
+    pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /**
+     * Set the URL of the directory containing per-package log directories,
+     * used by the HTML report.  Defaults to two levels above the package
+     * pages, matching pbulk's layout when the report is written to `meta`.
+     */
+    #[must_use]
+    pub fn with_log_url(mut self, url: impl Into<String>) -> Self {
+        self.log_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /**
+     * Return the number of packages in the report.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    /**
+     * Return true if the report contains no packages.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    /**
+     * Return the scan records.
+     */
+    #[must_use]
+    pub fn packages(&self) -> &[ScanIndex] {
+        &self.packages
+    }
+
+    /**
+     * Return the final status of a package.
+     *
+     * # Panics
+     *
+     * Panics if `id` is out of range.  The same applies to all other
+     * methods that take a package id.
+     */
+    #[must_use]
+    pub fn status(&self, id: usize) -> BuildStatus {
+        self.status[id]
+    }
+
+    /**
+     * Return the build result of a package, if one was used.
+     */
+    #[must_use]
+    pub fn result(&self, id: usize) -> Option<&BuildResult> {
+        self.results[id].as_ref()
+    }
+
+    /**
+     * Return the pbulk `PKG_DEPTH` of a package.
+     */
+    #[must_use]
+    pub fn depth(&self, id: usize) -> usize {
+        self.depth[id]
+    }
+
+    /**
+     * Return the number of packages that were not built because this
+     * package failed or was prefailed.  Always zero for other packages.
+     */
+    #[must_use]
+    pub fn breaks(&self, id: usize) -> usize {
+        self.breaks[id]
+    }
+
+    /**
+     * Return the number of packages with the given status.
+     */
+    #[must_use]
+    pub fn count(&self, status: BuildStatus) -> usize {
+        self.status.iter().filter(|&&s| s == status).count()
+    }
+
+    /**
+     * Return failure counts for every maintainer with at least one failed
+     * package, most failures first.
+     */
+    #[must_use]
+    pub fn maintainers(&self) -> Vec<MaintainerSummary<'_>> {
+        let mut by: HashMap<&str, MaintainerSummary<'_>> = HashMap::new();
+        for id in self.failed() {
+            let maintainer = self.maintainer(id);
+            let entry = by.entry(maintainer).or_insert(MaintainerSummary {
+                maintainer,
+                failed: 0,
+                breaks: 0,
+            });
+            entry.failed += 1;
+            entry.breaks += self.breaks[id];
+        }
+        let mut list: Vec<_> = by.into_values().collect();
+        list.sort_by(|a, b| {
+            b.failed
+                .cmp(&a.failed)
+                .then(b.breaks.cmp(&a.breaks))
+                .then(a.maintainer.cmp(b.maintainer))
+        });
+        list
+    }
+
+    /**
+     * Return a [`Display`] wrapper for pbulk's machine-readable
+     * `meta/report` file: each package's [`report`] record followed by its
+     * `PKG_DEPTH` and `BUILD_STATUS`.
+     *
+     * [`Display`]: fmt::Display
+     * [`report`]: ScanIndex::report
+     */
+    #[must_use]
+    pub fn meta(&self) -> Meta<'_> {
+        Meta(self)
+    }
+
+    /**
+     * Return a [`Display`] wrapper for pbulk's `report.txt` summary,
+     * followed by failure counts for each maintainer.
+     *
+     * [`Display`]: fmt::Display
+     */
+    #[must_use]
+    pub fn text(&self) -> Text<'_> {
+        Text(self)
+    }
+
+    /**
+     * Write a static HTML report to `dir`, creating it if necessary.  The
+     * summary is written to `report.html`, with a page for each package
+     * under `pkg/` linking to its build logs.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the files cannot be written.
+     */
+    pub fn write_html(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        html::write(self, dir.as_ref())
+    }
+
+    fn pkgpath(&self, id: usize) -> &str {
+        let pkg = &self.packages[id];
+        match &pkg.pkg_location {
+            Some(path) => path.as_str(),
+            None => pkg.pkgname.pkgname(),
+        }
+    }
+
+    fn maintainer(&self, id: usize) -> &str {
+        self.packages[id].maintainer.as_deref().unwrap_or("")
+    }
+
+    /*
+     * Failed packages, sorted by PKGPATH.
+     */
+    fn failed(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = (0..self.len())
+            .filter(|&id| self.status[id] == BuildStatus::Failed)
+            .collect();
+        ids.sort_by(|&a, &b| {
+            self.pkgpath(a)
+                .cmp(self.pkgpath(b))
+                .then(self.packages[a].pkgname.cmp(&self.packages[b].pkgname))
+        });
+        ids
+    }
+
+    /*
+     * Failed and prefailed packages that break others, most first.
+     */
+    fn top_breakers(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> =
+            (0..self.len()).filter(|&id| self.breaks[id] > 0).collect();
+        ids.sort_by(|&a, &b| {
+            self.breaks[b]
+                .cmp(&self.breaks[a])
+                .then(self.pkgpath(a).cmp(self.pkgpath(b)))
+        });
+        ids.truncate(TOP_BREAKERS);
+        ids
+    }
+}
+
+/**
+ * [`Display`](fmt::Display) wrapper for the `meta/report` file.
+ *
+ * Created by [`BuildReport::meta`].
+ */
+pub struct Meta<'a>(&'a BuildReport);
+
+impl fmt::Display for Meta<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let r = self.0;
+        for (id, pkg) in r.packages.iter().enumerate() {
+            write!(f, "{}", pkg.report())?;
+            writeln!(f, "PKG_DEPTH={}", r.depth[id])?;
+            writeln!(f, "BUILD_STATUS={}", r.status[id])?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * [`Display`](fmt::Display) wrapper for the `report.txt` summary.
+ *
+ * Created by [`BuildReport::text`].
+ */
+pub struct Text<'a>(&'a BuildReport);
+
+const RULE: &str =
+    "-------------------------------------------------------------------------";
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let r = self.0;
+        writeln!(f, "pkgsrc bulk build report")?;
+        writeln!(f, "========================")?;
+        writeln!(f)?;
+        if r.platform.is_some() || r.compiler.is_some() {
+            if let Some(platform) = &r.platform {
+                writeln!(f, "{platform}")?;
+            }
+            if let Some(compiler) = &r.compiler {
+                writeln!(f, "Compiler: {compiler}")?;
+            }
+            writeln!(f)?;
+        }
+        if let (Some(start), Some(end)) = (&r.build_start, &r.build_end) {
+            writeln!(f, "Build start: {start}")?;
+            writeln!(f, "Build end:   {end}")?;
+            writeln!(f)?;
+        }
+        if let Some(url) = &r.base_url {
+            writeln!(f, "Full report: {url}/meta/report.html")?;
+            writeln!(f, "Machine readable version: {url}/meta/report.bz2")?;
+            writeln!(f)?;
+        }
+
+        let rows = [
+            ("  Successfully built:", BuildStatus::Done),
+            ("  Failed to build:", BuildStatus::Failed),
+            (
+                "  Depending on failed package:",
+                BuildStatus::IndirectFailed,
+            ),
+            ("  Explicitly broken or masked:", BuildStatus::Prefailed),
+            (
+                "  Depending on masked package:",
+                BuildStatus::IndirectPrefailed,
+            ),
+        ];
+        writeln!(f, "{:<30} {:5}", "Total number of packages:", r.len())?;
+        for (label, status) in rows {
+            writeln!(f, "{label:<30} {:5}", r.count(status))?;
+        }
+        let pending = r.count(BuildStatus::Pending);
+        if pending > 0 {
+            writeln!(f, "{:<30} {pending:5}", "  Not yet built:")?;
+        }
+
+        let table = |f: &mut fmt::Formatter<'_>,
+                     title: &str,
+                     ids: &[usize]|
+         -> fmt::Result {
+            if ids.is_empty() {
+                return Ok(());
+            }
+            writeln!(f)?;
+            writeln!(f, "{title}")?;
+            writeln!(f)?;
+            writeln!(f, "{:<37} {:>6} Maintainer", "Package", "Breaks")?;
+            writeln!(f, "{RULE}")?;
+            for &id in ids {
+                let line = format!(
+                    "{:<37} {:>6} {}",
+                    r.pkgpath(id),
+                    r.breaks[id],
+                    r.maintainer(id)
+                );
+                writeln!(f, "{}", line.trim_end())?;
+            }
+            Ok(())
+        };
+        table(
+            f,
+            "Packages breaking the most other packages",
+            &r.top_breakers(),
+        )?;
+        table(f, "Build failures", &r.failed())?;
+
+        let maintainers = r.maintainers();
+        if !maintainers.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Build failures by maintainer")?;
+            writeln!(f)?;
+            writeln!(f, "{:<37} {:>6} Breaks", "Maintainer", "Failed")?;
+            writeln!(f, "{RULE}")?;
+            for m in maintainers {
+                writeln!(
+                    f,
+                    "{:<37} {:>6} {}",
+                    m.maintainer, m.failed, m.breaks
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report<R: BuildResults>(results: &R) -> BuildReport {
+        let scan = "PKGNAME=a-1\nPKG_LOCATION=x/a\nMAINTAINER=m1\n\
+                    PKGNAME=b-1\nPKG_LOCATION=x/b\nDEPENDS=a-1\n\
+                    PKGNAME=c-1\nPKG_LOCATION=x/c\nPKG_SKIP_REASON=no\n\
+                    PKGNAME=d-1\nPKG_LOCATION=x/d\nDEPENDS=b-1 c-1\n\
+                    PKGNAME=e-1\nPKG_LOCATION=x/e\nMAINTAINER=m1\n";
+        let pkgs = ScanIndex::from_reader(scan.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("valid scan");
+        BuildReport::new(pkgs, results).expect("valid graph")
+    }
+
+    #[test]
+    fn status() {
+        let report = report(&|p: &PkgName| match p.pkgname() {
+            "a-1" | "e-1" => Some(BuildResult::failure()),
+            _ => Some(BuildResult::success()),
+        });
+        let status: Vec<_> =
+            (0..report.len()).map(|i| report.status(i)).collect();
+        assert_eq!(
+            status,
+            [
+                BuildStatus::Failed,
+                BuildStatus::IndirectFailed,
+                BuildStatus::Prefailed,
+                BuildStatus::IndirectPrefailed,
+                BuildStatus::Failed,
+            ]
+        );
+        /* d-1 is counted against both a-1 and c-1. */
+        assert_eq!(report.breaks(0), 2);
+        assert_eq!(report.breaks(2), 1);
+        assert_eq!(report.breaks(4), 0);
+        assert!(report.result(3).is_none());
+        let m = report.maintainers();
+        assert_eq!(m.len(), 1);
+        assert_eq!(
+            (m[0].maintainer(), m[0].failed(), m[0].breaks()),
+            ("m1", 2, 2)
+        );
+    }
+
+    #[test]
+    fn meta() {
+        let results: HashMap<String, BuildResult> = HashMap::new();
+        let report = report(&results);
+        let meta = report.meta().to_string();
+        assert!(meta.contains("PKGNAME=a-1\n"));
+        assert!(meta.contains("PKG_DEPTH=3\nBUILD_STATUS=pending\n"));
+        assert!(meta.ends_with(
+            "DEPENDS=b-1 c-1\nPKG_DEPTH=1\nBUILD_STATUS=indirect-prefailed\n\
+             PKGNAME=e-1\nPKG_LOCATION=x/e\nALL_DEPENDS=\n\
+             PKG_SKIP_REASON=\nPKG_FAIL_REASON=\nNO_BIN_ON_FTP=\n\
+             RESTRICTED=\nCATEGORIES=\nMAINTAINER=m1\nUSE_DESTDIR=\n\
+             BOOTSTRAP_PKG=\nUSERGROUP_PHASE=\nSCAN_DEPENDS=\n\
+             PKG_DEPTH=1\nBUILD_STATUS=pending\n"
+        ));
+    }
+
+    #[test]
+    fn text() {
+        let report = report(&|p: &PkgName| {
+            (p.pkgname() == "a-1").then(BuildResult::failure)
+        })
+        .with_platform("NetBSD 10.0/x86_64")
+        .with_base_url("https://example.org/reports/");
+        assert_eq!(
+            report.text().to_string(),
+            "pkgsrc bulk build report\n\
+             ========================\n\
+             \n\
+             NetBSD 10.0/x86_64\n\
+             \n\
+             Full report: https://example.org/reports/meta/report.html\n\
+             Machine readable version: \
+             https://example.org/reports/meta/report.bz2\n\
+             \n\
+             Total number of packages:          5\n  \
+             Successfully built:              0\n  \
+             Failed to build:                 1\n  \
+             Depending on failed package:     1\n  \
+             Explicitly broken or masked:     1\n  \
+             Depending on masked package:     1\n  \
+             Not yet built:                   1\n\
+             \n\
+             Packages breaking the most other packages\n\
+             \n\
+             Package                               Breaks Maintainer\n\
+             -------------------------------------------------------------------------\n\
+             x/a                                        2 m1\n\
+             x/c                                        1\n\
+             \n\
+             Build failures\n\
+             \n\
+             Package                               Breaks Maintainer\n\
+             -------------------------------------------------------------------------\n\
+             x/a                                        2 m1\n\
+             \n\
+             Build failures by maintainer\n\
+             \n\
+             Maintainer                            Failed Breaks\n\
+             -------------------------------------------------------------------------\n\
+             m1                                         1 2\n"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*
+ * Static HTML output for BuildReport.
+ */
+
+use super::BuildReport;
+use crate::graph::BuildStatus;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const DEFAULT_LOG_URL: &str = "../..";
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn header(out: &mut String, title: &str) {
+    let title = escape(title);
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n");
+    out.push_str("<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{title}</title>");
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(out, "<h1>{title}</h1>");
+}
+
+fn footer(out: &mut String) {
+    out.push_str("</body>\n</html>\n");
+}
+
+fn pkg_link(report: &BuildReport, id: usize, prefix: &str) -> String {
+    let pkgname = escape(report.packages[id].pkgname.pkgname());
+    format!("<a href=\"{prefix}{pkgname}.html\">{pkgname}</a>")
+}
+
+fn package_table(out: &mut String, report: &BuildReport, ids: &[usize]) {
+    out.push_str("<table>\n<tr><th>Package</th><th>PKGNAME</th>");
+    out.push_str("<th>Status</th><th>Breaks</th><th>Maintainer</th></tr>\n");
+    for &id in ids {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td>{}</td></tr>",
+            escape(report.pkgpath(id)),
+            pkg_link(report, id, "pkg/"),
+            report.status[id],
+            report.breaks[id],
+            escape(report.maintainer(id)),
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn index(report: &BuildReport) -> String {
+    let mut out = String::new();
+    header(&mut out, "pkgsrc bulk build report");
+
+    let mut info = Vec::new();
+    if let Some(platform) = &report.platform {
+        info.push(escape(platform));
+    }
+    if let Some(compiler) = &report.compiler {
+        info.push(format!("Compiler: {}", escape(compiler)));
+    }
+    if let (Some(start), Some(end)) = (&report.build_start, &report.build_end) {
+        info.push(format!("Build start: {}", escape(start)));
+        info.push(format!("Build end: {}", escape(end)));
+    }
+    if !info.is_empty() {
+        let _ = writeln!(out, "<p>{}</p>", info.join("<br>\n"));
+    }
+
+    out.push_str("<table>\n");
+    let _ = writeln!(
+        out,
+        "<tr><td>Total number of packages</td><td>{}</td></tr>",
+        report.len()
+    );
+    for (label, status) in [
+        ("Successfully built", BuildStatus::Done),
+        ("Failed to build", BuildStatus::Failed),
+        ("Depending on failed package", BuildStatus::IndirectFailed),
+        ("Explicitly broken or masked", BuildStatus::Prefailed),
+        (
+            "Depending on masked package",
+            BuildStatus::IndirectPrefailed,
+        ),
+        ("Not yet built", BuildStatus::Pending),
+    ] {
+        let count = report.count(status);
+        if status != BuildStatus::Pending || count > 0 {
+            let _ = writeln!(out, "<tr><td>{label}</td><td>{count}</td></tr>");
+        }
+    }
+    out.push_str("</table>\n");
+
+    let top = report.top_breakers();
+    if !top.is_empty() {
+        out.push_str("<h2>Packages breaking the most other packages</h2>\n");
+        package_table(&mut out, report, &top);
+    }
+    let failed = report.failed();
+    if !failed.is_empty() {
+        out.push_str("<h2>Build failures</h2>\n");
+        package_table(&mut out, report, &failed);
+    }
+    let maintainers = report.maintainers();
+    if !maintainers.is_empty() {
+        out.push_str("<h2>Build failures by maintainer</h2>\n");
+        out.push_str("<table>\n<tr><th>Maintainer</th><th>Failed</th>");
+        out.push_str("<th>Breaks</th></tr>\n");
+        for m in maintainers {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(m.maintainer()),
+                m.failed(),
+                m.breaks(),
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    let mut other: Vec<usize> = (0..report.len())
+        .filter(|&id| {
+            !matches!(
+                report.status[id],
+                BuildStatus::Done | BuildStatus::Failed
+            )
+        })
+        .collect();
+    other.sort_by(|&a, &b| report.pkgpath(a).cmp(report.pkgpath(b)));
+    if !other.is_empty() {
+        out.push_str("<h2>Packages not built</h2>\n");
+        package_table(&mut out, report, &other);
+    }
+
+    footer(&mut out);
+    out
+}
+
+fn package(report: &BuildReport, id: usize) -> String {
+    let pkg = &report.packages[id];
+    let pkgname = pkg.pkgname.pkgname();
+    let mut out = String::new();
+    header(&mut out, pkgname);
+
+    out.push_str("<table>\n");
+    let mut row = |key: &str, value: &str| {
+        if !value.is_empty() {
+            let _ = writeln!(
+                out,
+                "<tr><th>{key}</th><td>{}</td></tr>",
+                escape(value)
+            );
+        }
+    };
+    row("PKGPATH", report.pkgpath(id));
+    row("Status", &report.status[id].to_string());
+    row("Maintainer", report.maintainer(id));
+    row("Categories", pkg.categories.as_deref().unwrap_or(""));
+    row(
+        "PKG_SKIP_REASON",
+        pkg.pkg_skip_reason.as_deref().unwrap_or(""),
+    );
+    row(
+        "PKG_FAIL_REASON",
+        pkg.pkg_fail_reason.as_deref().unwrap_or(""),
+    );
+    row("PKG_DEPTH", &report.depth[id].to_string());
+    row("Breaks", &report.breaks[id].to_string());
+    out.push_str("</table>\n");
+
+    let deps: Vec<String> = pkg
+        .depends()
+        .iter()
+        .map(|d| {
+            let d = escape(d.pkgname());
+            format!("<li><a href=\"{d}.html\">{d}</a></li>")
+        })
+        .collect();
+    if !deps.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Dependencies</h2>\n<ul>\n{}\n</ul>",
+            deps.join("\n")
+        );
+    }
+
+    if let Some(result) = &report.results[id]
+        && !result.logs().is_empty()
+    {
+        let base = report.log_url.as_deref().unwrap_or(DEFAULT_LOG_URL);
+        out.push_str("<h2>Build logs</h2>\n<ul>\n");
+        for log in result.logs() {
+            let log = escape(log);
+            let _ = writeln!(
+                out,
+                "<li><a href=\"{}/{}/{log}\">{log}</a></li>",
+                escape(base),
+                escape(pkgname),
+            );
+        }
+        out.push_str("</ul>\n");
+    }
+
+    let _ =
+        writeln!(out, "<p><a href=\"../report.html\">Back to report</a></p>");
+    footer(&mut out);
+    out
+}
+
+pub(super) fn write(report: &BuildReport, dir: &Path) -> io::Result<()> {
+    let pkgdir = dir.join("pkg");
+    fs::create_dir_all(&pkgdir)?;
+    fs::write(dir.join("report.html"), index(report))?;
+    for (id, pkg) in report.packages.iter().enumerate() {
+        let file = format!("{}.html", pkg.pkgname.pkgname());
+        fs::write(pkgdir.join(file), package(report, id))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScanIndex;
+    use crate::report::BuildResult;
+    use std::collections::HashMap;
+
+    #[test]
+    fn pages() {
+        let scan = "PKGNAME=a-1\nPKG_LOCATION=x/a\nMAINTAINER=<m@x>\n\
+                    PKGNAME=b-1\nPKG_LOCATION=x/b\nDEPENDS=a-1\n";
+        let pkgs = ScanIndex::from_reader(scan.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("valid scan");
+        let results = HashMap::from([(
+            "a-1".to_string(),
+            BuildResult::failure().with_log("build.log"),
+        )]);
+        let report = BuildReport::new(pkgs, &results)
+            .expect("valid graph")
+            .with_log_url("https://example.org/logs/");
+
+        let html = index(&report);
+        assert!(html.contains(
+            "<tr><td>x/a</td><td><a href=\"pkg/a-1.html\">a-1</a></td>\
+             <td>failed</td><td>1</td><td>&lt;m@x&gt;</td></tr>\n"
+        ));
+        assert!(html.contains("<h2>Packages not built</h2>"));
+
+        let html = package(&report, 0);
+        assert!(html.contains(
+            "<li><a href=\"https://example.org/logs/a-1/build.log\">\
+             build.log</a></li>\n"
+        ));
+        let html = package(&report, 1);
+        assert!(html.contains("<li><a href=\"a-1.html\">a-1</a></li>"));
+        assert!(html.contains("<tr><th>Status</th><td>indirect-failed</td>"));
+    }
+}
@@ -61,6 +61,27 @@
  * }
  * # Ok::<(), std::io::Error>(())
  * ```
+ *
+ * # Zero-copy Parsing
+ *
+ * Full-tree scan files can be hundreds of megabytes.  [`ScanIndexRef::iter`]
+ * splits an in-memory or mapped input into [`ScanIndexRef`] records that
+ * borrow from it, locating fields only when they are accessed, and
+ * converting to an owned [`ScanIndex`] on request.
+ *
+ * # Resolving Dependencies
+ *
+ * Raw scan output contains only `ALL_DEPENDS` patterns.  A [`Resolver`]
+ * matches them against the scanned packages to produce presolve output with
+ * `DEPENDS` filled in, reporting any [`Unresolved`] patterns.
+ *
+ * # Scanning a Tree
+ *
+ * A [`Scanner`] runs `make pbulk-index` across a whole pkgsrc tree, or a
+ * limited list of packages and their dependencies, in parallel.  Commands
+ * are run through a [`CommandRunner`], which defaults to [`MakeRunner`].
+ * A [`ScanCache`] keeps the results between runs, so that only packages
+ * whose `SCAN_DEPENDS` files have changed need to be scanned again.
  */
 
 use crate::kv::KvWarning;
@@ -71,6 +92,16 @@ use std::io::{self, BufRead};
 use std::path::Path;
 use std::str::FromStr;
 
+mod borrowed;
+mod cache;
+mod resolve;
+mod scan;
+
+pub use borrowed::{ScanIndexRef, ScanIndexRefIter};
+pub use cache::{ScanCache, StampMode};
+pub use resolve::{Resolution, Resolver, Unresolved};
+pub use scan::{CommandRunner, MakeRunner, ScanFailure, ScanResult, Scanner};
+
 /**
  * Space-separated list of filesystem paths, stored as a single string.
  *
@@ -549,6 +580,41 @@ impl ScanIndex {
         Pscan(self)
     }
 
+    /**
+     * Format every field exactly as `bmake pbulk-index` writes it.
+     *
+     * Unlike [`pscan`](Self::pscan), which always writes the standard
+     * fields and omits `DEPENDS`, this writes precisely the fields that are
+     * set, in the order `pbulk-index` uses, and nothing else.  Fields that
+     * `pbulk-index` prints even when empty are parsed as `Some("")` rather
+     * than `None`, so records read from its output are written back
+     * byte-for-byte, and any record survives a round trip through
+     * [`from_reader`](Self::from_reader) unchanged.  `MAKE_JOBS_SAFE` is
+     * normalised to `yes` or `no`, and `DEPENDS` only appears if
+     * [`resolved_depends`](Self::resolved_depends) is set.
+     *
+     * ```
+     * use pkgsrc::ScanIndex;
+     *
+     * let input = "PKGNAME=foo-1.0\n\
+     *              ALL_DEPENDS=\n\
+     *              PKG_SKIP_REASON=\n\
+     *              MULTI_VERSION= PYTHON_VERSION_REQD=313\n";
+     * let mut pkg: ScanIndex = input.parse()?;
+     * assert_eq!(pkg.pbulk_index().to_string(), input);
+     *
+     * pkg.pbulk_weight = Some(200);
+     * let output = pkg.pbulk_index().to_string();
+     * assert!(output.ends_with("PBULK_WEIGHT=200\nMULTI_VERSION= PYTHON_VERSION_REQD=313\n"));
+     * assert_eq!(output.parse::<ScanIndex>()?, pkg);
+     * # Ok::<(), pkgsrc::kv::KvError>(())
+     * ```
+     */
+    #[must_use]
+    pub fn pbulk_index(&self) -> PbulkIndex<'_> {
+        PbulkIndex(self)
+    }
+
     /**
      * Format as resolved scan output.
      *
@@ -664,6 +730,74 @@ impl fmt::Display for Pscan<'_> {
     }
 }
 
+/**
+ * [`Display`](fmt::Display) wrapper for the full `bmake pbulk-index` record.
+ *
+ * Created by [`ScanIndex::pbulk_index`].
+ */
+pub struct PbulkIndex<'a>(&'a ScanIndex);
+
+impl fmt::Display for PbulkIndex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.0;
+        writeln!(f, "PKGNAME={}", s.pkgname)?;
+        if let Some(v) = &s.pkg_location {
+            writeln!(f, "PKG_LOCATION={v}")?;
+        }
+        if let Some(v) = &s.all_depends {
+            writeln!(f, "ALL_DEPENDS={v}")?;
+        }
+        for (key, value) in [
+            ("PKG_SKIP_REASON", &s.pkg_skip_reason),
+            ("PKG_FAIL_REASON", &s.pkg_fail_reason),
+            ("NO_BIN_ON_FTP", &s.no_bin_on_ftp),
+            ("RESTRICTED", &s.restricted),
+            ("CATEGORIES", &s.categories),
+            ("MAINTAINER", &s.maintainer),
+            ("USE_DESTDIR", &s.use_destdir),
+        ] {
+            if let Some(v) = value {
+                writeln!(f, "{key}={v}")?;
+            }
+        }
+        if let Some(v) = &s.bootstrap_pkg {
+            let v = if v.is_bootstrap() { "yes" } else { "" };
+            writeln!(f, "BOOTSTRAP_PKG={v}")?;
+        }
+        if let Some(v) = &s.usergroup_phase {
+            writeln!(f, "USERGROUP_PHASE={v}")?;
+        }
+        if let Some(v) = &s.scan_depends {
+            writeln!(f, "SCAN_DEPENDS={v}")?;
+        }
+        if let Some(v) = &s.make_jobs_safe {
+            let v = if v.is_safe() { "yes" } else { "no" };
+            writeln!(f, "MAKE_JOBS_SAFE={v}")?;
+        }
+        if let Some(v) = &s.pbulk_weight {
+            writeln!(f, "PBULK_WEIGHT={v}")?;
+        }
+        if let Some(vars) = &s.multi_version {
+            write!(f, "MULTI_VERSION=")?;
+            for v in vars {
+                write!(f, " {v}")?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(deps) = &s.resolved_depends {
+            write!(f, "DEPENDS=")?;
+            for (i, d) in deps.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{d}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 /**
  * [`Display`](fmt::Display) wrapper for resolved scan output.
  *
@@ -1069,6 +1203,45 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn pbulk_index_exact() -> anyhow::Result<()> {
+        let mut scanfile = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        scanfile.push("tests/data/scanindex/pbulk-index.txt");
+        let input = std::fs::read_to_string(&scanfile)?;
+        let original: Vec<_> = ScanIndex::from_reader(input.as_bytes())
+            .collect::<Result<_, _>>()?;
+
+        let output: String = original
+            .iter()
+            .map(|s| s.pbulk_index().to_string())
+            .collect();
+        assert_eq!(output, input);
+        Ok(())
+    }
+
+    #[test]
+    fn pbulk_index_roundtrip() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        let sparse = ScanIndex::from_str("PKGNAME=foo-1.0\n")?;
+        assert_eq!(sparse.pbulk_index().to_string(), "PKGNAME=foo-1.0\n");
+
+        let full = ScanIndex {
+            pkg_location: Some(PkgPath::new("devel/foo")?),
+            all_depends: Some("bar-[0-9]*:../../devel/bar".into()),
+            pkg_skip_reason: Some(String::new()),
+            bootstrap_pkg: Some(false.into()),
+            make_jobs_safe: Some(true.into()),
+            pbulk_weight: Some(250),
+            multi_version: Some(Vec::new()),
+            resolved_depends: Some(vec![PkgName::new("bar-1.0")]),
+            ..sparse
+        };
+        let output = full.pbulk_index().to_string();
+        assert_eq!(ScanIndex::from_str(&output)?, full);
+        Ok(())
+    }
+
     #[test]
     fn resolved_depends_none() -> Result<(), KvError> {
         use std::str::FromStr;
@@ -0,0 +1,507 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*
+ * Zero-copy views of pbulk-index records.
+ */
+
+use super::{AllDependsIter, ScanIndex};
+use crate::kv::{KvError, KvWarning, Span};
+use std::path::Path;
+use std::str::{SplitAsciiWhitespace, Utf8Error};
+
+const PKGNAME: &str = "PKGNAME=";
+
+/**
+ * A borrowed view of a single `pbulk-index` record.
+ *
+ * Where [`ScanIndex`] allocates every field up front, a [`ScanIndexRef`]
+ * is just a slice of the input.  Fields are located only when they are
+ * accessed, and returned as slices or iterators borrowing from the input,
+ * so a full-tree scan file, read into memory or mapped with `mmap`, can be
+ * filtered without copying the records that are not needed.  Use
+ * [`to_scan_index`](Self::to_scan_index) to convert a record to the owned
+ * form.
+ *
+ * As with [`ScanIndex`], if a variable appears more than once the last
+ * value wins.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::ScanIndexRef;
+ *
+ * let input = "PKGNAME=foo-1.0\n\
+ *              PKG_LOCATION=devel/foo\n\
+ *              ALL_DEPENDS=bar>=1:../../devel/bar\n\
+ *              PKGNAME=bar-1.0\n\
+ *              PKG_LOCATION=devel/bar\n\
+ *              PBULK_WEIGHT=lots\n";
+ * let mut iter = ScanIndexRef::iter(input);
+ * let records: Vec<_> = iter.by_ref().collect::<Result<_, _>>()?;
+ * assert_eq!(records[0].pkgname(), "foo-1.0");
+ * assert_eq!(records[0].all_depends().map(|d| d.count()), Some(1));
+ * assert_eq!(records[1].pkg_location(), Some("devel/bar"));
+ *
+ * /* Invalid lenient values are reported as the records are read. */
+ * assert_eq!(records[1].pbulk_weight(), None);
+ * assert_eq!(iter.warnings()[0].value, "lots");
+ *
+ * let owned = records[1].to_scan_index()?;
+ * assert_eq!(owned.pkgname.pkgname(), "bar-1.0");
+ * # Ok::<(), pkgsrc::kv::KvError>(())
+ * ```
+ */
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ScanIndexRef<'a> {
+    record: &'a str,
+    offset: usize,
+}
+
+impl<'a> ScanIndexRef<'a> {
+    /**
+     * Create an iterator over the records in `input`.
+     *
+     * Records are delimited by lines starting with `PKGNAME=`, as for
+     * [`ScanIndex::from_reader`].
+     */
+    #[must_use]
+    pub fn iter(input: &'a str) -> ScanIndexRefIter<'a> {
+        ScanIndexRefIter {
+            input,
+            pos: 0,
+            warnings: Vec::new(),
+        }
+    }
+
+    /**
+     * Create an iterator over the records in `input`, which is validated
+     * as UTF-8 once up front.
+     *
+     * # Errors
+     *
+     * Returns [`Utf8Error`] if `input` is not valid UTF-8.
+     */
+    pub fn iter_bytes(
+        input: &'a [u8],
+    ) -> Result<ScanIndexRefIter<'a>, Utf8Error> {
+        Ok(Self::iter(std::str::from_utf8(input)?))
+    }
+
+    /**
+     * Return the raw text of the record.
+     */
+    #[must_use]
+    pub fn as_str(&self) -> &'a str {
+        self.record
+    }
+
+    /**
+     * Return the byte offset of the record within the input.
+     */
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /**
+     * Return the raw value of any variable in the record.  If it appears
+     * more than once the last value is returned.
+     */
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.record
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+    }
+
+    /**
+     * Return the `PKGNAME`.
+     */
+    #[must_use]
+    pub fn pkgname(&self) -> &'a str {
+        let first = self.record.lines().next().unwrap_or_default();
+        first.strip_prefix(PKGNAME).unwrap_or_default()
+    }
+
+    /**
+     * Return the `PKG_LOCATION`.
+     */
+    #[must_use]
+    pub fn pkg_location(&self) -> Option<&'a str> {
+        self.get("PKG_LOCATION")
+    }
+
+    /**
+     * Return an iterator over the `ALL_DEPENDS` entries.
+     */
+    #[must_use]
+    pub fn all_depends(&self) -> Option<AllDependsIter<'a>> {
+        self.get("ALL_DEPENDS")
+            .map(|v| AllDependsIter(v.split_ascii_whitespace()))
+    }
+
+    /**
+     * Return the `PKG_SKIP_REASON`.
+     */
+    #[must_use]
+    pub fn pkg_skip_reason(&self) -> Option<&'a str> {
+        self.get("PKG_SKIP_REASON")
+    }
+
+    /**
+     * Return the `PKG_FAIL_REASON`.
+     */
+    #[must_use]
+    pub fn pkg_fail_reason(&self) -> Option<&'a str> {
+        self.get("PKG_FAIL_REASON")
+    }
+
+    /**
+     * Return the `NO_BIN_ON_FTP` reason.
+     */
+    #[must_use]
+    pub fn no_bin_on_ftp(&self) -> Option<&'a str> {
+        self.get("NO_BIN_ON_FTP")
+    }
+
+    /**
+     * Return the `RESTRICTED` reason.
+     */
+    #[must_use]
+    pub fn restricted(&self) -> Option<&'a str> {
+        self.get("RESTRICTED")
+    }
+
+    /**
+     * Return the `CATEGORIES`.
+     */
+    #[must_use]
+    pub fn categories(&self) -> Option<&'a str> {
+        self.get("CATEGORIES")
+    }
+
+    /**
+     * Return the `MAINTAINER`.
+     */
+    #[must_use]
+    pub fn maintainer(&self) -> Option<&'a str> {
+        self.get("MAINTAINER")
+    }
+
+    /**
+     * Return the `USE_DESTDIR` method.
+     */
+    #[must_use]
+    pub fn use_destdir(&self) -> Option<&'a str> {
+        self.get("USE_DESTDIR")
+    }
+
+    /**
+     * Return whether the package is part of the pkgsrc bootstrap, with the
+     * same rules as [`BootstrapPkg`](super::BootstrapPkg).
+     */
+    #[must_use]
+    pub fn bootstrap_pkg(&self) -> Option<bool> {
+        self.get("BOOTSTRAP_PKG").map(|v| v == "yes")
+    }
+
+    /**
+     * Return the `USERGROUP_PHASE`.
+     */
+    #[must_use]
+    pub fn usergroup_phase(&self) -> Option<&'a str> {
+        self.get("USERGROUP_PHASE")
+    }
+
+    /**
+     * Return an iterator over the `SCAN_DEPENDS` paths.
+     */
+    #[must_use]
+    pub fn scan_depends(&self) -> Option<impl Iterator<Item = &'a Path>> {
+        self.get("SCAN_DEPENDS")
+            .map(|v| v.split_ascii_whitespace().map(Path::new))
+    }
+
+    /**
+     * Return whether the package supports parallel builds, with the same
+     * rules as [`MakeJobsSafe`](super::MakeJobsSafe).
+     */
+    #[must_use]
+    pub fn make_jobs_safe(&self) -> Option<bool> {
+        self.get("MAKE_JOBS_SAFE")
+            .map(|v| !v.eq_ignore_ascii_case("no"))
+    }
+
+    /**
+     * Return the `PBULK_WEIGHT`, or `None` if it is unset or invalid.
+     */
+    #[must_use]
+    pub fn pbulk_weight(&self) -> Option<u32> {
+        self.get("PBULK_WEIGHT").and_then(|v| v.parse().ok())
+    }
+
+    /**
+     * Return an iterator over the `MULTI_VERSION` variables.
+     */
+    #[must_use]
+    pub fn multi_version(&self) -> Option<SplitAsciiWhitespace<'a>> {
+        self.get("MULTI_VERSION").map(str::split_ascii_whitespace)
+    }
+
+    /**
+     * Return an iterator over the resolved `DEPENDS` package names.
+     */
+    #[must_use]
+    pub fn depends(&self) -> Option<SplitAsciiWhitespace<'a>> {
+        self.get("DEPENDS").map(str::split_ascii_whitespace)
+    }
+
+    /**
+     * Parse the record into an owned [`ScanIndex`].  Error spans are
+     * relative to the whole input rather than the record.
+     *
+     * # Errors
+     *
+     * Returns [`KvError`] if the record is invalid.
+     */
+    pub fn to_scan_index(&self) -> Result<ScanIndex, KvError> {
+        self.to_scan_index_with_warnings(&mut Vec::new())
+    }
+
+    /**
+     * Parse the record into an owned [`ScanIndex`], appending a
+     * [`KvWarning`] for each lenient value that was dropped.  Spans are
+     * relative to the whole input rather than the record.
+     *
+     * # Errors
+     *
+     * Returns [`KvError`] if the record is invalid.
+     */
+    pub fn to_scan_index_with_warnings(
+        &self,
+        warnings: &mut Vec<KvWarning>,
+    ) -> Result<ScanIndex, KvError> {
+        let start = warnings.len();
+        let result = ScanIndex::parse_with_warnings(self.record, warnings);
+        for w in &mut warnings[start..] {
+            w.span = self.shift(w.span);
+        }
+        result.map_err(|e| match e {
+            KvError::ParseLine(span) => KvError::ParseLine(self.shift(span)),
+            KvError::UnknownVariable { variable, span } => {
+                KvError::UnknownVariable {
+                    variable,
+                    span: self.shift(span),
+                }
+            }
+            KvError::ParseInt { source, span } => KvError::ParseInt {
+                source,
+                span: self.shift(span),
+            },
+            KvError::Parse { message, span } => KvError::Parse {
+                message,
+                span: self.shift(span),
+            },
+            e @ KvError::Incomplete(_) => e,
+        })
+    }
+
+    fn shift(&self, span: Span) -> Span {
+        Span {
+            offset: span.offset + self.offset,
+            len: span.len,
+        }
+    }
+}
+
+impl TryFrom<ScanIndexRef<'_>> for ScanIndex {
+    type Error = KvError;
+
+    fn try_from(r: ScanIndexRef<'_>) -> Result<Self, Self::Error> {
+        r.to_scan_index()
+    }
+}
+
+/**
+ * Iterator over the [`ScanIndexRef`] records in a string.
+ *
+ * Created by [`ScanIndexRef::iter`].  Each record is only checked to start
+ * with `PKGNAME=`, and for invalid values of the
+ * [`LENIENT_VARIABLES`](ScanIndex::LENIENT_VARIABLES), such as
+ * `PBULK_WEIGHT`.  These accumulate as the same [`KvWarning`]s that
+ * [`ScanIndexIter`](super::ScanIndexIter) reports, except that spans are
+ * relative to the whole input rather than each record.
+ */
+#[derive(Clone, Debug)]
+pub struct ScanIndexRefIter<'a> {
+    input: &'a str,
+    pos: usize,
+    warnings: Vec<KvWarning>,
+}
+
+impl ScanIndexRefIter<'_> {
+    /**
+     * The warnings accumulated so far, in the order encountered.
+     */
+    #[must_use]
+    pub fn warnings(&self) -> &[KvWarning] {
+        &self.warnings
+    }
+
+    /**
+     * Consume the iterator and return the accumulated warnings.
+     */
+    #[must_use]
+    pub fn into_warnings(self) -> Vec<KvWarning> {
+        self.warnings
+    }
+}
+
+impl<'a> Iterator for ScanIndexRefIter<'a> {
+    type Item = Result<ScanIndexRef<'a>, KvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.input[self.pos..];
+        if rest.trim().is_empty() {
+            self.pos = self.input.len();
+            return None;
+        }
+        let len = rest
+            .match_indices("\nPKGNAME=")
+            .next()
+            .map_or(rest.len(), |(i, _)| i + 1);
+        let record = ScanIndexRef {
+            record: &rest[..len],
+            offset: self.pos,
+        };
+        self.pos += len;
+        if !record.record.starts_with(PKGNAME) {
+            return Some(Err(KvError::Incomplete("PKGNAME".to_string())));
+        }
+
+        /*
+         * Check every line, not just the last of each variable, to report
+         * the same warnings as ScanIndex::parse_with_warnings.
+         */
+        let mut pos = record.offset;
+        for line in record.record.split_inclusive('\n') {
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            if let Some((key, value)) = text.split_once('=')
+                && ScanIndex::LENIENT_VARIABLES.contains(&key)
+            {
+                let span = Span {
+                    offset: pos + key.len() + 1,
+                    len: value.len(),
+                };
+                self.warnings
+                    .extend(ScanIndex::lenient_warning(key, value, span));
+            }
+            pos += line.len();
+        }
+        Some(Ok(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn matches_owned() -> anyhow::Result<()> {
+        let mut scanfile = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        scanfile.push("tests/data/scanindex/pbulk-index.txt");
+        let input = std::fs::read(&scanfile)?;
+        let owned: Vec<_> = ScanIndex::from_reader(input.as_slice())
+            .collect::<Result<_, _>>()?;
+        let borrowed: Vec<_> =
+            ScanIndexRef::iter_bytes(&input)?.collect::<Result<_, _>>()?;
+        assert_eq!(borrowed.len(), owned.len());
+        for (r, o) in borrowed.iter().zip(&owned) {
+            assert_eq!(r.pkgname(), o.pkgname.pkgname());
+            assert_eq!(
+                r.scan_depends().map(Iterator::count),
+                o.scan_depends.as_ref().map(|d| d.iter().count())
+            );
+            assert_eq!(
+                r.multi_version().map(Iterator::count),
+                o.multi_version.as_ref().map(Vec::len)
+            );
+            assert_eq!(&r.to_scan_index()?, o);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn warnings_and_errors() {
+        let input = "PKGNAME=a-1\nPBULK_WEIGHT=x\nPKGNAME=b-1\nBOGUS=1\n";
+        let mut iter = ScanIndexRef::iter(input);
+        let records: Vec<_> = iter
+            .by_ref()
+            .collect::<Result<_, _>>()
+            .expect("valid records");
+        let warnings = iter.into_warnings();
+        assert_eq!(warnings.len(), 1);
+        let span = warnings[0].span;
+        assert_eq!(&input[span.offset..span.offset + span.len], "x");
+
+        let mut owned_warnings = Vec::new();
+        let a = records[0]
+            .to_scan_index_with_warnings(&mut owned_warnings)
+            .expect("lenient record");
+        assert_eq!(a.pbulk_weight, None);
+        assert_eq!(owned_warnings, warnings);
+
+        let err = records[1].to_scan_index().expect_err("unknown variable");
+        let span = err.span().expect("span");
+        assert_eq!(&input[span.offset..span.offset + span.len], "BOGUS");
+
+        let mut iter = ScanIndexRef::iter("JUNK=1\nPKGNAME=a-1\n");
+        assert!(matches!(iter.next(), Some(Err(KvError::Incomplete(_)))));
+        assert!(matches!(iter.next(), Some(Ok(_))));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn warnings_match_owned() -> anyhow::Result<()> {
+        let input = "PKGNAME=a-1\nPBULK_WEIGHT=x\nPBULK_WEIGHT=5\n\
+                     PKGNAME=b-1\nPBULK_WEIGHT=\n\
+                     PKGNAME=c-1\nPBULK_WEIGHT=-1\n";
+        let mut owned = ScanIndex::from_reader(input.as_bytes());
+        let owned_records = owned.by_ref().collect::<Result<Vec<_>, _>>()?;
+        let mut borrowed = ScanIndexRef::iter(input);
+        let records = borrowed.by_ref().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(records.len(), owned_records.len());
+
+        let owned = owned.into_warnings();
+        let borrowed = borrowed.into_warnings();
+        assert_eq!(borrowed.len(), 3);
+        let pairs = |w: &[KvWarning]| -> Vec<(String, String)> {
+            w.iter()
+                .map(|w| (w.variable.clone(), w.value.clone()))
+                .collect()
+        };
+        assert_eq!(pairs(&borrowed), pairs(&owned));
+        for w in &borrowed {
+            let Span { offset, len } = w.span;
+            assert_eq!(&input[offset..offset + len], w.value);
+        }
+        Ok(())
+    }
+}
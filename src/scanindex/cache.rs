@@ -0,0 +1,532 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::{ScanIndex, ScanResult};
+use crate::{Digest, PkgPath};
+use hashbrown::HashMap;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::path::{Component, Path};
+use std::time::UNIX_EPOCH;
+
+/**
+ * How [`ScanCache`] records the state of each file.
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum StampMode {
+    /// Record modification times.  Cheap, but a file that is rewritten with
+    /// identical contents, for example by a VCS checkout, is treated as
+    /// changed.
+    #[default]
+    Mtime,
+    /// Record a BLAKE2s hash of the contents.
+    Hash,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Stamp {
+    Missing,
+    Mtime(u64, u32),
+    Hash(String),
+}
+
+impl Stamp {
+    fn read(path: &Path, mode: StampMode) -> io::Result<Self> {
+        let result = match mode {
+            StampMode::Mtime => fs::metadata(path).and_then(|m| m.modified()),
+            StampMode::Hash => {
+                return match File::open(path) {
+                    Ok(mut f) => Digest::BLAKE2s
+                        .hash_file(&mut f)
+                        .map(Stamp::Hash)
+                        .map_err(io::Error::other),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                        Ok(Stamp::Missing)
+                    }
+                    Err(e) => Err(e),
+                };
+            }
+        };
+        match result {
+            Ok(time) => {
+                let d = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+                Ok(Stamp::Mtime(d.as_secs(), d.subsec_nanos()))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Stamp::Missing),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn mode(&self) -> StampMode {
+        match self {
+            Stamp::Hash(_) => StampMode::Hash,
+            _ => StampMode::Mtime,
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        if s == "-" {
+            return Some(Stamp::Missing);
+        }
+        if let Some(hash) = s.strip_prefix("blake2s:") {
+            return Some(Stamp::Hash(hash.to_string()));
+        }
+        let (secs, nanos) = s.strip_prefix("mtime:")?.split_once('.')?;
+        Some(Stamp::Mtime(secs.parse().ok()?, nanos.parse().ok()?))
+    }
+}
+
+impl std::fmt::Display for Stamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stamp::Missing => write!(f, "-"),
+            Stamp::Mtime(secs, nanos) => write!(f, "mtime:{secs}.{nanos:09}"),
+            Stamp::Hash(hash) => write!(f, "blake2s:{hash}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    records: Vec<ScanIndex>,
+    files: Vec<(String, Stamp)>,
+}
+
+/*
+ * Resolve a SCAN_DEPENDS path relative to the package directory, returning
+ * it relative to the top of the tree if it is inside it.  Paths are
+ * normalised lexically so that files shared between packages are only
+ * checked once.
+ */
+fn tree_path(pkgpath: &PkgPath, file: &str) -> String {
+    let file = Path::new(file);
+    if file.is_absolute() {
+        return file.to_string_lossy().into_owned();
+    }
+    let mut parts: Vec<String> =
+        pkgpath.as_str().split('/').map(str::to_string).collect();
+    for c in file.components() {
+        match c {
+            Component::ParentDir if parts.pop().is_none() => {
+                return pkgpath.as_path().join(file).to_string_lossy().into();
+            }
+            Component::Normal(s) => parts.push(s.to_string_lossy().into()),
+            _ => {}
+        }
+    }
+    parts.join("/")
+}
+
+/**
+ * A cache of scan results, used to rescan only the packages whose inputs
+ * have changed.
+ *
+ * Each package's records are stored along with the state of its own
+ * `Makefile` and every file listed in its [`scan_depends`], as either a
+ * modification time or a content hash according to [`StampMode`].  After
+ * the tree is updated, [`stale`](Self::stale) returns the packages that
+ * need rescanning, which can be passed to
+ * [`Scanner::with_pkgpaths`](super::Scanner::with_pkgpaths) with
+ * dependency following disabled.
+ *
+ * The cache is saved in a simple line-based format: a `PKGPATH=` line,
+ * a `SCAN_STAMP=` line for each file, then the package's records in the
+ * lossless [`pbulk_index`](ScanIndex::pbulk_index) format.
+ *
+ * # Example
+ *
+ * ```no_run
+ * use pkgsrc::scanindex::{MakeRunner, ScanCache, Scanner};
+ * use std::fs::File;
+ * use std::io::BufReader;
+ *
+ * let pkgsrc = "/usr/pkgsrc";
+ * let mut cache = ScanCache::from_reader(BufReader::new(
+ *     File::open("/var/cache/pscan")?,
+ * ))?;
+ * let scanner = Scanner::new(pkgsrc, MakeRunner::default());
+ * let stale = cache.stale(pkgsrc, &scanner.pkgpaths()?)?;
+ * let scan = scanner
+ *     .with_pkgpaths(stale)
+ *     .with_follow_depends(false)
+ *     .scan()?;
+ * cache.update(pkgsrc, &scan)?;
+ * cache.write_to(File::create("/var/cache/pscan")?)?;
+ * let packages: Vec<_> = cache.records().cloned().collect();
+ * # Ok::<(), std::io::Error>(())
+ * ```
+ *
+ * [`scan_depends`]: ScanIndex::scan_depends
+ */
+#[derive(Clone, Debug, Default)]
+pub struct ScanCache {
+    mode: StampMode,
+    entries: BTreeMap<PkgPath, Entry>,
+}
+
+impl ScanCache {
+    /**
+     * Create an empty cache using modification times.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Set how the state of files is recorded for newly inserted packages.
+     * Existing entries keep the mode they were recorded with.
+     */
+    #[must_use]
+    pub fn with_mode(mut self, mode: StampMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /**
+     * Return the number of cached packages.
+     */
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /**
+     * Return true if the cache is empty.
+     */
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /**
+     * Return the cached records for a package.
+     */
+    #[must_use]
+    pub fn get(&self, pkgpath: &PkgPath) -> Option<&[ScanIndex]> {
+        self.entries.get(pkgpath).map(|e| e.records.as_slice())
+    }
+
+    /**
+     * Iterate over every cached record, ordered by `PKGPATH`.
+     */
+    pub fn records(&self) -> impl Iterator<Item = &ScanIndex> {
+        self.entries.values().flat_map(|e| e.records.iter())
+    }
+
+    /**
+     * Store the records scanned for `pkgpath`, recording the current state
+     * of the files they were scanned from in the tree at `pkgsrc`.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if a file exists but its state cannot be
+     * read.
+     */
+    pub fn insert(
+        &mut self,
+        pkgsrc: impl AsRef<Path>,
+        pkgpath: PkgPath,
+        records: Vec<ScanIndex>,
+    ) -> io::Result<()> {
+        let pkgsrc = pkgsrc.as_ref();
+        let mut paths = vec![tree_path(&pkgpath, "Makefile")];
+        for record in &records {
+            if let Some(deps) = &record.scan_depends {
+                for file in deps.as_str().split_ascii_whitespace() {
+                    paths.push(tree_path(&pkgpath, file));
+                }
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            let stamp = Stamp::read(&pkgsrc.join(&path), self.mode)?;
+            files.push((path, stamp));
+        }
+        self.entries.insert(pkgpath, Entry { records, files });
+        Ok(())
+    }
+
+    /**
+     * Remove a package from the cache, returning its records.
+     */
+    pub fn remove(&mut self, pkgpath: &PkgPath) -> Option<Vec<ScanIndex>> {
+        self.entries.remove(pkgpath).map(|e| e.records)
+    }
+
+    /**
+     * Store the results of a scan of the tree at `pkgsrc`.  Packages are
+     * grouped by their [`pkg_location`], and packages that failed to scan
+     * are removed so that they are rescanned next time.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the state of a file cannot be read.
+     *
+     * [`pkg_location`]: ScanIndex::pkg_location
+     */
+    pub fn update(
+        &mut self,
+        pkgsrc: impl AsRef<Path>,
+        scan: &ScanResult,
+    ) -> io::Result<()> {
+        let mut groups: BTreeMap<PkgPath, Vec<ScanIndex>> = BTreeMap::new();
+        for record in scan.packages() {
+            if let Some(pkgpath) = &record.pkg_location {
+                groups
+                    .entry(pkgpath.clone())
+                    .or_default()
+                    .push(record.clone());
+            }
+        }
+        for (pkgpath, records) in groups {
+            self.insert(pkgsrc.as_ref(), pkgpath, records)?;
+        }
+        for failure in scan.failures() {
+            self.remove(failure.pkgpath());
+        }
+        Ok(())
+    }
+
+    /**
+     * Return the packages in `pkgpaths` that need scanning: those that are
+     * not cached, and those where any recorded file has changed, appeared
+     * or disappeared.  Cached packages that are not in `pkgpaths` are
+     * removed, as they no longer exist in the tree.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if a file exists but its state cannot be
+     * read.
+     */
+    pub fn stale(
+        &mut self,
+        pkgsrc: impl AsRef<Path>,
+        pkgpaths: &[PkgPath],
+    ) -> io::Result<Vec<PkgPath>> {
+        let pkgsrc = pkgsrc.as_ref();
+        let wanted: hashbrown::HashSet<&PkgPath> = pkgpaths.iter().collect();
+        self.entries.retain(|p, _| wanted.contains(p));
+
+        let mut seen: HashMap<(&str, StampMode), bool> = HashMap::new();
+        let mut stale = Vec::new();
+        for pkgpath in pkgpaths {
+            let Some(entry) = self.entries.get(pkgpath) else {
+                stale.push(pkgpath.clone());
+                continue;
+            };
+            for (path, stamp) in &entry.files {
+                let key = (path.as_str(), stamp.mode());
+                let changed = match seen.get(&key) {
+                    Some(&changed) => changed,
+                    None => {
+                        let now = Stamp::read(&pkgsrc.join(path), key.1)?;
+                        let changed = now != *stamp;
+                        /*
+                         * Only unchanged results can be shared, as another
+                         * package may have recorded a different state.
+                         */
+                        if !changed {
+                            seen.insert(key, false);
+                        }
+                        changed
+                    }
+                };
+                if changed {
+                    stale.push(pkgpath.clone());
+                    break;
+                }
+            }
+        }
+        Ok(stale)
+    }
+
+    /**
+     * Read a cache previously saved with [`write_to`](Self::write_to).
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if reading fails, or with
+     * [`io::ErrorKind::InvalidData`] if the input is malformed.
+     */
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let invalid =
+            |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut cache = Self::new();
+        let mut current: Option<(PkgPath, Entry, String)> = None;
+        let finish = |cache: &mut Self,
+                      current: Option<(PkgPath, Entry, String)>|
+         -> io::Result<()> {
+            if let Some((pkgpath, mut entry, text)) = current {
+                entry.records = ScanIndex::from_reader(text.as_bytes())
+                    .collect::<io::Result<Vec<_>>>()?;
+                cache.entries.insert(pkgpath, entry);
+            }
+            Ok(())
+        };
+        for line in reader.lines() {
+            let line = line?;
+            if let Some(path) = line.strip_prefix("PKGPATH=") {
+                finish(&mut cache, current.take())?;
+                let pkgpath = PkgPath::new(path)
+                    .map_err(|e| invalid(format!("{path}: {e}")))?;
+                let entry = Entry {
+                    records: Vec::new(),
+                    files: Vec::new(),
+                };
+                current = Some((pkgpath, entry, String::new()));
+                continue;
+            }
+            let Some((_, entry, text)) = current.as_mut() else {
+                if line.is_empty() {
+                    continue;
+                }
+                return Err(invalid(format!("expected PKGPATH: {line}")));
+            };
+            if let Some(value) = line.strip_prefix("SCAN_STAMP=") {
+                let (stamp, path) = value
+                    .split_once(' ')
+                    .and_then(|(s, p)| Some((Stamp::parse(s)?, p)))
+                    .ok_or_else(|| {
+                        invalid(format!("bad SCAN_STAMP: {value}"))
+                    })?;
+                entry.files.push((path.to_string(), stamp));
+            } else {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        finish(&mut cache, current.take())?;
+        Ok(cache)
+    }
+
+    /**
+     * Save the cache.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if writing fails.
+     */
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (pkgpath, entry) in &self.entries {
+            writeln!(writer, "PKGPATH={pkgpath}")?;
+            for (path, stamp) in &entry.files {
+                writeln!(writer, "SCAN_STAMP={stamp} {path}")?;
+            }
+            for record in &entry.records {
+                write!(writer, "{}", record.pbulk_index())?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(s: &str) -> PkgPath {
+        PkgPath::new(s).expect("valid pkgpath")
+    }
+
+    #[test]
+    fn normalise() {
+        let p = path("devel/bar");
+        assert_eq!(tree_path(&p, "Makefile"), "devel/bar/Makefile");
+        assert_eq!(tree_path(&p, "../../mk/bsd.pkg.mk"), "mk/bsd.pkg.mk");
+        assert_eq!(tree_path(&p, "/etc/mk.conf"), "/etc/mk.conf");
+        assert_eq!(tree_path(&p, "../../../x.mk"), "devel/bar/../../../x.mk");
+    }
+
+    #[test]
+    fn round_trip() -> io::Result<()> {
+        let pkgsrc =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pkgsrc");
+        let record: ScanIndex = "PKGNAME=bar-1.0\n\
+                                 ALL_DEPENDS=libfoo>=1:../../devel/libfoo\n\
+                                 PKG_SKIP_REASON=\n\
+                                 DEPENDS=libfoo-1.0\n"
+            .parse()
+            .map_err(io::Error::other)?;
+        let mut cache = ScanCache::new();
+        cache.insert(&pkgsrc, path("devel/bar"), vec![record.clone()])?;
+
+        let mut saved = Vec::new();
+        cache.write_to(&mut saved)?;
+        let cache = ScanCache::from_reader(saved.as_slice())?;
+        let mut resaved = Vec::new();
+        cache.write_to(&mut resaved)?;
+        assert_eq!(resaved, saved);
+
+        let cache = ScanCache::from_reader(resaved.as_slice())?;
+        let records = cache.get(&path("devel/bar")).expect("cached");
+        assert_eq!(records, [record]);
+        assert!(records[0].resolved_depends.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn stale() -> io::Result<()> {
+        let pkgsrc =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pkgsrc");
+        let record = |s: &str| s.parse::<ScanIndex>().expect("valid record");
+        for mode in [StampMode::Mtime, StampMode::Hash] {
+            let mut cache = ScanCache::new().with_mode(mode);
+            cache.insert(
+                &pkgsrc,
+                path("devel/bar"),
+                vec![record(
+                    "PKGNAME=bar-1.0\n\
+                     SCAN_DEPENDS=../../mk/bsd.pkg.mk ../../mk/gone.mk\n",
+                )],
+            )?;
+            cache.insert(
+                &pkgsrc,
+                path("devel/libfoo"),
+                vec![record("PKGNAME=libfoo-1.0\n")],
+            )?;
+            cache.insert(
+                &pkgsrc,
+                path("devel/old"),
+                vec![record("PKGNAME=old-1.0\n")],
+            )?;
+
+            let mut saved = Vec::new();
+            cache.write_to(&mut saved)?;
+            let mut cache = ScanCache::from_reader(saved.as_slice())?;
+            assert_eq!(cache.len(), 3);
+
+            let all =
+                [path("devel/bar"), path("devel/libfoo"), path("lang/baz")];
+            assert_eq!(cache.stale(&pkgsrc, &all)?, [path("lang/baz")]);
+            assert!(cache.get(&path("devel/old")).is_none());
+
+            /* Simulate a change to a shared file. */
+            let entry =
+                cache.entries.get_mut(&path("devel/bar")).expect("cached");
+            assert_eq!(entry.files[1].0, "mk/bsd.pkg.mk");
+            entry.files[1].1 = Stamp::Missing;
+            assert_eq!(
+                cache.stale(&pkgsrc, &all)?,
+                [path("devel/bar"), path("lang/baz")]
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::ScanIndex;
+use crate::{DependError, PatternSet, PkgName};
+use hashbrown::HashMap;
+
+/**
+ * Resolve `ALL_DEPENDS` patterns in pbulk scan output to concrete package
+ * names, producing presolve output.
+ *
+ * Every dependency pattern is matched against every `PKGNAME` in the scan,
+ * and the best match is chosen as pbulk does: the highest version, with
+ * ties broken by the lexicographically greater name.  The default settings
+ * reproduce `pbulk-resolve` exactly:
+ *
+ * * [`resolved_depends`] is only set when every pattern of a package
+ *   resolves.  A package with any unresolved pattern gets no `DEPENDS`.
+ * * A package with an unresolved pattern and no existing `PKG_FAIL_REASON`
+ *   has one synthesised from the first unresolved pattern.
+ * * Unresolved patterns are only reported for packages that do not already
+ *   have a `PKG_SKIP_REASON` or `PKG_FAIL_REASON`.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::ScanIndex;
+ * use pkgsrc::scanindex::Resolver;
+ *
+ * let scan = "PKGNAME=mktool-1.4.2\n\
+ *             ALL_DEPENDS=rust>=1.80:../../lang/rust\n\
+ *             PKGNAME=rust-1.86.0\n\
+ *             ALL_DEPENDS=\n\
+ *             PKGNAME=pkgin-25.1.0\n\
+ *             ALL_DEPENDS=libarchive>=3:../../archivers/libarchive\n";
+ * let mut pkgs = ScanIndex::from_reader(scan.as_bytes())
+ *     .collect::<Result<Vec<_>, _>>()?;
+ *
+ * let resolution = Resolver::new().resolve(&mut pkgs)?;
+ * assert_eq!(pkgs[0].depends()[0].pkgname(), "rust-1.86.0");
+ * assert_eq!(
+ *     pkgs[2].pkg_fail_reason.as_deref(),
+ *     Some("\"could not resolve dependency \"libarchive>=3\"\""),
+ * );
+ *
+ * let unresolved = resolution.unresolved();
+ * assert_eq!(unresolved.len(), 1);
+ * assert_eq!(unresolved[0].pattern(), "libarchive>=3");
+ * assert_eq!(unresolved[0].consumers()[0].pkgname(), "pkgin-25.1.0");
+ *
+ * for pkg in &pkgs {
+ *     print!("{}", pkg.presolve());
+ * }
+ * # Ok::<(), Box<dyn std::error::Error>>(())
+ * ```
+ *
+ * [`resolved_depends`]: ScanIndex::resolved_depends
+ */
+#[derive(Clone, Debug)]
+pub struct Resolver {
+    pbulk_tiebreak: bool,
+    partial_depends: bool,
+    fail_reason: bool,
+    report_all: bool,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    /**
+     * Create a new [`Resolver`] that matches `pbulk-resolve` behaviour.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pbulk_tiebreak: true,
+            partial_depends: false,
+            fail_reason: true,
+            report_all: false,
+        }
+    }
+
+    /**
+     * Choose how ties between equal versions are broken.  When `true` (the
+     * default) the lexicographically greater name wins as with pbulk,
+     * otherwise the smaller name wins as with `pkg_install`.
+     */
+    #[must_use]
+    pub fn with_pbulk_tiebreak(mut self, pbulk: bool) -> Self {
+        self.pbulk_tiebreak = pbulk;
+        self
+    }
+
+    /**
+     * Set [`resolved_depends`] to the patterns that did resolve even when
+     * others did not.  pbulk omits `DEPENDS` entirely in that case, which
+     * is the default.
+     *
+     * [`resolved_depends`]: ScanIndex::resolved_depends
+     */
+    #[must_use]
+    pub fn with_partial_depends(mut self, partial: bool) -> Self {
+        self.partial_depends = partial;
+        self
+    }
+
+    /**
+     * Whether to synthesise `PKG_FAIL_REASON` for packages with unresolved
+     * patterns.  Enabled by default.
+     */
+    #[must_use]
+    pub fn with_fail_reason(mut self, fail_reason: bool) -> Self {
+        self.fail_reason = fail_reason;
+        self
+    }
+
+    /**
+     * Whether to report unresolved patterns for packages that already have
+     * a `PKG_SKIP_REASON` or `PKG_FAIL_REASON`.  Disabled by default.
+     */
+    #[must_use]
+    pub fn with_report_all(mut self, report_all: bool) -> Self {
+        self.report_all = report_all;
+        self
+    }
+
+    /**
+     * Resolve the dependencies of every package, updating
+     * [`resolved_depends`] and `pkg_fail_reason` in place.  Every package
+     * is resolved, including those that will be skipped, as pbulk still
+     * reports their `DEPENDS`.
+     *
+     * Any existing [`resolved_depends`] are replaced.
+     *
+     * # Errors
+     *
+     * Returns [`DependError`] if an `ALL_DEPENDS` entry is invalid, or if a
+     * matching `PKGNAME` has an unparsable version.
+     *
+     * [`resolved_depends`]: ScanIndex::resolved_depends
+     */
+    pub fn resolve(
+        &self,
+        packages: &mut [ScanIndex],
+    ) -> Result<Resolution, DependError> {
+        /*
+         * Compile every dependency pattern into a single set, then resolve
+         * them all against every package name in one pass.
+         */
+        let mut set = PatternSet::with_capacity(packages.len());
+        let mut ids: Vec<Vec<usize>> = Vec::with_capacity(packages.len());
+        for pkg in packages.iter() {
+            let mut pkg_ids = Vec::new();
+            if let Some(deps) = &pkg.all_depends {
+                for dep in deps {
+                    pkg_ids.push(set.insert(dep?.pattern())?);
+                }
+            }
+            ids.push(pkg_ids);
+        }
+        let names = packages.iter().map(|p| p.pkgname.pkgname());
+        let best: Vec<Option<PkgName>> = if self.pbulk_tiebreak {
+            set.best_matches_pbulk(names)?
+        } else {
+            set.best_matches(names)?
+        }
+        .into_iter()
+        .map(|m| m.map(PkgName::new))
+        .collect();
+
+        let mut unresolved: Vec<Unresolved> = Vec::new();
+        let mut by_pattern: HashMap<usize, usize> = HashMap::new();
+        let mut pattern_count = 0;
+
+        for (pkg, pkg_ids) in packages.iter_mut().zip(ids) {
+            pattern_count += pkg_ids.len();
+            let report = self.report_all
+                || (!has_reason(&pkg.pkg_skip_reason)
+                    && !has_reason(&pkg.pkg_fail_reason));
+            let mut resolved = Vec::with_capacity(pkg_ids.len());
+            let mut first_unresolved = None;
+
+            for id in pkg_ids {
+                if let Some(name) = &best[id] {
+                    resolved.push(name.clone());
+                    continue;
+                }
+                let pattern = set.get(id).expect("valid pattern id").pattern();
+                first_unresolved.get_or_insert(pattern);
+                if report {
+                    let idx = *by_pattern.entry(id).or_insert_with(|| {
+                        unresolved.push(Unresolved {
+                            pattern: pattern.to_string(),
+                            consumers: Vec::new(),
+                        });
+                        unresolved.len() - 1
+                    });
+                    unresolved[idx].consumers.push(pkg.pkgname.clone());
+                }
+            }
+
+            let complete = first_unresolved.is_none();
+            pkg.resolved_depends = (!resolved.is_empty()
+                && (complete || self.partial_depends))
+                .then_some(resolved);
+
+            /*
+             * pbulk writes the quotes into the value itself so that the
+             * failure is visible as-is in the report.
+             */
+            if self.fail_reason
+                && !has_reason(&pkg.pkg_fail_reason)
+                && let Some(pattern) = first_unresolved
+            {
+                pkg.pkg_fail_reason = Some(format!(
+                    "\"could not resolve dependency \"{pattern}\"\""
+                ));
+            }
+        }
+
+        Ok(Resolution {
+            pattern_count,
+            unique_patterns: set.len(),
+            unresolved,
+        })
+    }
+}
+
+fn has_reason(reason: &Option<String>) -> bool {
+    reason.as_deref().is_some_and(|s| !s.is_empty())
+}
+
+/**
+ * A dependency pattern that did not match any package in the scan, along
+ * with the packages that depend on it.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unresolved {
+    pattern: String,
+    consumers: Vec<PkgName>,
+}
+
+impl Unresolved {
+    /**
+     * Return the unresolved pattern.
+     */
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /**
+     * Return the packages that depend on this pattern, in scan order.
+     */
+    #[must_use]
+    pub fn consumers(&self) -> &[PkgName] {
+        &self.consumers
+    }
+}
+
+/**
+ * The result of [`Resolver::resolve`].
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Resolution {
+    pattern_count: usize,
+    unique_patterns: usize,
+    unresolved: Vec<Unresolved>,
+}
+
+impl Resolution {
+    /**
+     * Return the total number of `ALL_DEPENDS` patterns across all packages.
+     */
+    #[must_use]
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_count
+    }
+
+    /**
+     * Return the number of distinct `ALL_DEPENDS` patterns.
+     */
+    #[must_use]
+    pub fn unique_patterns(&self) -> usize {
+        self.unique_patterns
+    }
+
+    /**
+     * Return the unresolved patterns, in the order they were first seen.
+     */
+    #[must_use]
+    pub fn unresolved(&self) -> &[Unresolved] {
+        &self.unresolved
+    }
+
+    /**
+     * Return true if every reported pattern was resolved.
+     */
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.unresolved.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(s: &str) -> Vec<ScanIndex> {
+        ScanIndex::from_reader(s.as_bytes())
+            .collect::<Result<_, _>>()
+            .expect("valid scan")
+    }
+
+    const SCAN: &str = "PKGNAME=a-1.0\n\
+                        ALL_DEPENDS=b>=1:../../x/b c-[0-9]*:../../x/c\n\
+                        PKGNAME=b-1.0\n\
+                        PKGNAME=b-1.0nb1\n\
+                        PKGNAME=d-1.0\n\
+                        PKG_SKIP_REASON=skipped\n\
+                        ALL_DEPENDS=c-[0-9]*:../../x/c\n";
+
+    #[test]
+    fn pbulk_defaults() -> Result<(), DependError> {
+        let mut pkgs = scan(SCAN);
+        let res = Resolver::new().resolve(&mut pkgs)?;
+        assert_eq!(res.pattern_count(), 3);
+        assert_eq!(res.unique_patterns(), 2);
+        assert_eq!(pkgs[0].resolved_depends, None);
+        assert_eq!(
+            pkgs[0].pkg_fail_reason.as_deref(),
+            Some("\"could not resolve dependency \"c-[0-9]*\"\"")
+        );
+        assert_eq!(res.unresolved().len(), 1);
+        assert_eq!(res.unresolved()[0].consumers(), [PkgName::new("a-1.0")]);
+        /* The skipped package is still given a PKG_FAIL_REASON. */
+        assert!(pkgs[3].pkg_fail_reason.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn options() -> Result<(), DependError> {
+        let mut pkgs = scan(SCAN);
+        let res = Resolver::new()
+            .with_partial_depends(true)
+            .with_fail_reason(false)
+            .with_report_all(true)
+            .resolve(&mut pkgs)?;
+        assert_eq!(pkgs[0].depends(), [PkgName::new("b-1.0nb1")]);
+        assert_eq!(pkgs[0].pkg_fail_reason, None);
+        assert_eq!(res.unresolved()[0].consumers().len(), 2);
+        assert!(!res.is_complete());
+        Ok(())
+    }
+}
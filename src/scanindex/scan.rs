@@ -0,0 +1,583 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+use super::ScanIndex;
+use crate::PkgPath;
+use crate::kv::KvWarning;
+use crate::tree::Tree;
+use hashbrown::HashSet;
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::thread;
+
+/**
+ * Runs `make pbulk-index` for a package.
+ *
+ * [`Scanner`] calls this from multiple threads at once.  [`MakeRunner`]
+ * runs the real command; tests and alternative builders can implement
+ * this to return canned output or run the command elsewhere, for example
+ * inside a sandbox.
+ */
+pub trait CommandRunner: Sync {
+    /**
+     * Run `make pbulk-index` for `pkgpath` within the pkgsrc tree at
+     * `pkgsrc`, returning its standard output.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the command could not be run or did not
+     * exit successfully.
+     */
+    fn pbulk_index(
+        &self,
+        pkgsrc: &Path,
+        pkgpath: &PkgPath,
+    ) -> io::Result<Vec<u8>>;
+}
+
+/**
+ * A [`CommandRunner`] that executes `make pbulk-index` in each package
+ * directory.
+ */
+#[derive(Clone, Debug)]
+pub struct MakeRunner {
+    make: PathBuf,
+    env: Vec<(OsString, OsString)>,
+}
+
+impl Default for MakeRunner {
+    fn default() -> Self {
+        Self::new("bmake")
+    }
+}
+
+impl MakeRunner {
+    /**
+     * Create a runner using the given `make` command, which must be a BSD
+     * make.
+     */
+    #[must_use]
+    pub fn new(make: impl Into<PathBuf>) -> Self {
+        Self {
+            make: make.into(),
+            env: Vec::new(),
+        }
+    }
+
+    /**
+     * Set an environment variable for every command, for example
+     * `PKG_PATH` or `MAKECONF`.
+     */
+    #[must_use]
+    pub fn with_env(
+        mut self,
+        key: impl Into<OsString>,
+        value: impl Into<OsString>,
+    ) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+}
+
+impl CommandRunner for MakeRunner {
+    fn pbulk_index(
+        &self,
+        pkgsrc: &Path,
+        pkgpath: &PkgPath,
+    ) -> io::Result<Vec<u8>> {
+        let output = Command::new(&self.make)
+            .arg("pbulk-index")
+            .current_dir(pkgsrc.join(pkgpath.as_path()))
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(format!(
+                "{} pbulk-index failed ({}): {}",
+                self.make.display(),
+                output.status,
+                stderr.trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
+
+/**
+ * A package that could not be scanned.
+ */
+#[derive(Debug)]
+pub struct ScanFailure {
+    pkgpath: PkgPath,
+    error: io::Error,
+}
+
+impl ScanFailure {
+    /**
+     * Return the package that failed.
+     */
+    #[must_use]
+    pub fn pkgpath(&self) -> &PkgPath {
+        &self.pkgpath
+    }
+
+    /**
+     * Return the reason it failed.
+     */
+    #[must_use]
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+}
+
+/**
+ * The output of [`Scanner::scan`].
+ */
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    packages: Vec<ScanIndex>,
+    failures: Vec<ScanFailure>,
+    warnings: Vec<(PkgPath, KvWarning)>,
+}
+
+impl ScanResult {
+    /**
+     * Return the scanned packages, ordered by `PKGPATH`.  Each has
+     * [`pkg_location`] set to the `PKGPATH` it was scanned from.
+     *
+     * [`pkg_location`]: ScanIndex::pkg_location
+     */
+    #[must_use]
+    pub fn packages(&self) -> &[ScanIndex] {
+        &self.packages
+    }
+
+    /**
+     * Consume the result, returning the scanned packages.
+     */
+    #[must_use]
+    pub fn into_packages(self) -> Vec<ScanIndex> {
+        self.packages
+    }
+
+    /**
+     * Return the packages that could not be scanned, ordered by
+     * `PKGPATH`.  No records are returned for these.
+     */
+    #[must_use]
+    pub fn failures(&self) -> &[ScanFailure] {
+        &self.failures
+    }
+
+    /**
+     * Return values that could not be parsed but did not fail a record,
+     * such as an invalid `PBULK_WEIGHT`, with the `PKGPATH` they came from.
+     */
+    #[must_use]
+    pub fn warnings(&self) -> &[(PkgPath, KvWarning)] {
+        &self.warnings
+    }
+}
+
+enum Outcome {
+    Scanned(Vec<ScanIndex>, Vec<KvWarning>),
+    Failed(io::Error),
+}
+
+struct Queue {
+    pending: VecDeque<PkgPath>,
+    seen: HashSet<PkgPath>,
+    active: usize,
+    done: Vec<(PkgPath, Outcome)>,
+}
+
+/*
+ * Marks a worker as busy until dropped.  Dropping also happens when a
+ * CommandRunner panics, so that the other workers do not wait forever for
+ * it and the panic reaches Scanner::scan.
+ */
+struct Active<'a> {
+    queue: &'a Mutex<Queue>,
+    cond: &'a Condvar,
+}
+
+impl Drop for Active<'_> {
+    fn drop(&mut self) {
+        let mut guard =
+            self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        guard.active -= 1;
+        self.cond.notify_all();
+    }
+}
+
+/**
+ * Scan a pkgsrc tree, running `make pbulk-index` for each package in
+ * parallel.
+ *
+ * By default every package in the tree is scanned.  With
+ * [`with_pkgpaths`](Self::with_pkgpaths) only the listed packages are
+ * scanned, along with any packages they depend on, as pbulk does for a
+ * limited bulk build.
+ *
+ * # Example
+ *
+ * ```no_run
+ * use pkgsrc::scanindex::{MakeRunner, Resolver, Scanner};
+ *
+ * let scan = Scanner::new("/usr/pkgsrc", MakeRunner::default())
+ *     .with_jobs(8)
+ *     .scan()?;
+ * for failure in scan.failures() {
+ *     eprintln!("{}: {}", failure.pkgpath(), failure.error());
+ * }
+ * let mut packages = scan.into_packages();
+ * let resolution = Resolver::new().resolve(&mut packages)?;
+ * # Ok::<(), Box<dyn std::error::Error>>(())
+ * ```
+ */
+#[derive(Debug)]
+pub struct Scanner<R> {
+    pkgsrc: PathBuf,
+    runner: R,
+    jobs: usize,
+    pkgpaths: Option<Vec<PkgPath>>,
+    follow_depends: bool,
+}
+
+impl<R: CommandRunner> Scanner<R> {
+    /**
+     * Create a scanner for the pkgsrc tree at `pkgsrc`, running commands
+     * with `runner`.  The number of parallel jobs defaults to the available
+     * parallelism.
+     */
+    pub fn new(pkgsrc: impl Into<PathBuf>, runner: R) -> Self {
+        Self {
+            pkgsrc: pkgsrc.into(),
+            runner,
+            jobs: thread::available_parallelism().map_or(1, usize::from),
+            pkgpaths: None,
+            follow_depends: true,
+        }
+    }
+
+    /**
+     * Set the number of packages to scan in parallel.  Values below one
+     * are treated as one.
+     */
+    #[must_use]
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /**
+     * Scan only the listed packages instead of the whole tree.
+     */
+    #[must_use]
+    pub fn with_pkgpaths<I>(mut self, pkgpaths: I) -> Self
+    where
+        I: IntoIterator<Item = PkgPath>,
+    {
+        self.pkgpaths = Some(pkgpaths.into_iter().collect());
+        self
+    }
+
+    /**
+     * Whether to also scan packages named in the `ALL_DEPENDS` of scanned
+     * packages.  Enabled by default; this only has an effect when scanning
+     * a limited list, as a full scan already includes every package.
+     */
+    #[must_use]
+    pub fn with_follow_depends(mut self, follow: bool) -> Self {
+        self.follow_depends = follow;
+        self
+    }
+
+    /**
+     * Return every package listed in the tree's `SUBDIR` entries, as read
+     * by [`Tree`](crate::tree::Tree).  Packages listed under a condition
+     * are included, commented-out packages are not.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the tree cannot be read.
+     */
+    pub fn pkgpaths(&self) -> io::Result<Vec<PkgPath>> {
+        Ok(Tree::open(&self.pkgsrc)?.pkgpaths())
+    }
+
+    /**
+     * Run the scan.  Failures of individual packages are recorded in the
+     * [`ScanResult`] rather than stopping the scan.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the list of packages cannot be read from
+     * the tree.
+     */
+    pub fn scan(&self) -> io::Result<ScanResult> {
+        let initial = match &self.pkgpaths {
+            Some(list) => list.clone(),
+            None => self.pkgpaths()?,
+        };
+        let follow = self.follow_depends && self.pkgpaths.is_some();
+
+        let mut queue = Queue {
+            pending: VecDeque::new(),
+            seen: HashSet::new(),
+            active: 0,
+            done: Vec::new(),
+        };
+        for pkgpath in initial {
+            if queue.seen.insert(pkgpath.clone()) {
+                queue.pending.push_back(pkgpath);
+            }
+        }
+        let queue = Mutex::new(queue);
+        let cond = Condvar::new();
+
+        thread::scope(|s| {
+            for _ in 0..self.jobs {
+                s.spawn(|| self.worker(&queue, &cond, follow));
+            }
+        });
+
+        let mut done = queue.into_inner().expect("scan queue poisoned").done;
+        done.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut result = ScanResult::default();
+        for (pkgpath, outcome) in done {
+            match outcome {
+                Outcome::Scanned(records, warnings) => {
+                    result.packages.extend(records);
+                    result.warnings.extend(
+                        warnings.into_iter().map(|w| (pkgpath.clone(), w)),
+                    );
+                }
+                Outcome::Failed(error) => {
+                    result.failures.push(ScanFailure { pkgpath, error });
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn worker(&self, queue: &Mutex<Queue>, cond: &Condvar, follow: bool) {
+        let mut guard = queue.lock().expect("scan queue poisoned");
+        loop {
+            let Some(pkgpath) = guard.pending.pop_front() else {
+                if guard.active == 0 {
+                    cond.notify_all();
+                    return;
+                }
+                guard = cond.wait(guard).expect("scan queue poisoned");
+                continue;
+            };
+            guard.active += 1;
+            drop(guard);
+            let active = Active { queue, cond };
+
+            let outcome = self.scan_one(&pkgpath);
+            let mut depends = Vec::new();
+            if follow && let Outcome::Scanned(records, _) = &outcome {
+                for dep in records
+                    .iter()
+                    .filter_map(|r| r.all_depends.as_ref())
+                    .flat_map(|d| d.iter().flatten())
+                {
+                    if let Ok(path) = PkgPath::new(dep.pkgpath()) {
+                        depends.push(path);
+                    }
+                }
+            }
+
+            guard = queue.lock().expect("scan queue poisoned");
+            for dep in depends {
+                if guard.seen.insert(dep.clone()) {
+                    guard.pending.push_back(dep);
+                }
+            }
+            guard.done.push((pkgpath, outcome));
+            drop(guard);
+            drop(active);
+            guard = queue.lock().expect("scan queue poisoned");
+        }
+    }
+
+    fn scan_one(&self, pkgpath: &PkgPath) -> Outcome {
+        let output = match self.runner.pbulk_index(&self.pkgsrc, pkgpath) {
+            Ok(output) => output,
+            Err(e) => return Outcome::Failed(e),
+        };
+        let mut iter = ScanIndex::from_reader(output.as_slice());
+        let mut records = Vec::new();
+        for record in iter.by_ref() {
+            match record {
+                Ok(mut record) => {
+                    if record.pkg_location.is_none() {
+                        record.pkg_location = Some(pkgpath.clone());
+                    }
+                    records.push(record);
+                }
+                Err(e) => return Outcome::Failed(e),
+            }
+        }
+        if records.is_empty() {
+            return Outcome::Failed(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "pbulk-index produced no output",
+            ));
+        }
+        for record in &records {
+            if let Some(deps) = &record.all_depends
+                && let Some(Err(e)) = deps.iter().find(Result::is_err)
+            {
+                return Outcome::Failed(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: invalid ALL_DEPENDS: {e}", record.pkgname),
+                ));
+            }
+        }
+        Outcome::Scanned(records, iter.into_warnings())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hashbrown::HashMap;
+
+    struct FakeRunner(HashMap<&'static str, &'static str>);
+
+    impl CommandRunner for FakeRunner {
+        fn pbulk_index(
+            &self,
+            _pkgsrc: &Path,
+            pkgpath: &PkgPath,
+        ) -> io::Result<Vec<u8>> {
+            match self.0.get(pkgpath.as_str()) {
+                Some(out) => Ok(out.as_bytes().to_vec()),
+                None => Err(io::Error::other("make failed")),
+            }
+        }
+    }
+
+    fn runner() -> FakeRunner {
+        FakeRunner(HashMap::from([
+            (
+                "devel/bar",
+                "PKGNAME=bar-1.0\n\
+                 ALL_DEPENDS=libfoo>=1:../../devel/libfoo \
+                 baz-[0-9]*:../../lang/baz\n\
+                 PBULK_WEIGHT=heavy\n",
+            ),
+            ("devel/libfoo", "PKGNAME=libfoo-1.0\nALL_DEPENDS=\n"),
+            ("lang/qux", "PKGNAME=qux-1.0\nALL_DEPENDS=bad\n"),
+        ]))
+    }
+
+    fn path(s: &str) -> PkgPath {
+        PkgPath::new(s).expect("valid pkgpath")
+    }
+
+    #[test]
+    fn follow_depends() -> io::Result<()> {
+        let scan = Scanner::new("/nonexistent", runner())
+            .with_jobs(4)
+            .with_pkgpaths([path("devel/bar"), path("lang/qux")])
+            .scan()?;
+        let names: Vec<_> = scan
+            .packages()
+            .iter()
+            .map(|p| p.pkgname.to_string())
+            .collect();
+        assert_eq!(names, ["bar-1.0", "libfoo-1.0"]);
+        assert_eq!(
+            scan.packages()[1].pkg_location.as_ref(),
+            Some(&path("devel/libfoo"))
+        );
+        let failed: Vec<_> = scan
+            .failures()
+            .iter()
+            .map(|f| f.pkgpath().as_str())
+            .collect();
+        assert_eq!(failed, ["lang/baz", "lang/qux"]);
+        assert_eq!(scan.warnings().len(), 1);
+        assert_eq!(scan.warnings()[0].0, path("devel/bar"));
+        assert_eq!(scan.warnings()[0].1.variable, "PBULK_WEIGHT");
+
+        let scan = Scanner::new("/nonexistent", runner())
+            .with_pkgpaths([path("devel/bar")])
+            .with_follow_depends(false)
+            .scan()?;
+        assert_eq!(scan.packages().len(), 1);
+        assert!(scan.failures().is_empty());
+        Ok(())
+    }
+
+    struct PanicRunner;
+
+    impl CommandRunner for PanicRunner {
+        fn pbulk_index(
+            &self,
+            _pkgsrc: &Path,
+            pkgpath: &PkgPath,
+        ) -> io::Result<Vec<u8>> {
+            if pkgpath.as_str() == "devel/bar" {
+                panic!("runner panicked");
+            }
+            let (_, name) =
+                pkgpath.as_str().split_once('/').unwrap_or_default();
+            Ok(format!("PKGNAME={name}-1.0\n").into_bytes())
+        }
+    }
+
+    #[test]
+    fn runner_panic() {
+        /* The panic must reach the caller rather than hang the workers. */
+        let scanner = Scanner::new("/nonexistent", PanicRunner)
+            .with_jobs(4)
+            .with_pkgpaths(
+                ["devel/bar", "devel/libfoo", "lang/baz", "lang/qux"].map(path),
+            );
+        let result = std::panic::catch_unwind(|| scanner.scan());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn walk_tree() -> io::Result<()> {
+        let pkgsrc =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pkgsrc");
+        let scanner = Scanner::new(pkgsrc, runner()).with_jobs(2);
+        let pkgpaths: Vec<_> = scanner
+            .pkgpaths()?
+            .iter()
+            .map(|p| p.as_str().to_string())
+            .collect();
+        assert_eq!(
+            pkgpaths,
+            ["devel/bar", "devel/libfoo", "devel/netbsd-only", "lang/baz"]
+        );
+        let scan = scanner.scan()?;
+        assert_eq!(scan.packages().len(), 2);
+        assert_eq!(scan.failures().len(), 2);
+        Ok(())
+    }
+}
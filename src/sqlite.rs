@@ -0,0 +1,508 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Export [`pkg_summary(5)`] and pkgdb data to a SQLite database.
+ *
+ * This module is only available with the `sqlite` feature enabled.
+ *
+ * A [`Database`] stores any number of named repositories, each containing a
+ * set of [`Summary`] entries.  A repository can be a remote `pkg_summary`
+ * file, or the installed packages from a [`PkgDB`].
+ *
+ * Updating a repository replaces all of its rows inside a single
+ * transaction, leaving all other repositories untouched.  A checksum of the
+ * repository contents is stored, and if it is unchanged the update is
+ * skipped entirely.
+ *
+ * # Schema
+ *
+ * ```sql
+ * repositories (id, name UNIQUE, checksum)
+ * packages     (id, repository_id, pkgname, pkgbase, pkgversion, pkgpath,
+ *               comment, description, build_date, machine_arch, opsys,
+ *               os_version, pkgtools_version, homepage, license,
+ *               pkg_options, prev_pkgpath, size_pkg, file_name, file_size,
+ *               file_cksum)
+ * categories   (package_id, category)
+ * depends      (package_id, pattern)
+ * conflicts    (package_id, pattern)
+ * provides     (package_id, path)
+ * requires     (package_id, path)
+ * supersedes   (package_id, pattern)
+ * ```
+ *
+ * Rows in all package tables are removed automatically when their
+ * repository is replaced or removed.  `description` contains all
+ * `DESCRIPTION` lines joined with newlines.
+ *
+ * # Example
+ *
+ * ```no_run
+ * use flate2::read::GzDecoder;
+ * use pkgsrc::PkgDB;
+ * use pkgsrc::sqlite::Database;
+ * use pkgsrc::summary::Summary;
+ * use std::fs::File;
+ * use std::io::BufReader;
+ *
+ * # fn main() -> Result<(), Box<dyn std::error::Error>> {
+ * let mut db = Database::open("pkgsrc.db")?;
+ *
+ * let file = File::open("pkg_summary.gz")?;
+ * let reader = BufReader::new(GzDecoder::new(file));
+ * let pkgs = Summary::from_reader(reader).collect::<Result<Vec<_>, _>>()?;
+ * db.update_repository("https://cdn.example.com/packages/All", &pkgs)?;
+ *
+ * db.update_pkgdb("installed", PkgDB::open("/var/db/pkg")?)?;
+ *
+ * let count: i64 = db.connection().query_row(
+ *     "SELECT COUNT(*) FROM packages",
+ *     [],
+ *     |row| row.get(0),
+ * )?;
+ * println!("{count} packages");
+ * # Ok(())
+ * # }
+ * ```
+ *
+ * [`pkg_summary(5)`]: https://man.netbsd.org/pkg_summary.5
+ */
+
+use crate::digest::hex_encode;
+use crate::pkgdb::PkgDB;
+use crate::summary::Summary;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+/**
+ * Database schema, created on open if it does not already exist.
+ */
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS repositories (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        checksum TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS packages (
+        id INTEGER PRIMARY KEY,
+        repository_id INTEGER NOT NULL
+            REFERENCES repositories(id) ON DELETE CASCADE,
+        pkgname TEXT NOT NULL,
+        pkgbase TEXT NOT NULL,
+        pkgversion TEXT NOT NULL,
+        pkgpath TEXT NOT NULL,
+        comment TEXT NOT NULL,
+        description TEXT NOT NULL,
+        build_date TEXT NOT NULL,
+        machine_arch TEXT NOT NULL,
+        opsys TEXT NOT NULL,
+        os_version TEXT NOT NULL,
+        pkgtools_version TEXT NOT NULL,
+        homepage TEXT,
+        license TEXT,
+        pkg_options TEXT,
+        prev_pkgpath TEXT,
+        size_pkg INTEGER NOT NULL,
+        file_name TEXT,
+        file_size INTEGER,
+        file_cksum TEXT
+    );
+    CREATE INDEX IF NOT EXISTS packages_repository_id
+        ON packages(repository_id);
+    CREATE INDEX IF NOT EXISTS packages_pkgbase ON packages(pkgbase);
+    CREATE INDEX IF NOT EXISTS packages_pkgpath ON packages(pkgpath);
+    CREATE TABLE IF NOT EXISTS categories (
+        package_id INTEGER NOT NULL
+            REFERENCES packages(id) ON DELETE CASCADE,
+        category TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS depends (
+        package_id INTEGER NOT NULL
+            REFERENCES packages(id) ON DELETE CASCADE,
+        pattern TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS conflicts (
+        package_id INTEGER NOT NULL
+            REFERENCES packages(id) ON DELETE CASCADE,
+        pattern TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS provides (
+        package_id INTEGER NOT NULL
+            REFERENCES packages(id) ON DELETE CASCADE,
+        path TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS requires (
+        package_id INTEGER NOT NULL
+            REFERENCES packages(id) ON DELETE CASCADE,
+        path TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS supersedes (
+        package_id INTEGER NOT NULL
+            REFERENCES packages(id) ON DELETE CASCADE,
+        pattern TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS categories_package_id
+        ON categories(package_id);
+    CREATE INDEX IF NOT EXISTS depends_package_id ON depends(package_id);
+    CREATE INDEX IF NOT EXISTS conflicts_package_id ON conflicts(package_id);
+    CREATE INDEX IF NOT EXISTS provides_package_id ON provides(package_id);
+    CREATE INDEX IF NOT EXISTS provides_path ON provides(path);
+    CREATE INDEX IF NOT EXISTS requires_package_id ON requires(package_id);
+    CREATE INDEX IF NOT EXISTS supersedes_package_id
+        ON supersedes(package_id);
+";
+
+/**
+ * Errors returned by [`Database`] operations.
+ */
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum SqliteError {
+    /// Transparent [`rusqlite::Error`].
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    /// Reading an installed package failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/**
+ * A type alias for the result from [`Database`] operations, with
+ * [`SqliteError`] returned in [`Err`] variants.
+ */
+pub type Result<T> = std::result::Result<T, SqliteError>;
+
+/**
+ * A SQLite database of package repositories.
+ *
+ * See the [module documentation](self) for the schema.
+ */
+#[derive(Debug)]
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    /**
+     * Open or create a database file, creating the schema if necessary.
+     *
+     * # Errors
+     *
+     * Returns [`SqliteError::Sqlite`] if the database cannot be opened or
+     * the schema cannot be created.
+     */
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /**
+     * Create a new in-memory database.
+     *
+     * # Errors
+     *
+     * Returns [`SqliteError::Sqlite`] if the schema cannot be created.
+     */
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /**
+     * Return the underlying connection, for running queries.
+     */
+    #[must_use]
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /**
+     * Replace the contents of the named repository with `pkgs`, creating
+     * the repository if it does not exist.
+     *
+     * Returns `false` without modifying the database if the repository
+     * contents are unchanged since the last update.
+     *
+     * # Errors
+     *
+     * Returns [`SqliteError::Sqlite`] if any statement fails, in which case
+     * the repository is left unchanged.
+     */
+    pub fn update_repository(
+        &mut self,
+        name: &str,
+        pkgs: &[Summary],
+    ) -> Result<bool> {
+        let checksum = checksum(pkgs);
+        let current: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT checksum FROM repositories WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if current.as_deref() == Some(checksum.as_str()) {
+            return Ok(false);
+        }
+
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM repositories WHERE name = ?1", [name])?;
+        tx.execute(
+            "INSERT INTO repositories (name, checksum) VALUES (?1, ?2)",
+            params![name, checksum],
+        )?;
+        let repository_id = tx.last_insert_rowid();
+        insert_packages(&tx, repository_id, pkgs)?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /**
+     * Replace the contents of the named repository with the installed
+     * packages in `pkgdb`, converted using
+     * [`InstalledPackage::to_summary`].
+     *
+     * Returns `false` if the installed packages are unchanged since the
+     * last update.
+     *
+     * # Errors
+     *
+     * Returns [`SqliteError::Io`] if any package cannot be read, or
+     * [`SqliteError::Sqlite`] if any statement fails.  In either case the
+     * repository is left unchanged.
+     *
+     * [`InstalledPackage::to_summary`]: crate::pkgdb::InstalledPackage::to_summary
+     */
+    pub fn update_pkgdb(&mut self, name: &str, pkgdb: PkgDB) -> Result<bool> {
+        let mut pkgs = pkgdb
+            .map(|pkg| pkg.and_then(|p| p.to_summary()))
+            .collect::<io::Result<Vec<_>>>()?;
+        /*
+         * Directory order is arbitrary, sort so that the checksum is stable.
+         */
+        pkgs.sort_by(|a, b| a.pkgname().cmp(b.pkgname()));
+        self.update_repository(name, &pkgs)
+    }
+
+    /**
+     * Remove the named repository and all of its packages.  Returns `false`
+     * if no such repository exists.
+     *
+     * # Errors
+     *
+     * Returns [`SqliteError::Sqlite`] if the statement fails.
+     */
+    pub fn remove_repository(&mut self, name: &str) -> Result<bool> {
+        let n = self
+            .conn
+            .execute("DELETE FROM repositories WHERE name = ?1", [name])?;
+        Ok(n > 0)
+    }
+
+    /**
+     * Return the names of all repositories in the database.
+     *
+     * # Errors
+     *
+     * Returns [`SqliteError::Sqlite`] if the query fails.
+     */
+    pub fn repositories(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM repositories ORDER BY name")?;
+        let names = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(names)
+    }
+}
+
+/*
+ * Checksum the repository contents as written out in pkg_summary format.
+ */
+fn checksum(pkgs: &[Summary]) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for pkg in pkgs {
+        hasher.update(pkg.to_string().as_bytes());
+        hasher.update(b"\n");
+    }
+    hex_encode(&hasher.finalize())
+}
+
+fn insert_packages(
+    tx: &Transaction<'_>,
+    repository_id: i64,
+    pkgs: &[Summary],
+) -> rusqlite::Result<()> {
+    let mut package = tx.prepare(
+        "INSERT INTO packages (
+            repository_id, pkgname, pkgbase, pkgversion, pkgpath, comment,
+            description, build_date, machine_arch, opsys, os_version,
+            pkgtools_version, homepage, license, pkg_options, prev_pkgpath,
+            size_pkg, file_name, file_size, file_cksum
+        ) VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+            ?16, ?17, ?18, ?19, ?20
+        )",
+    )?;
+    let mut categories = tx.prepare(
+        "INSERT INTO categories (package_id, category) VALUES (?1, ?2)",
+    )?;
+    let mut depends = tx
+        .prepare("INSERT INTO depends (package_id, pattern) VALUES (?1, ?2)")?;
+    let mut conflicts = tx.prepare(
+        "INSERT INTO conflicts (package_id, pattern) VALUES (?1, ?2)",
+    )?;
+    let mut provides =
+        tx.prepare("INSERT INTO provides (package_id, path) VALUES (?1, ?2)")?;
+    let mut requires =
+        tx.prepare("INSERT INTO requires (package_id, path) VALUES (?1, ?2)")?;
+    let mut supersedes = tx.prepare(
+        "INSERT INTO supersedes (package_id, pattern) VALUES (?1, ?2)",
+    )?;
+
+    for pkg in pkgs {
+        let pkgname = pkg.pkgname();
+        let size_pkg = i64::try_from(pkg.size_pkg()).unwrap_or(i64::MAX);
+        let file_size = pkg
+            .file_size()
+            .map(|n| i64::try_from(n).unwrap_or(i64::MAX));
+        package.execute(params![
+            repository_id,
+            pkgname.pkgname(),
+            pkgname.pkgbase(),
+            pkgname.pkgversion(),
+            pkg.pkgpath(),
+            pkg.comment(),
+            pkg.description().join("\n"),
+            pkg.build_date(),
+            pkg.machine_arch(),
+            pkg.opsys(),
+            pkg.os_version(),
+            pkg.pkgtools_version(),
+            pkg.homepage(),
+            pkg.license(),
+            pkg.pkg_options(),
+            pkg.prev_pkgpath(),
+            size_pkg,
+            pkg.file_name(),
+            file_size,
+            pkg.file_cksum(),
+        ])?;
+        let id = tx.last_insert_rowid();
+        for category in pkg.categories() {
+            categories.execute(params![id, category])?;
+        }
+        for pattern in pkg.depends().unwrap_or_default() {
+            depends.execute(params![id, pattern])?;
+        }
+        for pattern in pkg.conflicts().unwrap_or_default() {
+            conflicts.execute(params![id, pattern])?;
+        }
+        for path in pkg.provides().unwrap_or_default() {
+            provides.execute(params![id, path])?;
+        }
+        for path in pkg.requires().unwrap_or_default() {
+            requires.execute(params![id, path])?;
+        }
+        for pattern in pkg.supersedes().unwrap_or_default() {
+            supersedes.execute(params![id, pattern])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::test_entry;
+
+    fn count(db: &Database, sql: &str) -> Result<i64> {
+        Ok(db.connection().query_row(sql, [], |row| row.get(0))?)
+    }
+
+    #[test]
+    fn update_repository() -> Result<()> {
+        let mut db = Database::open_in_memory()?;
+        let base = vec![
+            test_entry(
+                "foo-1.0",
+                &["DEPENDS=bar>=1.0", "REQUIRES=/usr/pkg/lib/libbar.so"],
+            ),
+            test_entry("bar-1.0", &["PROVIDES=/usr/pkg/lib/libbar.so"]),
+        ];
+        let local = vec![test_entry("baz-1.0", &["CONFLICTS=foo-[0-9]*"])];
+
+        assert!(db.update_repository("base", &base)?);
+        assert!(db.update_repository("local", &local)?);
+        assert_eq!(db.repositories()?, ["base", "local"]);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM packages")?, 3);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM categories")?, 6);
+
+        let dep: String = db.connection().query_row(
+            "SELECT p.pkgname FROM packages p
+               JOIN requires r ON r.package_id = p.id
+               JOIN provides v ON v.path = r.path
+              WHERE v.package_id = (
+                  SELECT id FROM packages WHERE pkgname = 'bar-1.0')",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(dep, "foo-1.0");
+
+        /* Unchanged contents are skipped. */
+        assert!(!db.update_repository("base", &base)?);
+
+        /* Changed contents only replace that repository. */
+        let base = vec![test_entry("foo-2.0", &[])];
+        assert!(db.update_repository("base", &base)?);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM packages")?, 2);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM depends")?, 0);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM provides")?, 0);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM conflicts")?, 1);
+
+        assert!(db.remove_repository("local")?);
+        assert!(!db.remove_repository("local")?);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM conflicts")?, 0);
+        assert_eq!(count(&db, "SELECT COUNT(*) FROM categories")?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn update_pkgdb() -> Result<()> {
+        let mut db = Database::open_in_memory()?;
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/pkgdb");
+        assert!(db.update_pkgdb("installed", PkgDB::open(path)?)?);
+        assert!(!db.update_pkgdb("installed", PkgDB::open(path)?)?);
+        let pattern: String = db.connection().query_row(
+            "SELECT pattern FROM depends",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(pattern, "cwrappers>=20150314");
+        Ok(())
+    }
+}
@@ -43,6 +43,10 @@
  * Once validated, [`Summary`] provides many access [`methods`] to retrieve
  * information about each variable in a summary entry.
  *
+ * The [`lint`] module performs semantic checks across a complete set of
+ * entries, such as unresolved dependencies or duplicate package names, and
+ * the [`merge`] module combines several repositories into a single view.
+ *
  * ## Examples
  *
  * Read [`pkg_summary.gz`] and print list of packages in `pkg_info` format,
@@ -115,6 +119,10 @@
  * [`pkg_summary.gz`]: https://github.com/jperkin/pkgsrc-rs/blob/master/tests/data/summary/pkg_summary.gz
  *
  */
+
+pub mod lint;
+pub mod merge;
+
 use std::fmt;
 use std::io::{self, BufRead};
 use std::num::ParseIntError;
@@ -197,6 +205,17 @@ pub type Result<T> = std::result::Result<T, SummaryError>;
  */
 impl fmt::Display for Summary {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        /* Entries parsed in preserve mode are written back verbatim. */
+        if let Some(lines) = &self.original.0 {
+            for line in lines {
+                write!(f, "{line}")?;
+            }
+            if lines.last().is_some_and(|l| !l.ends_with('\n')) {
+                writeln!(f)?;
+            }
+            return Ok(());
+        }
+
         macro_rules! write_required_field {
             ($field:expr, $name:expr) => {
                 writeln!(f, "{}={}", $name, $field)?;
@@ -341,6 +360,34 @@ pub struct Summary {
 
     #[kv(variable = "SUPERSEDES", multiline)]
     supersedes: Option<Vec<String>>,
+
+    /*
+     * The original input lines including their line endings, retained when
+     * parsed in preserve mode so that Display can reproduce the input
+     * exactly.
+     */
+    #[kv(skip)]
+    original: Original,
+}
+
+/*
+ * The preserved input of a Summary.  It is only a formatting detail, so it
+ * is ignored by equality and hashing, and an entry compares equal whether
+ * or not it was parsed in preserve mode.
+ */
+#[derive(Clone, Debug, Default)]
+struct Original(Option<Vec<String>>);
+
+impl PartialEq for Original {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Original {}
+
+impl std::hash::Hash for Original {
+    fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
 }
 
 /**
@@ -370,6 +417,7 @@ pub struct SummaryBuilder {
     lines: Vec<String>,
     allow_unknown: bool,
     allow_incomplete: bool,
+    preserve: bool,
 }
 
 impl SummaryBuilder {
@@ -448,6 +496,51 @@ impl SummaryBuilder {
         self
     }
 
+    /**
+     * Preserve the original input, including unknown variables, the order
+     * of variables, blank lines and `\r\n` line endings, so that the
+     * [`Display`] output of the resulting [`Summary`] is byte-for-byte
+     * identical to the input.  Lines added with [`var`](Self::var) are
+     * joined with `\n`, so a line ending in `\r` is kept as `\r\n`, and a
+     * final `\n` is added if missing.  Implies [`allow_unknown`].
+     *
+     * Unknown variables are available from [`Summary::unknown_variables`].
+     *
+     * The preserved input is always what [`Display`] writes, and is ignored
+     * by [`PartialEq`] and [`Hash`], so an entry parsed with and without
+     * `preserve` compares equal and hashes the same.
+     *
+     * ## Example
+     *
+     * ```
+     * use pkgsrc::summary::SummaryBuilder;
+     *
+     * let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/summary/mktool.txt");
+     * let input = std::fs::read_to_string(path).expect("failed to read mktool.txt");
+     * let input = format!("PKG_SIGNER=builder@example.com\n{input}");
+     *
+     * let pkg = SummaryBuilder::new()
+     *     .vars(input.lines())
+     *     .preserve(true)
+     *     .build()
+     *     .expect("build failed");
+     * assert_eq!(pkg.to_string(), input);
+     * assert_eq!(
+     *     pkg.unknown_variables().collect::<Vec<_>>(),
+     *     [("PKG_SIGNER", "builder@example.com")]
+     * );
+     * ```
+     *
+     * [`Display`]: std::fmt::Display
+     * [`Hash`]: std::hash::Hash
+     * [`allow_unknown`]: SummaryBuilder::allow_unknown
+     */
+    #[must_use]
+    pub fn preserve(mut self, yes: bool) -> Self {
+        self.preserve = yes;
+        self
+    }
+
     /**
      * Validate and finalize the [`Summary`].
      *
@@ -525,7 +618,12 @@ impl SummaryBuilder {
      */
     pub fn build(self) -> Result<Summary> {
         let input = self.lines.join("\n");
-        parse_summary(&input, self.allow_unknown, self.allow_incomplete)
+        parse_summary(
+            &input,
+            self.allow_unknown,
+            self.allow_incomplete,
+            self.preserve,
+        )
     }
 }
 
@@ -582,6 +680,7 @@ impl Summary {
             requires,
             size_pkg,
             supersedes,
+            original: Original::default(),
         }
     }
 
@@ -617,6 +716,7 @@ impl Summary {
             entry_start: 0,
             allow_unknown: false,
             allow_incomplete: false,
+            preserve: false,
         }
     }
 
@@ -1622,6 +1722,27 @@ impl Summary {
     pub fn supersedes(&self) -> Option<&[String]> {
         self.supersedes.as_deref()
     }
+
+    /**
+     * Returns an iterator over any variables not defined by
+     * [`pkg_summary(5)`], as `(VARIABLE, VALUE)` pairs in their original
+     * order.
+     *
+     * Unknown variables are only retained when parsed with
+     * [`SummaryBuilder::preserve`] or [`SummaryIter::preserve`], otherwise
+     * the iterator is always empty.
+     *
+     * [`pkg_summary(5)`]: https://man.netbsd.org/pkg_summary.5
+     */
+    pub fn unknown_variables(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.original
+            .0
+            .iter()
+            .flatten()
+            .map(|line| line.trim_end_matches(['\r', '\n']))
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| !Summary::VARIABLES.contains(key))
+    }
 }
 
 impl FromStr for Summary {
@@ -1636,13 +1757,20 @@ fn parse_summary(
     s: &str,
     allow_unknown: bool,
     allow_incomplete: bool,
+    preserve: bool,
 ) -> Result<Summary> {
+    let allow_unknown = allow_unknown || preserve;
     // For allow_unknown/allow_incomplete, we need to wrap the parsing
-    if allow_unknown || allow_incomplete {
-        parse_summary_lenient(s, allow_unknown, allow_incomplete)
+    let mut summary = if allow_unknown || allow_incomplete {
+        parse_summary_lenient(s, allow_unknown, allow_incomplete)?
     } else {
-        Summary::parse(s).map_err(SummaryError::from)
+        Summary::parse(s).map_err(SummaryError::from)?
+    };
+    if preserve {
+        summary.original =
+            Original(Some(s.split_inclusive('\n').map(String::from).collect()));
     }
+    Ok(summary)
 }
 
 fn parse_summary_lenient(
@@ -1987,6 +2115,7 @@ fn parse_summary_lenient(
         requires,
         size_pkg,
         supersedes,
+        original: Original::default(),
     })
 }
 
@@ -2008,6 +2137,7 @@ pub struct SummaryIter<R: BufRead> {
     entry_start: usize,
     allow_unknown: bool,
     allow_incomplete: bool,
+    preserve: bool,
 }
 
 impl<R: BufRead> Iterator for SummaryIter<R> {
@@ -2033,6 +2163,7 @@ impl<R: BufRead> Iterator for SummaryIter<R> {
                                 &self.buffer,
                                 self.allow_unknown,
                                 self.allow_incomplete,
+                                self.preserve,
                             )
                             .map_err(
                                 |e: SummaryError| {
@@ -2055,18 +2186,20 @@ impl<R: BufRead> Iterator for SummaryIter<R> {
                         if !self.buffer.is_empty() {
                             let entry = self.record_number;
                             let entry_start = self.entry_start;
-                            // Trim trailing newline for parsing (doesn't affect offsets
-                            // since FromStr handles any line ending style)
-                            let to_parse =
-                                self.buffer.trim_end_matches(['\r', '\n']);
-                            let entry_len = to_parse.len();
+                            // The trailing newline is excluded from the
+                            // entry span, but kept for preserve mode.
+                            let entry_len = self
+                                .buffer
+                                .trim_end_matches(['\r', '\n'])
+                                .len();
                             self.record_number += 1;
                             self.entry_start = self.byte_offset;
                             return Some(
                                 parse_summary(
-                                    to_parse,
+                                    &self.buffer,
                                     self.allow_unknown,
                                     self.allow_incomplete,
+                                    self.preserve,
                                 )
                                 .map_err(
                                     |e: SummaryError| {
@@ -2105,6 +2238,14 @@ impl<R: BufRead> SummaryIter<R> {
         self.allow_incomplete = yes;
         self
     }
+
+    /// Preserve the original input of each entry.  See
+    /// [`SummaryBuilder::preserve`].
+    #[must_use]
+    pub fn preserve(mut self, yes: bool) -> Self {
+        self.preserve = yes;
+        self
+    }
 }
 
 /**
@@ -2316,6 +2457,27 @@ impl SummaryError {
     }
 }
 
+/*
+ * Build a minimal valid entry for tests from the shared template in
+ * tests/data/summary/template.txt, with any extra lines appended.
+ */
+#[cfg(test)]
+pub(crate) fn test_entry(pkgname: &str, extra: &[&str]) -> Summary {
+    let mut input = format!(
+        "{}PKGNAME={pkgname}\nPKGPATH=devel/{}\n",
+        include_str!("../tests/data/summary/template.txt"),
+        PkgName::new(pkgname).pkgbase()
+    );
+    for line in extra {
+        input.push_str(line);
+        input.push('\n');
+    }
+    SummaryBuilder::new()
+        .vars(input.lines())
+        .build()
+        .unwrap_or_else(|e| panic!("invalid test entry {pkgname}: {e}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2411,6 +2573,147 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_preserve_roundtrip() -> Result<()> {
+        let input = indoc! {"
+            PKGNAME=testpkg-1.0
+            BUILD_HOST=builder.example.com
+            COMMENT=Test package
+            SIZE_PKG=1234
+            DESCRIPTION=First line
+            BUILD_DATE=2019-08-12 15:58:02 +0100
+            CATEGORIES=test
+            DESCRIPTION=Second line
+            MACHINE_ARCH=x86_64
+            OPSYS=NetBSD
+            OS_VERSION=9.0
+            PKGPATH=test/testpkg
+            PKGTOOLS_VERSION=20091115
+            PKG_SIGNER=signer@example.com
+
+            PKGNAME=other-2.0
+            COMMENT=Another package
+            SIZE_PKG=1
+            BUILD_DATE=2019-08-12 15:58:02 +0100
+            CATEGORIES=test
+            DESCRIPTION=
+            MACHINE_ARCH=x86_64
+            OPSYS=NetBSD
+            OS_VERSION=9.0
+            PKGPATH=test/other
+            PKGTOOLS_VERSION=20091115
+        "};
+
+        /* Unknown variables are still rejected without preserve. */
+        let mut iter = Summary::from_reader(input.as_bytes());
+        assert!(matches!(
+            iter.next(),
+            Some(Err(SummaryError::UnknownVariable { .. }))
+        ));
+
+        let pkgs = Summary::from_reader(input.as_bytes())
+            .preserve(true)
+            .collect::<Result<Vec<_>>>()?;
+        let output = pkgs
+            .iter()
+            .map(Summary::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(output, input);
+
+        assert_eq!(pkgs[0].description(), ["First line", "Second line"]);
+        assert_eq!(
+            pkgs[0].unknown_variables().collect::<Vec<_>>(),
+            [
+                ("BUILD_HOST", "builder.example.com"),
+                ("PKG_SIGNER", "signer@example.com"),
+            ]
+        );
+        assert_eq!(pkgs[1].unknown_variables().count(), 0);
+
+        /* Without preserve the canonical order is used. */
+        let pkg = SummaryBuilder::new()
+            .vars(input.lines().take(14))
+            .allow_unknown(true)
+            .build()?;
+        assert_ne!(pkg.to_string(), input.split("\n\n").next().unwrap_or(""));
+        assert_eq!(pkg.unknown_variables().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_preserve_crlf() -> Result<()> {
+        let input = "PKGNAME=testpkg-1.0\r\n\
+                     COMMENT=Test package\r\n\
+                     SIZE_PKG=1234\r\n\
+                     BUILD_DATE=2019-08-12 15:58:02 +0100\r\n\
+                     CATEGORIES=test\r\n\
+                     DESCRIPTION=Test\r\n\
+                     MACHINE_ARCH=x86_64\r\n\
+                     OPSYS=NetBSD\r\n\
+                     OS_VERSION=9.0\r\n\
+                     PKGPATH=test/testpkg\r\n\
+                     PKGTOOLS_VERSION=20091115\r\n\
+                     PKG_SIGNER=signer@example.com\r\n";
+        let pkgs =
+            Summary::from_reader(format!("{input}\r\n{input}").as_bytes())
+                .preserve(true)
+                .collect::<Result<Vec<_>>>()?;
+        assert_eq!(pkgs.len(), 2);
+        assert_eq!(pkgs[1].to_string(), input);
+        assert_eq!(pkgs[1].comment(), "Test package");
+        assert_eq!(
+            pkgs[1].unknown_variables().collect::<Vec<_>>(),
+            [("PKG_SIGNER", "signer@example.com")]
+        );
+
+        /* The builder also keeps blank lines. */
+        let lines = input
+            .split_inclusive('\n')
+            .map(|l| l.trim_end_matches('\n'));
+        let pkg = SummaryBuilder::new()
+            .vars(lines.clone().take(3))
+            .var("")
+            .vars(lines.skip(3))
+            .preserve(true)
+            .build()?;
+        let (head, tail) =
+            input.split_at(input.find("BUILD_DATE").unwrap_or(0));
+        assert_eq!(pkg.to_string(), format!("{head}\n{tail}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_preserve_eq_hash() -> Result<()> {
+        use std::collections::HashSet;
+
+        let input = indoc! {"
+            PKGNAME=testpkg-1.0
+            COMMENT=Test package
+            SIZE_PKG=1234
+            BUILD_DATE=2019-08-12 15:58:02 +0100
+            CATEGORIES=test
+            DESCRIPTION=Test
+            MACHINE_ARCH=x86_64
+            OPSYS=NetBSD
+            OS_VERSION=9.0
+            PKGPATH=test/testpkg
+            PKGTOOLS_VERSION=20091115
+        "};
+        let plain = SummaryBuilder::new().vars(input.lines()).build()?;
+        let preserved = SummaryBuilder::new()
+            .vars(input.lines())
+            .preserve(true)
+            .build()?;
+        assert_eq!(plain, preserved);
+        assert_eq!(HashSet::from([plain.clone(), preserved.clone()]).len(), 1);
+
+        /* Display emits the preserved text, not the canonical order. */
+        assert_eq!(preserved.to_string(), input);
+        assert_ne!(plain.to_string(), input);
+        Ok(())
+    }
+
     #[test]
     fn test_lenient_parse_mode() -> Result<()> {
         let input = indoc! {"
@@ -2434,7 +2737,7 @@ mod tests {
             matches!(err, SummaryError::UnknownVariable { variable, .. } if variable == "UNKNOWN_FIELD")
         );
 
-        let pkg = parse_summary(trimmed, true, false)?;
+        let pkg = parse_summary(trimmed, true, false, false)?;
         assert_eq!(pkg.pkgname().pkgname(), "testpkg-1.0");
 
         let pkg = SummaryBuilder::new()
@@ -2753,7 +3056,7 @@ mod tests {
             UNKNOWN_FIELD=ignored
         "};
 
-        let pkg = parse_summary(input.trim(), true, false)?;
+        let pkg = parse_summary(input.trim(), true, false, false)?;
         assert_eq!(pkg.pkgname().pkgname(), "lenient-1.0");
         assert_eq!(pkg.comment(), "Lenient test");
         assert_eq!(pkg.build_date(), "2025-01-01");
@@ -0,0 +1,743 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Semantic checks across a complete `pkg_summary` repository.
+ *
+ * [`SummaryBuilder::build`] only validates that each entry is well formed.
+ * A [`Linter`] looks at the repository as a whole and reports problems that
+ * only become apparent when entries are considered together, and is intended
+ * to be run before a repository is published.
+ *
+ * The following checks are performed, each reported as a [`LintIssue`]:
+ *
+ * * `DEPENDS` or `CONFLICTS` patterns that fail to parse.
+ * * `DEPENDS` patterns that do not match any package in the repository.
+ * * `REQUIRES` entries that are not in the `PROVIDES` of the package itself
+ *   or any package in its dependency closure.  Libraries under a configurable
+ *   set of system paths are ignored.
+ * * `CONFLICTS` patterns that match a package in the dependency closure of
+ *   the declaring package, or one that depends on it.
+ * * Duplicate `PKGNAME` and `FILE_NAME` values.
+ * * `OPSYS` and `MACHINE_ARCH` values that differ from the majority of the
+ *   repository.
+ *
+ * Dependencies are resolved in the same way as `pkg_add`, choosing the best
+ * match for each `DEPENDS` pattern.
+ *
+ * ## Example
+ *
+ * ```
+ * use pkgsrc::summary::Summary;
+ * use pkgsrc::summary::lint::{LintIssue, Linter};
+ *
+ * let input = "PKGNAME=foo-1.0\n\
+ *              DEPENDS=bar>=2.0\n\
+ *              \n\
+ *              PKGNAME=bar-1.0\n";
+ * let pkgs = Summary::from_reader(input.as_bytes())
+ *     .allow_incomplete(true)
+ *     .collect::<Result<Vec<_>, _>>()?;
+ *
+ * let issues = Linter::new().check(&pkgs);
+ * assert_eq!(issues.len(), 1);
+ * assert!(matches!(
+ *     &issues[0],
+ *     LintIssue::UnresolvedDepend { pattern, .. } if pattern == "bar>=2.0"
+ * ));
+ * # Ok::<(), pkgsrc::summary::SummaryError>(())
+ * ```
+ *
+ * [`SummaryBuilder::build`]: super::SummaryBuilder::build
+ */
+
+use crate::summary::Summary;
+use crate::{Pattern, PatternError, PkgName};
+use hashbrown::{HashMap, HashSet};
+use std::fmt;
+
+/**
+ * Default path prefixes for `REQUIRES` entries that are provided by the
+ * operating system rather than by packages.
+ */
+const SYSTEM_PATHS: &[&str] = &[
+    "/lib/",
+    "/lib32/",
+    "/lib64/",
+    "/usr/lib/",
+    "/usr/lib32/",
+    "/usr/lib64/",
+    "/usr/X11R7/lib/",
+    "/System/",
+];
+
+/**
+ * A problem found by [`Linter::check`].
+ *
+ * Every variant carries the `PKGNAME` of the entry the issue was found in,
+ * available via [`LintIssue::pkgname`].
+ */
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LintIssue {
+    /// A `DEPENDS` or `CONFLICTS` pattern could not be parsed.
+    InvalidPattern {
+        /// Package containing the pattern.
+        pkgname: PkgName,
+        /// Variable the pattern was found in.
+        variable: &'static str,
+        /// The pattern as written.
+        pattern: String,
+        /// The underlying parse error.
+        error: PatternError,
+    },
+    /// A `DEPENDS` pattern does not match any package in the repository.
+    UnresolvedDepend {
+        /// Package containing the dependency.
+        pkgname: PkgName,
+        /// The unmatched pattern.
+        pattern: String,
+    },
+    /// A `REQUIRES` entry is not provided by the package or any package in
+    /// its dependency closure.
+    UnresolvedRequire {
+        /// Package containing the requirement.
+        pkgname: PkgName,
+        /// The required library path.
+        library: String,
+    },
+    /// A `CONFLICTS` pattern matches a package that either depends on, or
+    /// is a dependency of, the declaring package.
+    DependConflict {
+        /// Package declaring the conflict.
+        pkgname: PkgName,
+        /// The matching `CONFLICTS` pattern.
+        pattern: String,
+        /// The conflicting package.
+        conflict: PkgName,
+    },
+    /// The same `PKGNAME` appears in more than one entry.
+    DuplicatePkgname {
+        /// The duplicated package name.
+        pkgname: PkgName,
+        /// Number of entries sharing this name.
+        count: usize,
+    },
+    /// The same `FILE_NAME` is used by more than one entry.  Reported once
+    /// for every entry after the first.
+    DuplicateFileName {
+        /// Package with the duplicate file name.
+        pkgname: PkgName,
+        /// The duplicated file name.
+        file_name: String,
+        /// The first package that used this file name.
+        first: PkgName,
+    },
+    /// The `OPSYS` of an entry differs from the majority of the repository.
+    InconsistentOpsys {
+        /// Package with the differing value.
+        pkgname: PkgName,
+        /// The value in this entry.
+        found: String,
+        /// The most common value in the repository.
+        expected: String,
+    },
+    /// The `MACHINE_ARCH` of an entry differs from the majority of the
+    /// repository.
+    InconsistentMachineArch {
+        /// Package with the differing value.
+        pkgname: PkgName,
+        /// The value in this entry.
+        found: String,
+        /// The most common value in the repository.
+        expected: String,
+    },
+}
+
+impl LintIssue {
+    /**
+     * Return the package this issue was found in.
+     */
+    #[must_use]
+    pub fn pkgname(&self) -> &PkgName {
+        match self {
+            Self::InvalidPattern { pkgname, .. }
+            | Self::UnresolvedDepend { pkgname, .. }
+            | Self::UnresolvedRequire { pkgname, .. }
+            | Self::DependConflict { pkgname, .. }
+            | Self::DuplicatePkgname { pkgname, .. }
+            | Self::DuplicateFileName { pkgname, .. }
+            | Self::InconsistentOpsys { pkgname, .. }
+            | Self::InconsistentMachineArch { pkgname, .. } => pkgname,
+        }
+    }
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPattern {
+                pkgname,
+                variable,
+                pattern,
+                error,
+            } => write!(
+                f,
+                "{pkgname}: invalid {variable} pattern \"{pattern}\": {error}"
+            ),
+            Self::UnresolvedDepend { pkgname, pattern } => {
+                write!(f, "{pkgname}: no package matches \"{pattern}\"")
+            }
+            Self::UnresolvedRequire { pkgname, library } => {
+                write!(
+                    f,
+                    "{pkgname}: {library} is not provided by any dependency"
+                )
+            }
+            Self::DependConflict {
+                pkgname,
+                pattern,
+                conflict,
+            } => write!(
+                f,
+                "{pkgname}: conflict \"{pattern}\" matches dependency-related \
+                 package {conflict}"
+            ),
+            Self::DuplicatePkgname { pkgname, count } => {
+                write!(f, "{pkgname}: appears {count} times")
+            }
+            Self::DuplicateFileName {
+                pkgname,
+                file_name,
+                first,
+            } => write!(
+                f,
+                "{pkgname}: FILE_NAME {file_name} already used by {first}"
+            ),
+            Self::InconsistentOpsys {
+                pkgname,
+                found,
+                expected,
+            } => write!(
+                f,
+                "{pkgname}: OPSYS {found} differs from repository ({expected})"
+            ),
+            Self::InconsistentMachineArch {
+                pkgname,
+                found,
+                expected,
+            } => write!(
+                f,
+                "{pkgname}: MACHINE_ARCH {found} differs from repository \
+                 ({expected})"
+            ),
+        }
+    }
+}
+
+/**
+ * Repository-wide checker for a set of [`Summary`] entries.
+ *
+ * See the [module documentation](self) for the list of checks performed.
+ */
+#[derive(Clone, Debug)]
+pub struct Linter {
+    system_paths: Vec<String>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /**
+     * Create a new [`Linter`] with the default set of system library paths.
+     */
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            system_paths: SYSTEM_PATHS
+                .iter()
+                .map(|s| (*s).to_string())
+                .collect(),
+        }
+    }
+
+    /**
+     * Add a path prefix under which `REQUIRES` entries are assumed to be
+     * provided by the operating system and are not checked.
+     */
+    #[must_use]
+    pub fn system_path(mut self, prefix: impl Into<String>) -> Self {
+        self.system_paths.push(prefix.into());
+        self
+    }
+
+    /**
+     * Remove all system path prefixes, including the defaults, so that every
+     * `REQUIRES` entry must be provided by a package.
+     */
+    #[must_use]
+    pub fn clear_system_paths(mut self) -> Self {
+        self.system_paths.clear();
+        self
+    }
+
+    /**
+     * Check a repository, returning all issues found.  Issues are returned
+     * in the order of the entries they relate to.
+     */
+    #[must_use]
+    pub fn check(&self, pkgs: &[Summary]) -> Vec<LintIssue> {
+        let mut issues: Vec<(usize, LintIssue)> = Vec::new();
+
+        let mut bybase: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, pkg) in pkgs.iter().enumerate() {
+            bybase.entry(pkg.pkgname().pkgbase()).or_default().push(i);
+        }
+
+        /*
+         * Compile CONFLICTS patterns and resolve DEPENDS to the best match,
+         * recording forward edges for the closure calculations below.
+         */
+        let mut deps: Vec<Vec<usize>> = vec![Vec::new(); pkgs.len()];
+        let mut conflicts: Vec<Vec<Pattern>> = vec![Vec::new(); pkgs.len()];
+        for (i, pkg) in pkgs.iter().enumerate() {
+            for pattern in pkg.depends().unwrap_or_default() {
+                let compiled = match Pattern::new(pattern) {
+                    Ok(p) => p,
+                    Err(error) => {
+                        issues.push((
+                            i,
+                            LintIssue::InvalidPattern {
+                                pkgname: pkg.pkgname().clone(),
+                                variable: "DEPENDS",
+                                pattern: pattern.clone(),
+                                error,
+                            },
+                        ));
+                        continue;
+                    }
+                };
+                match best_match(&compiled, pkgs, &bybase) {
+                    Some(dep) => deps[i].push(dep),
+                    None => issues.push((
+                        i,
+                        LintIssue::UnresolvedDepend {
+                            pkgname: pkg.pkgname().clone(),
+                            pattern: pattern.clone(),
+                        },
+                    )),
+                }
+            }
+            for pattern in pkg.conflicts().unwrap_or_default() {
+                match Pattern::new(pattern) {
+                    Ok(p) => conflicts[i].push(p),
+                    Err(error) => issues.push((
+                        i,
+                        LintIssue::InvalidPattern {
+                            pkgname: pkg.pkgname().clone(),
+                            variable: "CONFLICTS",
+                            pattern: pattern.clone(),
+                            error,
+                        },
+                    )),
+                }
+            }
+        }
+
+        for (i, pkg) in pkgs.iter().enumerate() {
+            let requires = pkg.requires().unwrap_or_default();
+            let closure = closure(i, &deps);
+
+            if !requires.is_empty() {
+                let provided: HashSet<&str> = std::iter::once(i)
+                    .chain(closure.iter().copied())
+                    .flat_map(|p| pkgs[p].provides().unwrap_or_default())
+                    .map(String::as_str)
+                    .collect();
+                for library in requires {
+                    if provided.contains(library.as_str())
+                        || self
+                            .system_paths
+                            .iter()
+                            .any(|p| library.starts_with(p.as_str()))
+                    {
+                        continue;
+                    }
+                    issues.push((
+                        i,
+                        LintIssue::UnresolvedRequire {
+                            pkgname: pkg.pkgname().clone(),
+                            library: library.clone(),
+                        },
+                    ));
+                }
+            }
+
+            /*
+             * Check both directions so that a conflict is reported against
+             * whichever package declares it.
+             */
+            for &dep in &closure {
+                let dep_pkg = &pkgs[dep];
+                for pattern in &conflicts[i] {
+                    if pattern.matches(dep_pkg.pkgname().pkgname()) {
+                        issues.push((
+                            i,
+                            LintIssue::DependConflict {
+                                pkgname: pkg.pkgname().clone(),
+                                pattern: pattern.pattern().to_string(),
+                                conflict: dep_pkg.pkgname().clone(),
+                            },
+                        ));
+                    }
+                }
+                for pattern in &conflicts[dep] {
+                    if pattern.matches(pkg.pkgname().pkgname()) {
+                        issues.push((
+                            dep,
+                            LintIssue::DependConflict {
+                                pkgname: dep_pkg.pkgname().clone(),
+                                pattern: pattern.pattern().to_string(),
+                                conflict: pkg.pkgname().clone(),
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut pkgnames: HashMap<&str, (usize, usize)> = HashMap::new();
+        let mut file_names: HashMap<&str, usize> = HashMap::new();
+        for (i, pkg) in pkgs.iter().enumerate() {
+            pkgnames
+                .entry(pkg.pkgname().pkgname())
+                .and_modify(|(_, count)| *count += 1)
+                .or_insert((i, 1));
+            if let Some(file_name) = pkg.file_name() {
+                match file_names.get(file_name) {
+                    Some(&first) => issues.push((
+                        i,
+                        LintIssue::DuplicateFileName {
+                            pkgname: pkg.pkgname().clone(),
+                            file_name: file_name.to_string(),
+                            first: pkgs[first].pkgname().clone(),
+                        },
+                    )),
+                    None => {
+                        file_names.insert(file_name, i);
+                    }
+                }
+            }
+        }
+        for (first, count) in pkgnames.into_values() {
+            if count > 1 {
+                issues.push((
+                    first,
+                    LintIssue::DuplicatePkgname {
+                        pkgname: pkgs[first].pkgname().clone(),
+                        count,
+                    },
+                ));
+            }
+        }
+
+        if let Some(expected) = majority(pkgs.iter().map(Summary::opsys)) {
+            for (i, pkg) in pkgs.iter().enumerate() {
+                if pkg.opsys() != expected {
+                    issues.push((
+                        i,
+                        LintIssue::InconsistentOpsys {
+                            pkgname: pkg.pkgname().clone(),
+                            found: pkg.opsys().to_string(),
+                            expected: expected.to_string(),
+                        },
+                    ));
+                }
+            }
+        }
+        if let Some(expected) = majority(pkgs.iter().map(Summary::machine_arch))
+        {
+            for (i, pkg) in pkgs.iter().enumerate() {
+                if pkg.machine_arch() != expected {
+                    issues.push((
+                        i,
+                        LintIssue::InconsistentMachineArch {
+                            pkgname: pkg.pkgname().clone(),
+                            found: pkg.machine_arch().to_string(),
+                            expected: expected.to_string(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        /*
+         * Stable sort, so issues for the same entry retain the order in which
+         * the checks were performed.
+         */
+        issues.sort_by_key(|(i, _)| *i);
+        issues.into_iter().map(|(_, issue)| issue).collect()
+    }
+}
+
+/*
+ * Find the best matching package for a pattern, narrowing the candidates by
+ * PKGBASE where the pattern allows it.
+ */
+fn best_match(
+    pattern: &Pattern,
+    pkgs: &[Summary],
+    bybase: &HashMap<&str, Vec<usize>>,
+) -> Option<usize> {
+    let candidates: Vec<usize> = match pattern.pkgbases() {
+        Some(bases) => bases
+            .iter()
+            .filter_map(|b| bybase.get(b))
+            .flatten()
+            .copied()
+            .collect(),
+        None => (0..pkgs.len()).collect(),
+    };
+    let mut best = pattern.best_matcher();
+    let mut found = None;
+    for i in candidates {
+        /*
+         * A version that cannot be compared is still a match, it just can't
+         * win against another candidate.
+         */
+        match best.consider(pkgs[i].pkgname().pkgname()) {
+            Ok(true) => found = Some(i),
+            Ok(false) => {}
+            Err(_) => {
+                found = found.or(Some(i));
+            }
+        }
+    }
+    found
+}
+
+/*
+ * Return every package reachable from `start`, excluding `start` itself.
+ */
+fn closure(start: usize, deps: &[Vec<usize>]) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    seen.insert(start);
+    let mut stack = deps[start].clone();
+    let mut out = Vec::new();
+    while let Some(i) = stack.pop() {
+        if seen.insert(i) {
+            out.push(i);
+            stack.extend(&deps[i]);
+        }
+    }
+    out
+}
+
+/*
+ * Return the most common value, preferring the first seen on a tie.
+ */
+fn majority<'a>(values: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    let mut best: Option<(&str, usize)> = None;
+    for (value, n) in counts {
+        if best.is_none_or(|(_, b)| n > b) {
+            best = Some((value, n));
+        }
+    }
+    best.map(|(v, _)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::{SummaryBuilder, test_entry};
+
+    #[test]
+    fn clean_repository() {
+        let pkgs = vec![
+            test_entry(
+                "foo-1.0",
+                &["DEPENDS=bar>=1.0", "REQUIRES=/usr/pkg/lib/libbar.so.1"],
+            ),
+            test_entry(
+                "bar-1.2",
+                &[
+                    "PROVIDES=/usr/pkg/lib/libbar.so.1",
+                    "REQUIRES=/usr/lib/libc.so.12",
+                ],
+            ),
+        ];
+        let issues = Linter::new().check(&pkgs);
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn depends() {
+        let pkgs = vec![
+            test_entry("foo-1.0", &["DEPENDS=bar>=2.0", "DEPENDS=baz-[0-9"]),
+            test_entry("bar-1.0", &[]),
+        ];
+        let issues = Linter::new().check(&pkgs);
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(
+            &issues[0],
+            LintIssue::UnresolvedDepend { pattern, .. } if pattern == "bar>=2.0"
+        ));
+        assert!(matches!(
+            &issues[1],
+            LintIssue::InvalidPattern {
+                variable: "DEPENDS",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn requires_transitive() {
+        let pkgs = vec![
+            test_entry(
+                "foo-1.0",
+                &[
+                    "DEPENDS=bar-[0-9]*",
+                    "REQUIRES=/usr/pkg/lib/libbaz.so.1",
+                    "REQUIRES=/usr/pkg/lib/libmissing.so.1",
+                    "REQUIRES=/usr/lib/libc.so.12",
+                ],
+            ),
+            test_entry("bar-1.0", &["DEPENDS=baz-[0-9]*"]),
+            test_entry("baz-1.0", &["PROVIDES=/usr/pkg/lib/libbaz.so.1"]),
+        ];
+        let issues = Linter::new().check(&pkgs);
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            &issues[0],
+            LintIssue::UnresolvedRequire { library, .. }
+                if library == "/usr/pkg/lib/libmissing.so.1"
+        ));
+
+        let issues = Linter::new().clear_system_paths().check(&pkgs);
+        assert_eq!(issues.len(), 2);
+
+        let issues = Linter::new().system_path("/usr/pkg/lib/").check(&pkgs);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn requires_best_match() {
+        /*
+         * Only the best match is installed, so libraries provided by an
+         * older version do not count.
+         */
+        let pkgs = vec![
+            test_entry(
+                "foo-1.0",
+                &["DEPENDS=bar-[0-9]*", "REQUIRES=/usr/pkg/lib/libbar.so.1"],
+            ),
+            test_entry("bar-1.0", &["PROVIDES=/usr/pkg/lib/libbar.so.1"]),
+            test_entry("bar-2.0", &["PROVIDES=/usr/pkg/lib/libbar.so.2"]),
+        ];
+        let issues = Linter::new().check(&pkgs);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].pkgname(), "foo-1.0");
+    }
+
+    #[test]
+    fn conflicts() {
+        let pkgs = vec![
+            test_entry("foo-1.0", &["DEPENDS=bar-[0-9]*"]),
+            test_entry(
+                "bar-1.0",
+                &["DEPENDS=baz-[0-9]*", "CONFLICTS=foo-[0-9]*"],
+            ),
+            test_entry("baz-1.0", &[]),
+            test_entry("qux-1.0", &["CONFLICTS=baz-[0-9]*"]),
+            test_entry("quux-1.0", &["DEPENDS=baz-[0-9]*", "CONFLICTS=baz<1"]),
+        ];
+        let issues = Linter::new().check(&pkgs);
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            &issues[0],
+            LintIssue::DependConflict { pkgname, pattern, conflict }
+                if pkgname == "bar-1.0"
+                    && pattern == "foo-[0-9]*"
+                    && conflict == "foo-1.0"
+        ));
+        assert_eq!(
+            issues[0].to_string(),
+            "bar-1.0: conflict \"foo-[0-9]*\" matches dependency-related \
+             package foo-1.0"
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let pkgs = vec![
+            test_entry("foo-1.0", &["FILE_NAME=foo-1.0.tgz"]),
+            test_entry("foo-1.0", &["FILE_NAME=foo-1.0a.tgz"]),
+            test_entry("bar-1.0", &["FILE_NAME=foo-1.0.tgz"]),
+        ];
+        let issues = Linter::new().check(&pkgs);
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(
+            &issues[0],
+            LintIssue::DuplicatePkgname { count: 2, .. }
+        ));
+        assert!(matches!(
+            &issues[1],
+            LintIssue::DuplicateFileName { pkgname, first, .. }
+                if pkgname == "bar-1.0" && first == "foo-1.0"
+        ));
+    }
+
+    #[test]
+    fn inconsistent_platform() {
+        let mut pkgs =
+            vec![test_entry("foo-1.0", &[]), test_entry("bar-1.0", &[])];
+        let input = pkgs[1]
+            .to_string()
+            .replace("PKGNAME=bar-1.0", "PKGNAME=baz-1.0")
+            .replace("OPSYS=NetBSD", "OPSYS=Linux")
+            .replace("MACHINE_ARCH=x86_64", "MACHINE_ARCH=aarch64");
+        pkgs.push(
+            SummaryBuilder::new()
+                .vars(input.lines())
+                .build()
+                .expect("invalid entry"),
+        );
+        let issues = Linter::new().check(&pkgs);
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(
+            &issues[0],
+            LintIssue::InconsistentOpsys { found, expected, .. }
+                if found == "Linux" && expected == "NetBSD"
+        ));
+        assert!(matches!(
+            &issues[1],
+            LintIssue::InconsistentMachineArch { found, .. }
+                if found == "aarch64"
+        ));
+    }
+}
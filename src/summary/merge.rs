@@ -0,0 +1,500 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * A merged view over several `pkg_summary` repositories.
+ *
+ * Binary package setups commonly use more than one repository, for example
+ * a base repository and an overlay containing site-local packages.  Each
+ * [`Repository`] is given a priority, and entries in a higher priority
+ * repository shadow entries in lower priority ones according to its
+ * [`Shadow`] mode:
+ *
+ * * [`Shadow::Pkgbase`] hides every lower priority entry with the same
+ *   `PKGBASE`, regardless of version.
+ * * [`Shadow::Version`] hides only lower priority entries with an identical
+ *   `PKGNAME`.
+ *
+ * Repositories with equal priority never shadow each other.
+ *
+ * Every entry in a [`MergedView`] retains its provenance, and
+ * [`MergedView::best_match`] resolves a [`Pattern`] across the visible
+ * entries, preferring the highest version, then the highest priority
+ * repository, then the lexicographically smallest `PKGNAME` as
+ * `pkg_install` does.
+ *
+ * ## Example
+ *
+ * ```
+ * use pkgsrc::Pattern;
+ * use pkgsrc::summary::Summary;
+ * use pkgsrc::summary::merge::{MergedView, Repository, Shadow};
+ *
+ * let read = |input: &str| {
+ *     Summary::from_reader(input.as_bytes())
+ *         .allow_incomplete(true)
+ *         .collect::<Result<Vec<_>, _>>()
+ * };
+ * let base = read("PKGNAME=foo-2.0\n\nPKGNAME=bar-1.0\n")?;
+ * let local = read("PKGNAME=foo-1.0nb1\n")?;
+ *
+ * let base = Repository::new("base", base);
+ * let local = Repository::new("local", local)
+ *     .with_priority(10)
+ *     .with_shadow(Shadow::Pkgbase);
+ * let merged = MergedView::new([base, local]);
+ *
+ * let best = merged
+ *     .best_match(&Pattern::new("foo-[0-9]*")?)?
+ *     .expect("no match");
+ * assert_eq!(best.summary().pkgname(), "foo-1.0nb1");
+ * assert_eq!(best.repository().name(), "local");
+ * assert_eq!(merged.iter().count(), 2);
+ * # Ok::<(), Box<dyn std::error::Error>>(())
+ * ```
+ */
+
+use crate::dewey::{DeweyOp, DeweyVersion, dewey_cmp};
+use crate::summary::Summary;
+use crate::{Pattern, PatternError};
+use hashbrown::HashMap;
+use std::cmp::Ordering;
+
+/**
+ * How entries in a [`Repository`] shadow entries in lower priority
+ * repositories.
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shadow {
+    /// Hide all lower priority entries with the same `PKGBASE`.
+    Pkgbase,
+    /// Hide only lower priority entries with the same `PKGNAME`.  This is
+    /// the default.
+    #[default]
+    Version,
+}
+
+/**
+ * A named set of [`Summary`] entries with a priority and [`Shadow`] mode.
+ */
+#[derive(Clone, Debug)]
+pub struct Repository {
+    name: String,
+    priority: i32,
+    shadow: Shadow,
+    entries: Vec<Summary>,
+}
+
+impl Repository {
+    /**
+     * Create a new [`Repository`] with priority 0 and [`Shadow::Version`].
+     */
+    #[must_use]
+    pub fn new(
+        name: impl Into<String>,
+        entries: impl IntoIterator<Item = Summary>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            priority: 0,
+            shadow: Shadow::default(),
+            entries: entries.into_iter().collect(),
+        }
+    }
+
+    /**
+     * Set the priority.  Higher values take precedence.
+     */
+    #[must_use]
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /**
+     * Set how entries in this repository shadow lower priority ones.
+     */
+    #[must_use]
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    /**
+     * Return the repository name.
+     */
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Return the repository priority.
+     */
+    #[must_use]
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /**
+     * Return the repository [`Shadow`] mode.
+     */
+    #[must_use]
+    pub fn shadow(&self) -> Shadow {
+        self.shadow
+    }
+
+    /**
+     * Return all entries in this repository, including any that are
+     * shadowed in a [`MergedView`].
+     */
+    #[must_use]
+    pub fn entries(&self) -> &[Summary] {
+        &self.entries
+    }
+}
+
+/**
+ * A [`Summary`] entry together with the [`Repository`] it came from.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct MergedEntry<'a> {
+    repository: &'a Repository,
+    summary: &'a Summary,
+}
+
+impl<'a> MergedEntry<'a> {
+    /**
+     * Return the repository this entry was read from.
+     */
+    #[must_use]
+    pub fn repository(&self) -> &'a Repository {
+        self.repository
+    }
+
+    /**
+     * Return the entry itself.
+     */
+    #[must_use]
+    pub fn summary(&self) -> &'a Summary {
+        self.summary
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Slot {
+    repo: usize,
+    entry: usize,
+}
+
+/**
+ * A merged view over several [`Repository`] sources.
+ *
+ * See the [module documentation](self) for the shadowing rules.
+ */
+#[derive(Clone, Debug)]
+pub struct MergedView {
+    repos: Vec<Repository>,
+    visible: Vec<Slot>,
+    shadowed: Vec<(Slot, usize)>,
+    bybase: HashMap<String, Vec<usize>>,
+}
+
+impl MergedView {
+    /**
+     * Merge a set of repositories.  Repositories with equal priority retain
+     * the order they were supplied in.
+     */
+    #[must_use]
+    pub fn new(repos: impl IntoIterator<Item = Repository>) -> Self {
+        let mut repos: Vec<Repository> = repos.into_iter().collect();
+        repos.sort_by_key(|r| std::cmp::Reverse(r.priority));
+
+        let mut visible = Vec::new();
+        let mut shadowed = Vec::new();
+
+        /*
+         * Claims are only recorded once a priority level has been fully
+         * processed, so that repositories of equal priority cannot shadow
+         * each other and any existing claim is always from a strictly
+         * higher priority repository.
+         */
+        let mut base_claims: HashMap<String, usize> = HashMap::new();
+        let mut name_claims: HashMap<String, usize> = HashMap::new();
+        let mut start = 0;
+        while start < repos.len() {
+            let priority = repos[start].priority;
+            let end = repos[start..]
+                .iter()
+                .position(|r| r.priority != priority)
+                .map_or(repos.len(), |n| start + n);
+            for (r, repo) in repos.iter().enumerate().take(end).skip(start) {
+                for (e, pkg) in repo.entries.iter().enumerate() {
+                    let slot = Slot { repo: r, entry: e };
+                    let pkgname = pkg.pkgname();
+                    let by = base_claims
+                        .get(pkgname.pkgbase())
+                        .or_else(|| name_claims.get(pkgname.pkgname()));
+                    match by {
+                        Some(&by) => shadowed.push((slot, by)),
+                        None => visible.push(slot),
+                    }
+                }
+            }
+            for (r, repo) in repos.iter().enumerate().take(end).skip(start) {
+                for pkg in &repo.entries {
+                    let pkgname = pkg.pkgname();
+                    match repo.shadow {
+                        Shadow::Pkgbase => base_claims
+                            .entry(pkgname.pkgbase().to_string())
+                            .or_insert(r),
+                        Shadow::Version => name_claims
+                            .entry(pkgname.pkgname().to_string())
+                            .or_insert(r),
+                    };
+                }
+            }
+            start = end;
+        }
+
+        let mut bybase: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, slot) in visible.iter().enumerate() {
+            let pkg = &repos[slot.repo].entries[slot.entry];
+            bybase
+                .entry(pkg.pkgname().pkgbase().to_string())
+                .or_default()
+                .push(i);
+        }
+
+        Self {
+            repos,
+            visible,
+            shadowed,
+            bybase,
+        }
+    }
+
+    fn entry(&self, slot: Slot) -> MergedEntry<'_> {
+        let repository = &self.repos[slot.repo];
+        MergedEntry {
+            repository,
+            summary: &repository.entries[slot.entry],
+        }
+    }
+
+    /**
+     * Return the merged repositories, ordered from highest to lowest
+     * priority.
+     */
+    #[must_use]
+    pub fn repositories(&self) -> &[Repository] {
+        &self.repos
+    }
+
+    /**
+     * Iterate over all visible entries, from highest to lowest priority
+     * repository.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = MergedEntry<'_>> {
+        self.visible.iter().map(|&slot| self.entry(slot))
+    }
+
+    /**
+     * Iterate over all shadowed entries, each paired with the repository
+     * that shadowed it.
+     */
+    pub fn shadowed(
+        &self,
+    ) -> impl Iterator<Item = (MergedEntry<'_>, &Repository)> {
+        self.shadowed
+            .iter()
+            .map(|&(slot, by)| (self.entry(slot), &self.repos[by]))
+    }
+
+    /**
+     * Return all visible entries with the given `PKGNAME`.  More than one
+     * entry is only possible when the same package is available from
+     * repositories of equal priority.
+     */
+    pub fn get<'a>(
+        &'a self,
+        pkgname: &'a str,
+    ) -> impl Iterator<Item = MergedEntry<'a>> {
+        self.bybase
+            .get(crate::pkgname::pkgbase(pkgname))
+            .into_iter()
+            .flatten()
+            .map(|&i| self.entry(self.visible[i]))
+            .filter(move |e| e.summary.pkgname() == pkgname)
+    }
+
+    /**
+     * Find the best visible entry matching a pattern.
+     *
+     * The highest version wins.  Equal versions are resolved in favour of
+     * the higher priority repository, and then the lexicographically
+     * smaller `PKGNAME` to match `pkg_install`'s `pkg_order()`.
+     *
+     * # Errors
+     *
+     * Returns [`PatternError::Dewey`] if a matching package version cannot
+     * be parsed.
+     */
+    pub fn best_match(
+        &self,
+        pattern: &Pattern,
+    ) -> Result<Option<MergedEntry<'_>>, PatternError> {
+        let candidates: Vec<usize> = match pattern.pkgbases() {
+            Some(bases) => {
+                let mut v: Vec<usize> = bases
+                    .iter()
+                    .filter_map(|b| self.bybase.get(*b))
+                    .flatten()
+                    .copied()
+                    .collect();
+                v.sort_unstable();
+                v
+            }
+            None => (0..self.visible.len()).collect(),
+        };
+
+        let mut best: Option<(Slot, DeweyVersion)> = None;
+        for i in candidates {
+            let slot = self.visible[i];
+            let pkgname = self.repos[slot.repo].entries[slot.entry].pkgname();
+            if !pattern.matches(pkgname.pkgname()) {
+                continue;
+            }
+            let version = DeweyVersion::new(pkgname.pkgversion())?;
+            let won = match &best {
+                None => true,
+                Some((cur, curver)) => {
+                    if dewey_cmp(&version, &DeweyOp::GT, curver) {
+                        true
+                    } else if dewey_cmp(&version, &DeweyOp::LT, curver) {
+                        false
+                    } else {
+                        let curprio = self.repos[cur.repo].priority;
+                        let prio = self.repos[slot.repo].priority;
+                        match prio.cmp(&curprio) {
+                            Ordering::Greater => true,
+                            Ordering::Less => false,
+                            Ordering::Equal => {
+                                let curname = self.repos[cur.repo].entries
+                                    [cur.entry]
+                                    .pkgname();
+                                pkgname.pkgname() < curname.pkgname()
+                            }
+                        }
+                    }
+                }
+            };
+            if won {
+                best = Some((slot, version));
+            }
+        }
+        Ok(best.map(|(slot, _)| self.entry(slot)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::test_entry;
+
+    fn names(view: &MergedView) -> Vec<(&str, &str)> {
+        view.iter()
+            .map(|e| (e.repository().name(), e.summary().pkgname().pkgname()))
+            .collect()
+    }
+
+    #[test]
+    fn shadow_pkgbase() {
+        let base = Repository::new(
+            "base",
+            [
+                test_entry("foo-1.0", &[]),
+                test_entry("foo-2.0", &[]),
+                test_entry("bar-1.0", &[]),
+            ],
+        );
+        let local = Repository::new("local", [test_entry("foo-1.5", &[])])
+            .with_priority(1)
+            .with_shadow(Shadow::Pkgbase);
+        let view = MergedView::new([base, local]);
+        assert_eq!(names(&view), [("local", "foo-1.5"), ("base", "bar-1.0")]);
+        let shadowed: Vec<_> = view
+            .shadowed()
+            .map(|(e, by)| (e.summary().pkgname().pkgname(), by.name()))
+            .collect();
+        assert_eq!(shadowed, [("foo-1.0", "local"), ("foo-2.0", "local")]);
+    }
+
+    #[test]
+    fn shadow_version() -> Result<(), PatternError> {
+        let base = Repository::new(
+            "base",
+            [test_entry("foo-1.0", &[]), test_entry("foo-2.0", &[])],
+        );
+        let local = Repository::new("local", [test_entry("foo-1.0", &[])])
+            .with_priority(1);
+        let view = MergedView::new([base, local]);
+        assert_eq!(names(&view), [("local", "foo-1.0"), ("base", "foo-2.0")]);
+
+        let best = view.best_match(&Pattern::new("foo<2")?);
+        assert_eq!(best?.map(|e| e.repository().name()), Some("local"));
+        let best = view.best_match(&Pattern::new("foo-[0-9]*")?);
+        assert_eq!(best?.map(|e| e.repository().name()), Some("base"));
+        Ok(())
+    }
+
+    #[test]
+    fn equal_priority() -> Result<(), PatternError> {
+        let a = Repository::new("a", [test_entry("foo-1.0", &[])])
+            .with_shadow(Shadow::Pkgbase);
+        let b = Repository::new(
+            "b",
+            [test_entry("foo-1.0", &[]), test_entry("foo-1.0.0", &[])],
+        );
+        let view = MergedView::new([a, b]);
+        assert_eq!(view.iter().count(), 3);
+        assert_eq!(view.get("foo-1.0").count(), 2);
+
+        /*
+         * All three compare equal, so the lexicographically smallest wins,
+         * and the first repository supplied wins over the second.
+         */
+        let best = view
+            .best_match(&Pattern::new("foo>=1")?)?
+            .expect("foo>=1 should match");
+        assert_eq!(best.summary().pkgname(), "foo-1.0");
+        assert_eq!(best.repository().name(), "a");
+        Ok(())
+    }
+
+    #[test]
+    fn priority_tiebreak() -> Result<(), PatternError> {
+        let low = Repository::new("low", [test_entry("foo-1.0", &[])]);
+        let high = Repository::new("high", [test_entry("foo-1.0.0", &[])])
+            .with_priority(5);
+        let view = MergedView::new([low, high]);
+        assert_eq!(view.repositories()[0].name(), "high");
+        let best = view.best_match(&Pattern::new("foo-[0-9]*")?)?;
+        assert_eq!(best.map(|e| e.repository().name()), Some("high"));
+        Ok(())
+    }
+}
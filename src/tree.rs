@@ -0,0 +1,445 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Enumerate the packages in a pkgsrc tree.
+ *
+ * The packages in a pkgsrc checkout are listed with `SUBDIR+=` lines, in
+ * the top-level `Makefile` for categories and in each category `Makefile`
+ * for packages.  Entries may be commented out, usually with a reason, or
+ * only enabled under a `.if` condition:
+ *
+ * ```text
+ * SUBDIR+=        bar
+ * #SUBDIR+=       broken          # does not build
+ * .if ${OPSYS} == "NetBSD"
+ * SUBDIR+=        netbsd-only
+ * .endif
+ * ```
+ *
+ * [`parse_subdirs`] parses a single `Makefile`, and a [`Tree`] reads a
+ * whole checkout, returning validated [`PkgPath`]s and any [`TreeIssue`]s
+ * where the listings and the directories on disk disagree.  Conditions are
+ * recorded but not evaluated.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::tree::{Tree, TreeIssue};
+ *
+ * let root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/pkgsrc");
+ * let tree = Tree::open(root)?;
+ * let pkgpaths = tree.pkgpaths();
+ * let pkgpaths: Vec<_> = pkgpaths.iter().map(|p| p.as_str()).collect();
+ * assert_eq!(
+ *     pkgpaths,
+ *     ["devel/bar", "devel/libfoo", "devel/netbsd-only", "lang/baz"]
+ * );
+ * assert!(tree.issues().contains(&TreeIssue::Missing("lang/gone".into())));
+ * # Ok::<(), std::io::Error>(())
+ * ```
+ */
+
+use crate::PkgPath;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/**
+ * A single `SUBDIR+=` entry.
+ */
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Subdir {
+    name: String,
+    line: usize,
+    commented: bool,
+    conditions: Vec<String>,
+}
+
+impl Subdir {
+    /**
+     * Return the directory name.
+     */
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /**
+     * Return the line number the entry appears on, starting at 1.
+     */
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /**
+     * Return true if the entry is commented out.
+     */
+    #[must_use]
+    pub fn is_commented(&self) -> bool {
+        self.commented
+    }
+
+    /**
+     * Return the conditional directives enclosing the entry, outermost
+     * first, for example `[".if ${OPSYS} == \"NetBSD\""]`.  An entry in an
+     * `.else` branch includes both the `.if` and `.else` lines.
+     */
+    #[must_use]
+    pub fn conditions(&self) -> &[String] {
+        &self.conditions
+    }
+
+    /**
+     * Return true if the entry is neither commented out nor conditional.
+     */
+    #[must_use]
+    pub fn is_unconditional(&self) -> bool {
+        !self.commented && self.conditions.is_empty()
+    }
+}
+
+/*
+ * Return the values of a SUBDIR+= assignment, or None if the line is not
+ * one.  Any trailing comment is removed.
+ */
+fn subdir_values(line: &str) -> Option<Vec<&str>> {
+    let rest = line.trim_start().strip_prefix("SUBDIR")?;
+    let rest = rest.trim_start().strip_prefix("+=")?;
+    let rest = rest.split_once('#').map_or(rest, |(v, _)| v);
+    Some(rest.split_ascii_whitespace().collect())
+}
+
+/**
+ * Parse the `SUBDIR+=` entries of a top-level or category `Makefile`,
+ * including commented-out entries and those inside `.if` blocks.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::tree::parse_subdirs;
+ *
+ * let entries = parse_subdirs(
+ *     "SUBDIR+=\tbar\n\
+ *      #SUBDIR+=\tbroken\t# does not build\n\
+ *      .if ${OPSYS} == \"NetBSD\"\n\
+ *      SUBDIR+=\tnetbsd-only\n\
+ *      .endif\n",
+ * );
+ * assert_eq!(entries.len(), 3);
+ * assert!(entries[0].is_unconditional());
+ * assert!(entries[1].is_commented());
+ * assert_eq!(entries[2].conditions(), [".if ${OPSYS} == \"NetBSD\""]);
+ * ```
+ */
+#[must_use]
+pub fn parse_subdirs(makefile: &str) -> Vec<Subdir> {
+    let mut entries = Vec::new();
+    let mut stack: Vec<Vec<String>> = Vec::new();
+    let mut logical = String::new();
+    let mut start = 0;
+    for (n, raw) in makefile.lines().enumerate() {
+        if logical.is_empty() {
+            start = n + 1;
+        }
+        if let Some(cont) = raw.strip_suffix('\\') {
+            logical.push_str(cont);
+            logical.push(' ');
+            continue;
+        }
+        logical.push_str(raw);
+        let line = std::mem::take(&mut logical);
+
+        if let Some(directive) = line.strip_prefix('.') {
+            let word = directive
+                .trim_start()
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .next()
+                .unwrap_or("");
+            let text =
+                format!(".{}", directive.trim_start()).trim().to_string();
+            match word {
+                "if" | "ifdef" | "ifndef" | "ifmake" | "ifnmake" => {
+                    stack.push(vec![text]);
+                }
+                w if w.starts_with("el") => {
+                    if let Some(top) = stack.last_mut() {
+                        top.push(text);
+                    }
+                }
+                "endif" => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let (values, commented) = match line.strip_prefix('#') {
+            Some(rest) => (subdir_values(rest), true),
+            None => (subdir_values(&line), false),
+        };
+        for name in values.into_iter().flatten() {
+            entries.push(Subdir {
+                name: name.to_string(),
+                line: start,
+                commented,
+                conditions: stack.iter().flatten().cloned().collect(),
+            });
+        }
+    }
+    entries
+}
+
+/**
+ * A disagreement between the `SUBDIR` listings and the tree on disk.
+ * Paths are relative to the top of the tree.
+ */
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TreeIssue {
+    /// A directory containing a `Makefile` that is not listed in its
+    /// parent's `SUBDIR`, even as a comment.
+    Unlisted(String),
+    /// A `SUBDIR` entry whose directory or `Makefile` does not exist.
+    Missing(String),
+    /// A `SUBDIR` entry that does not form a valid [`PkgPath`].
+    Invalid(String),
+}
+
+impl fmt::Display for TreeIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeIssue::Unlisted(p) => write!(f, "{p}: not listed in SUBDIR"),
+            TreeIssue::Missing(p) => write!(f, "{p}: listed but missing"),
+            TreeIssue::Invalid(p) => write!(f, "{p}: invalid PKGPATH"),
+        }
+    }
+}
+
+/**
+ * The packages in a pkgsrc checkout, read from its `SUBDIR` listings.  See
+ * the [module documentation](self) for details.
+ */
+#[derive(Clone, Debug)]
+pub struct Tree {
+    root: PathBuf,
+    categories: Vec<Subdir>,
+    packages: Vec<(PkgPath, Subdir)>,
+    issues: Vec<TreeIssue>,
+}
+
+impl Tree {
+    /**
+     * Read the tree at `root`.  Commented-out categories are not read.
+     *
+     * # Errors
+     *
+     * Returns an [`io::Error`] if the top-level `Makefile` or a directory
+     * cannot be read.  Missing category `Makefile`s are reported as
+     * [`TreeIssue::Missing`] instead.
+     */
+    pub fn open(root: impl AsRef<Path>) -> io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        let categories =
+            parse_subdirs(&fs::read_to_string(root.join("Makefile"))?);
+        let mut packages = Vec::new();
+        let mut issues = Vec::new();
+
+        let listed: Vec<&str> = categories.iter().map(Subdir::name).collect();
+        for name in makefile_dirs(&root)? {
+            if !listed.contains(&name.as_str()) && is_category(&root, &name) {
+                issues.push(TreeIssue::Unlisted(name));
+            }
+        }
+
+        for category in categories.iter().filter(|c| !c.is_commented()) {
+            let dir = root.join(category.name());
+            let Ok(makefile) = fs::read_to_string(dir.join("Makefile")) else {
+                issues.push(TreeIssue::Missing(category.name().to_string()));
+                continue;
+            };
+            let entries = parse_subdirs(&makefile);
+            for entry in &entries {
+                let path = format!("{}/{}", category.name(), entry.name());
+                if entry.is_commented() {
+                    continue;
+                }
+                if !dir.join(entry.name()).join("Makefile").is_file() {
+                    issues.push(TreeIssue::Missing(path));
+                    continue;
+                }
+                match PkgPath::new(&path) {
+                    Ok(pkgpath) => {
+                        let mut entry = entry.clone();
+                        entry
+                            .conditions
+                            .splice(0..0, category.conditions.clone());
+                        packages.push((pkgpath, entry));
+                    }
+                    Err(_) => issues.push(TreeIssue::Invalid(path)),
+                }
+            }
+            let listed: Vec<&str> = entries.iter().map(Subdir::name).collect();
+            for name in makefile_dirs(&dir)? {
+                if !listed.contains(&name.as_str()) {
+                    issues.push(TreeIssue::Unlisted(format!(
+                        "{}/{name}",
+                        category.name()
+                    )));
+                }
+            }
+        }
+        packages.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(Self {
+            root,
+            categories,
+            packages,
+            issues,
+        })
+    }
+
+    /**
+     * Return the top of the tree.
+     */
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /**
+     * Return the entries of the top-level `Makefile`.
+     */
+    #[must_use]
+    pub fn categories(&self) -> &[Subdir] {
+        &self.categories
+    }
+
+    /**
+     * Return every package that is listed and not commented out, sorted.
+     * Packages listed only under a condition are included; use
+     * [`packages`](Self::packages) to filter them.
+     */
+    #[must_use]
+    pub fn pkgpaths(&self) -> Vec<PkgPath> {
+        self.packages.iter().map(|(p, _)| p.clone()).collect()
+    }
+
+    /**
+     * Return every package with its `SUBDIR` entry, sorted.  Conditions
+     * include those on the package's category.
+     */
+    #[must_use]
+    pub fn packages(&self) -> &[(PkgPath, Subdir)] {
+        &self.packages
+    }
+
+    /**
+     * Return the problems found while reading the tree.
+     */
+    #[must_use]
+    pub fn issues(&self) -> &[TreeIssue] {
+        &self.issues
+    }
+}
+
+/*
+ * Return the sorted names of the subdirectories of dir that contain a
+ * Makefile, ignoring hidden directories.
+ */
+fn makefile_dirs(dir: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if name.starts_with('.') || !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if entry.path().join("Makefile").is_file() {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/*
+ * Top-level directories such as mk or doc may have a Makefile without
+ * being a category, so only those that include category.mk are reported
+ * when unlisted.
+ */
+fn is_category(root: &Path, name: &str) -> bool {
+    fs::read_to_string(root.join(name).join("Makefile"))
+        .is_ok_and(|s| s.contains("mk/misc/category.mk"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let entries = parse_subdirs(
+            "SUBDIR+=\ta b\t# two\n\
+             # SUBDIR+=\tc\n\
+             #COMMENT=\tnot an entry\n\
+             .if ${OPSYS} == \"NetBSD\"\n\
+             .  if defined(X)\n\
+             SUBDIR+=\td\n\
+             .  endif\n\
+             .else\n\
+             SUBDIR+=\te \\\n\
+             \tf\n\
+             .endif\n\
+             SUBDIR+=\tg\n",
+        );
+        let names: Vec<_> = entries.iter().map(Subdir::name).collect();
+        assert_eq!(names, ["a", "b", "c", "d", "e", "f", "g"]);
+        assert!(entries[2].is_commented());
+        assert_eq!(
+            entries[3].conditions(),
+            [".if ${OPSYS} == \"NetBSD\"", ".if defined(X)"]
+        );
+        assert_eq!(
+            entries[5].conditions(),
+            [".if ${OPSYS} == \"NetBSD\"", ".else"]
+        );
+        assert_eq!(entries[5].line(), 9);
+        assert!(entries[6].is_unconditional());
+    }
+
+    #[test]
+    fn issues() -> io::Result<()> {
+        let tree = Tree::open(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pkgsrc"),
+        )?;
+        assert_eq!(
+            tree.issues(),
+            [
+                TreeIssue::Missing("lang/gone".into()),
+                TreeIssue::Unlisted("lang/unlisted".into()),
+            ]
+        );
+        let (path, entry) = &tree.packages()[2];
+        assert_eq!(path.as_str(), "devel/netbsd-only");
+        assert!(!entry.is_unconditional());
+        Ok(())
+    }
+}
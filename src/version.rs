@@ -0,0 +1,319 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Package versions with pkg_install ordering.
+ *
+ * A [`Version`] wraps a `PKGVERSION` string such as `1.4.2nb3` and orders it
+ * using exactly the same rules that `pkg_install` uses for dewey pattern
+ * matches.  This allows package versions to be sorted directly, without
+ * constructing patterns.
+ *
+ * ```
+ * use pkgsrc::Version;
+ *
+ * let mut versions: Vec<Version> = ["1.0", "1.0nb1", "1.0rc1", "0.9pl2"]
+ *     .iter()
+ *     .map(|v| v.parse())
+ *     .collect::<Result<_, _>>()?;
+ * versions.sort();
+ * let sorted: Vec<&str> = versions.iter().map(Version::as_str).collect();
+ * assert_eq!(sorted, ["0.9pl2", "1.0rc1", "1.0", "1.0nb1"]);
+ * # Ok::<(), pkgsrc::DeweyError>(())
+ * ```
+ *
+ * As with `pkg_install`, versions that differ only in trailing zero
+ * components compare equal, so `1.0` is equal to `1`.  [`PartialEq`],
+ * [`Eq`] and [`Hash`] all follow this rule, and [`Version::as_str`] can be
+ * used to compare the original strings.
+ */
+
+use crate::dewey::{DeweyError, DeweyVersion, dewey_order};
+use crate::pkgname::{pkgrevision, pkgversion_norev};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
+/**
+ * A package version, ordered according to pkg_install dewey rules.
+ *
+ * The following modifiers are supported, in ascending order, with any
+ * `nb<n>` PKGREVISION compared last:
+ *
+ * `alpha` < `beta` < `pre`, `rc` < (none), `pl`, `.`, `_`
+ *
+ * See the [`dewey`] module for full details.
+ *
+ * [`dewey`]: crate::dewey
+ */
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(SerializeDisplay, DeserializeFromStr))]
+pub struct Version {
+    version: String,
+    dewey: DeweyVersion,
+}
+
+impl Version {
+    /**
+     * Parse a `PKGVERSION` string.
+     *
+     * # Errors
+     *
+     * Returns [`DeweyError`] if a numeric component overflows [`i64`].
+     */
+    pub fn new(version: &str) -> Result<Self, DeweyError> {
+        Ok(Self {
+            version: version.to_string(),
+            dewey: DeweyVersion::new(version)?,
+        })
+    }
+
+    /**
+     * Return the original version string.
+     */
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.version
+    }
+
+    /**
+     * Return the upstream version, i.e. the version without any `nb<n>`
+     * PKGREVISION suffix.
+     *
+     * ```
+     * use pkgsrc::Version;
+     *
+     * let v = Version::new("1.4.2nb3")?;
+     * assert_eq!(v.upstream(), "1.4.2");
+     * # Ok::<(), pkgsrc::DeweyError>(())
+     * ```
+     */
+    #[must_use]
+    pub fn upstream(&self) -> &str {
+        pkgversion_norev(&self.version)
+    }
+
+    /**
+     * Return the `PKGREVISION`, or [`None`] if there is no `nb` suffix.  See
+     * [`pkgrevision`] for the parsing rules.
+     *
+     * [`pkgrevision`]: crate::pkgname::pkgrevision
+     */
+    #[must_use]
+    pub fn pkgrevision(&self) -> Option<i64> {
+        pkgrevision(&self.version)
+    }
+
+    /**
+     * Return a new version with the `PKGREVISION` incremented, as is done
+     * when a package is rebuilt without an upstream change.
+     *
+     * ```
+     * use pkgsrc::Version;
+     *
+     * let v = Version::new("1.4.2")?;
+     * assert_eq!(v.bump_pkgrevision()?.as_str(), "1.4.2nb1");
+     * let v = Version::new("1.4.2nb3")?;
+     * assert_eq!(v.bump_pkgrevision()?.as_str(), "1.4.2nb4");
+     * # Ok::<(), pkgsrc::DeweyError>(())
+     * ```
+     *
+     * # Errors
+     *
+     * Returns [`DeweyError`] if the new `PKGREVISION` overflows [`i64`].
+     */
+    pub fn bump_pkgrevision(&self) -> Result<Self, DeweyError> {
+        let rev = self.pkgrevision().unwrap_or(0);
+        let rev = rev.checked_add(1).ok_or(DeweyError {
+            pos: self.version.len(),
+            msg: "Version component overflow",
+        })?;
+        Self::new(&format!("{}nb{rev}", self.upstream()))
+    }
+
+    /**
+     * Return a new version with the last numeric component of the upstream
+     * version incremented, preserving any zero padding, and with the
+     * `PKGREVISION` removed.  If the upstream version contains no digits
+     * then `.1` is appended.
+     *
+     * ```
+     * use pkgsrc::Version;
+     *
+     * let v = Version::new("1.4.2nb3")?;
+     * assert_eq!(v.bump_upstream()?.as_str(), "1.4.3");
+     * let v = Version::new("2024.09")?;
+     * assert_eq!(v.bump_upstream()?.as_str(), "2024.10");
+     * let v = Version::new("1.0rc1")?;
+     * assert_eq!(v.bump_upstream()?.as_str(), "1.0rc2");
+     * # Ok::<(), pkgsrc::DeweyError>(())
+     * ```
+     *
+     * # Errors
+     *
+     * Returns [`DeweyError`] if the incremented component overflows
+     * [`i64`].
+     */
+    pub fn bump_upstream(&self) -> Result<Self, DeweyError> {
+        let upstream = self.upstream();
+        let Some(end) = upstream.rfind(|c: char| c.is_ascii_digit()) else {
+            return Self::new(&format!("{upstream}.1"));
+        };
+        let end = end + 1;
+        let start = upstream[..end]
+            .rfind(|c: char| !c.is_ascii_digit())
+            .map_or(0, |i| i + 1);
+        let digits = &upstream[start..end];
+        let overflow = DeweyError {
+            pos: start,
+            msg: "Version component overflow",
+        };
+        let n = digits
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| n.checked_add(1))
+            .ok_or(overflow)?;
+        Self::new(&format!(
+            "{}{n:0width$}{}",
+            &upstream[..start],
+            &upstream[end..],
+            width = digits.len()
+        ))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        dewey_order(&self.dewey, &other.dewey)
+    }
+}
+
+/*
+ * Trailing zero components do not affect ordering, so they must not affect
+ * the hash either.
+ */
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let c = self.dewey.components();
+        let len = c.iter().rposition(|&n| n != 0).map_or(0, |i| i + 1);
+        c[..len].hash(state);
+        self.dewey.pkgrevision().hash(state);
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.version)
+    }
+}
+
+impl FromStr for Version {
+    type Err = DeweyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<&str> for Version {
+    type Error = DeweyError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::new(s)
+    }
+}
+
+impl AsRef<str> for Version {
+    fn as_ref(&self) -> &str {
+        &self.version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn v(s: &str) -> Version {
+        Version::new(s).expect("valid version")
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(v("1.0alpha") < v("1.0beta"));
+        assert!(v("1.0beta") < v("1.0pre1"));
+        assert_eq!(v("1.0pre1"), v("1.0rc1"));
+        assert!(v("1.0rc1") < v("1.0"));
+        assert_eq!(v("1.0"), v("1.0pl"));
+        assert!(v("1.0") < v("1.0nb1"));
+        assert!(v("1.0nb9") < v("1.0.1"));
+        assert!(v("1.9") < v("1.10"));
+        assert!(v("1.0a") < v("1.0b"));
+        assert_eq!(v("1.0"), v("1"));
+        assert_eq!(v("1.0_0"), v("1"));
+    }
+
+    #[test]
+    fn hash_consistent_with_eq() {
+        let set: HashSet<Version> = ["1", "1.0", "1.0.0", "1.0nb1"]
+            .iter()
+            .map(|s| v(s))
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&v("1.0.0.0")));
+    }
+
+    #[test]
+    fn components() {
+        let ver = v("1.4.2nb3");
+        assert_eq!(ver.upstream(), "1.4.2");
+        assert_eq!(ver.pkgrevision(), Some(3));
+        assert_eq!(v("1.4.2").pkgrevision(), None);
+        assert_eq!(ver.to_string(), "1.4.2nb3");
+    }
+
+    #[test]
+    fn bump() -> Result<(), DeweyError> {
+        assert_eq!(v("1.4.2nb3").bump_pkgrevision()?.as_str(), "1.4.2nb4");
+        assert_eq!(v("1.4.2nb").bump_pkgrevision()?.as_str(), "1.4.2nb1");
+        assert_eq!(v("1.9").bump_upstream()?.as_str(), "1.10");
+        assert_eq!(v("009").bump_upstream()?.as_str(), "010");
+        assert_eq!(v("1.0a").bump_upstream()?.as_str(), "1.1a");
+        assert_eq!(v("abc").bump_upstream()?.as_str(), "abc.1");
+        assert!(v("9223372036854775807").bump_upstream().is_err());
+        assert!(v("1.4.2nb3").bump_upstream()? > v("1.4.2nb3"));
+        assert!(v("1.4.2nb3").bump_pkgrevision()? > v("1.4.2nb3"));
+        Ok(())
+    }
+}
@@ -0,0 +1,693 @@
+/*
+ * Copyright (c) 2026 Jonathan Perkin <jonathan@perkin.org.uk>
+ *
+ * Permission to use, copy, modify, and distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ * ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ * ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+ * OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+ */
+
+/*!
+ * Parse `pkg-vulnerabilities` files and audit packages against them.
+ *
+ * The `pkg-vulnerabilities` file is published by the pkgsrc security team
+ * and lists packages with known vulnerabilities.  It is usually fetched by
+ * `pkg_admin fetch-pkg-vulnerabilities` into the pkgdb directory, and then
+ * consulted by `pkg_admin audit`.  Each entry is a single line containing a
+ * package [`Pattern`], the type of vulnerability, and a URL with more
+ * information:
+ *
+ * ```text
+ * #FORMAT 1.0.0
+ * # package               type of exploit         URL
+ * cfengine<1.5.3nb3       remote-root-shell       ftp://ftp.NetBSD.org/...
+ * ```
+ *
+ * The file ends with one or more `#CHECKSUM` lines, which hash the contents
+ * of the file (excluding the `#CHECKSUM` lines themselves) with a
+ * [`Digest`] algorithm.  The whole file is then usually wrapped in an
+ * OpenPGP cleartext signature.
+ *
+ * This module does not verify OpenPGP signatures.  The original input is
+ * available via [`Vulnerabilities::raw`] and the armored signature via
+ * [`Vulnerabilities::signature`], so that callers may verify it with their
+ * tool of choice.  The embedded checksums can be verified with
+ * [`Vulnerabilities::verify_checksums`].
+ *
+ * Input may optionally be gzip compressed, as it is when downloaded.
+ *
+ * # Example
+ *
+ * ```
+ * use pkgsrc::vulnerabilities::Vulnerabilities;
+ *
+ * let input = "\
+ * #FORMAT 1.0.0
+ * openssl<3.0.14\tdenial-of-service\thttps://nvd.nist.gov/vuln/detail/CVE-2024-4741
+ * ";
+ * let vulns = Vulnerabilities::from_bytes(input.as_bytes())?;
+ * assert_eq!(vulns.entries().len(), 1);
+ *
+ * let found = vulns.check("openssl-3.0.13");
+ * assert_eq!(found.len(), 1);
+ * assert_eq!(found[0].kind(), "denial-of-service");
+ * assert!(vulns.check("openssl-3.0.14").is_empty());
+ * # Ok::<(), pkgsrc::vulnerabilities::VulnerabilitiesError>(())
+ * ```
+ */
+
+use crate::digest::{Digest, DigestError, hex_encode};
+use crate::pkgdb::PkgDB;
+use crate::{Pattern, PatternError, PkgName, Summary};
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::str::FromStr;
+use thiserror::Error;
+
+const PGP_SIGNED_MESSAGE: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+const PGP_SIGNATURE_BEGIN: &str = "-----BEGIN PGP SIGNATURE-----";
+const FORMAT_PREFIX: &str = "#FORMAT ";
+const CHECKSUM_PREFIX: &str = "#CHECKSUM ";
+
+/**
+ * A type alias for the result from parsing a `pkg-vulnerabilities` file,
+ * with [`VulnerabilitiesError`] returned in [`Err`] variants.
+ */
+pub type Result<T> = std::result::Result<T, VulnerabilitiesError>;
+
+/**
+ * Errors that can occur when parsing or verifying a `pkg-vulnerabilities`
+ * file.
+ */
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum VulnerabilitiesError {
+    /**
+     * An I/O error when reading the input.
+     */
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /**
+     * The input is not valid UTF-8.
+     */
+    #[error(transparent)]
+    Utf8(#[from] std::string::FromUtf8Error),
+    /**
+     * The input starts with an OpenPGP signed message header but has no
+     * signature block.
+     */
+    #[error("signed message has no signature block")]
+    UnterminatedSignature,
+    /**
+     * The input does not start with a `#FORMAT` line.
+     */
+    #[error("missing #FORMAT line")]
+    MissingFormat,
+    /**
+     * The `#FORMAT` version is not supported.
+     */
+    #[error("unsupported format version: {0}")]
+    UnsupportedFormat(String),
+    /**
+     * The input contains no `#CHECKSUM` lines to verify.
+     */
+    #[error("no #CHECKSUM lines found")]
+    MissingChecksum,
+    /**
+     * A `#CHECKSUM` line does not match the computed hash.
+     */
+    #[error("{digest} checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        /** The digest algorithm used. */
+        digest: Digest,
+        /** The hash recorded in the file. */
+        expected: String,
+        /** The hash computed from the file contents. */
+        actual: String,
+    },
+    /**
+     * A `#CHECKSUM` line uses an unsupported digest algorithm.
+     */
+    #[error(transparent)]
+    Digest(#[from] DigestError),
+    /**
+     * A line could not be parsed.
+     */
+    #[error("line {line}: invalid entry: {content}")]
+    ParseLine {
+        /** The 1-based line number within the message body. */
+        line: usize,
+        /** The offending line. */
+        content: String,
+    },
+    /**
+     * An entry contains an invalid package pattern.
+     */
+    #[error("line {line}: invalid pattern")]
+    Pattern {
+        /** The 1-based line number within the message body. */
+        line: usize,
+        /** The underlying pattern error. */
+        #[source]
+        source: PatternError,
+    },
+}
+
+/**
+ * A single vulnerability entry.
+ */
+#[derive(Clone, Debug)]
+pub struct Vulnerability {
+    pattern: Pattern,
+    kind: String,
+    url: String,
+}
+
+impl Vulnerability {
+    /**
+     * Return the [`Pattern`] matching affected packages.
+     */
+    #[must_use]
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /**
+     * Return the type of vulnerability, for example `remote-code-execution`.
+     */
+    #[must_use]
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /**
+     * Return the URL containing more information about the vulnerability.
+     */
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /**
+     * Return whether this entry marks affected packages as end-of-life
+     * rather than describing a specific vulnerability.  `pkg_admin audit`
+     * only reports these when `CHECK_END_OF_LIFE` is enabled.
+     */
+    #[must_use]
+    pub fn is_eol(&self) -> bool {
+        self.kind == "eol"
+    }
+}
+
+/**
+ * A package that matched a [`Vulnerability`] during an audit.
+ */
+#[derive(Clone, Debug)]
+pub struct Finding<'a> {
+    pkgname: String,
+    vulnerability: &'a Vulnerability,
+}
+
+impl<'a> Finding<'a> {
+    /**
+     * Return the name of the affected package.
+     */
+    #[must_use]
+    pub fn pkgname(&self) -> &str {
+        &self.pkgname
+    }
+
+    /**
+     * Return the matching vulnerability.
+     */
+    #[must_use]
+    pub fn vulnerability(&self) -> &'a Vulnerability {
+        self.vulnerability
+    }
+}
+
+/**
+ * A parsed `pkg-vulnerabilities` file.
+ */
+#[derive(Clone, Debug)]
+pub struct Vulnerabilities {
+    raw: Vec<u8>,
+    body: String,
+    signature: Option<String>,
+    format: String,
+    checksums: Vec<(Digest, String)>,
+    entries: Vec<Vulnerability>,
+    /*
+     * Entries indexed by PKGBASE, plus those whose pattern has no fixed
+     * PKGBASE and must be checked against every package.
+     */
+    index: HashMap<String, Vec<usize>>,
+    unindexed: Vec<usize>,
+}
+
+impl Vulnerabilities {
+    /**
+     * Parse a `pkg-vulnerabilities` file from a byte slice.  The input may
+     * be gzip compressed, and may be wrapped in an OpenPGP cleartext
+     * signature.
+     *
+     * Checksums are not verified, use [`verify_checksums`] for that.
+     *
+     * [`verify_checksums`]: Vulnerabilities::verify_checksums
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let raw = if bytes.starts_with(&[0x1f, 0x8b]) {
+            let mut out = Vec::new();
+            GzDecoder::new(bytes).read_to_end(&mut out)?;
+            out
+        } else {
+            bytes.to_vec()
+        };
+        Self::parse(raw)
+    }
+
+    /**
+     * Parse a `pkg-vulnerabilities` file from a reader.
+     */
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn parse(raw: Vec<u8>) -> Result<Self> {
+        let text = String::from_utf8(raw)?;
+        let (body, signature) = split_signed(&text)?;
+
+        let mut lines = body.lines();
+        let format = match lines.next() {
+            Some(l) if l.starts_with(FORMAT_PREFIX) => {
+                l[FORMAT_PREFIX.len()..].trim().to_string()
+            }
+            _ => return Err(VulnerabilitiesError::MissingFormat),
+        };
+        if !format.starts_with("1.") {
+            return Err(VulnerabilitiesError::UnsupportedFormat(format));
+        }
+
+        let mut checksums = Vec::new();
+        let mut entries = Vec::new();
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut unindexed = Vec::new();
+
+        for (n, line) in lines.enumerate() {
+            let lineno = n + 2;
+            if let Some(rest) = line.strip_prefix(CHECKSUM_PREFIX) {
+                let mut parts = rest.split_whitespace();
+                let (Some(alg), Some(hash), None) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(VulnerabilitiesError::ParseLine {
+                        line: lineno,
+                        content: line.to_string(),
+                    });
+                };
+                checksums.push((Digest::from_str(alg)?, hash.to_string()));
+                continue;
+            }
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let (Some(pattern), Some(kind), Some(url), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(VulnerabilitiesError::ParseLine {
+                    line: lineno,
+                    content: line.to_string(),
+                });
+            };
+            let pattern = Pattern::new(pattern).map_err(|source| {
+                VulnerabilitiesError::Pattern {
+                    line: lineno,
+                    source,
+                }
+            })?;
+            let idx = entries.len();
+            match pattern.pkgbases() {
+                Some(bases) => {
+                    for base in bases {
+                        index.entry(base.to_string()).or_default().push(idx);
+                    }
+                }
+                None => unindexed.push(idx),
+            }
+            entries.push(Vulnerability {
+                pattern,
+                kind: kind.to_string(),
+                url: url.to_string(),
+            });
+        }
+
+        Ok(Vulnerabilities {
+            raw: text.as_bytes().to_vec(),
+            body,
+            signature,
+            format,
+            checksums,
+            entries,
+            index,
+            unindexed,
+        })
+    }
+
+    /**
+     * Return the raw (decompressed) input, including any OpenPGP signature
+     * wrapper, suitable for passing to an external signature verifier.
+     */
+    #[must_use]
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /**
+     * Return the message body with any OpenPGP wrapper and dash-escaping
+     * removed.
+     */
+    #[must_use]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /**
+     * Return the armored OpenPGP signature block, if the input was signed.
+     */
+    #[must_use]
+    pub fn signature(&self) -> Option<&str> {
+        self.signature.as_deref()
+    }
+
+    /**
+     * Return the version from the `#FORMAT` line.
+     */
+    #[must_use]
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    /**
+     * Return the `#CHECKSUM` entries as digest algorithm and hash pairs.
+     */
+    #[must_use]
+    pub fn checksums(&self) -> &[(Digest, String)] {
+        &self.checksums
+    }
+
+    /**
+     * Return all vulnerability entries in file order.
+     */
+    #[must_use]
+    pub fn entries(&self) -> &[Vulnerability] {
+        &self.entries
+    }
+
+    /**
+     * Verify every `#CHECKSUM` line against the message body.  As with
+     * `pkg_admin`, the hash covers every line of the body except for the
+     * `#CHECKSUM` lines themselves.
+     *
+     * Returns [`VulnerabilitiesError::MissingChecksum`] if there are no
+     * checksums to verify.
+     */
+    pub fn verify_checksums(&self) -> Result<()> {
+        if self.checksums.is_empty() {
+            return Err(VulnerabilitiesError::MissingChecksum);
+        }
+        for (digest, expected) in &self.checksums {
+            let mut hasher = digest.hasher();
+            for line in self.body.lines() {
+                if line.starts_with(CHECKSUM_PREFIX) {
+                    continue;
+                }
+                hasher.update(line.as_bytes());
+                hasher.update(b"\n");
+            }
+            let actual = hex_encode(&hasher.finalize());
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(VulnerabilitiesError::ChecksumMismatch {
+                    digest: *digest,
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Return all vulnerabilities affecting the package `pkgname`, in file
+     * order.
+     */
+    #[must_use]
+    pub fn check(&self, pkgname: &str) -> Vec<&Vulnerability> {
+        let base = crate::pkgname::pkgbase(pkgname);
+        let mut idxs: Vec<usize> = self
+            .index
+            .get(base)
+            .into_iter()
+            .flatten()
+            .chain(&self.unindexed)
+            .copied()
+            .filter(|&i| self.entries[i].pattern.matches(pkgname))
+            .collect();
+        idxs.sort_unstable();
+        idxs.dedup();
+        idxs.into_iter().map(|i| &self.entries[i]).collect()
+    }
+
+    /**
+     * Return all vulnerabilities affecting the package `pkgname`.
+     */
+    #[must_use]
+    pub fn check_pkgname(&self, pkgname: &PkgName) -> Vec<&Vulnerability> {
+        self.check(pkgname.pkgname())
+    }
+
+    /**
+     * Audit a list of package names, returning a [`Finding`] for each
+     * vulnerability affecting each package.
+     */
+    pub fn audit<I, S>(&self, pkgnames: I) -> Vec<Finding<'_>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut findings = Vec::new();
+        for pkgname in pkgnames {
+            let pkgname = pkgname.as_ref();
+            for vulnerability in self.check(pkgname) {
+                findings.push(Finding {
+                    pkgname: pkgname.to_string(),
+                    vulnerability,
+                });
+            }
+        }
+        findings
+    }
+
+    /**
+     * Audit every package in a set of [`Summary`] entries, for example a
+     * binary package repository.
+     */
+    #[must_use]
+    pub fn audit_summaries(&self, summaries: &[Summary]) -> Vec<Finding<'_>> {
+        self.audit(summaries.iter().map(|s| s.pkgname()))
+    }
+
+    /**
+     * Audit every package installed in a [`PkgDB`], equivalent to
+     * `pkg_admin audit`.
+     */
+    pub fn audit_pkgdb(&self, pkgdb: PkgDB) -> io::Result<Vec<Finding<'_>>> {
+        let mut pkgnames = Vec::new();
+        for pkg in pkgdb {
+            pkgnames.push(pkg?.pkgname().to_string());
+        }
+        Ok(self.audit(pkgnames))
+    }
+}
+
+impl FromStr for Vulnerabilities {
+    type Err = VulnerabilitiesError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s.as_bytes().to_vec())
+    }
+}
+
+/*
+ * Split an optional OpenPGP cleartext signature wrapper into the
+ * dash-unescaped message body and the armored signature.  Unsigned input is
+ * returned unchanged.
+ */
+fn split_signed(text: &str) -> Result<(String, Option<String>)> {
+    let mut lines = text.lines();
+    if text.lines().next().map(str::trim_end) != Some(PGP_SIGNED_MESSAGE) {
+        return Ok((text.to_string(), None));
+    }
+    lines.next();
+
+    /* Skip armor headers such as "Hash: SHA512" up to the first blank. */
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut body = String::new();
+    for line in lines.by_ref() {
+        if line.trim_end() == PGP_SIGNATURE_BEGIN {
+            let mut signature = String::from(PGP_SIGNATURE_BEGIN);
+            signature.push('\n');
+            for line in lines {
+                signature.push_str(line);
+                signature.push('\n');
+            }
+            return Ok((body, Some(signature)));
+        }
+        body.push_str(line.strip_prefix("- ").unwrap_or(line));
+        body.push('\n');
+    }
+    Err(VulnerabilitiesError::UnterminatedSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn fixture() -> Vec<u8> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/data/pkg-vulnerabilities");
+        std::fs::read(path).expect("fixture")
+    }
+
+    #[test]
+    fn test_parse_signed() -> Result<()> {
+        let vulns = Vulnerabilities::from_bytes(&fixture())?;
+        assert_eq!(vulns.format(), "1.0.0");
+        assert_eq!(vulns.entries().len(), 5);
+        assert_eq!(vulns.checksums().len(), 2);
+        assert!(vulns.body().starts_with("#FORMAT 1.0.0\n"));
+        assert!(vulns.raw().starts_with(PGP_SIGNED_MESSAGE.as_bytes()));
+        let sig = vulns.signature().expect("signature");
+        assert!(sig.starts_with(PGP_SIGNATURE_BEGIN));
+        assert!(sig.trim_end().ends_with("-----END PGP SIGNATURE-----"));
+        vulns.verify_checksums()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_gzip() -> Result<()> {
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(&fixture())?;
+        let vulns = Vulnerabilities::from_bytes(&enc.finish()?)?;
+        assert_eq!(vulns.entries().len(), 5);
+        vulns.verify_checksums()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_mismatch() -> Result<()> {
+        let text = String::from_utf8(fixture()).expect("utf8");
+        let text = text.replace("remote-code-execution", "denial-of-service");
+        let vulns = Vulnerabilities::from_str(&text)?;
+        assert!(matches!(
+            vulns.verify_checksums(),
+            Err(VulnerabilitiesError::ChecksumMismatch { .. })
+        ));
+
+        let vulns = Vulnerabilities::from_str("#FORMAT 1.0.0\n")?;
+        assert!(matches!(
+            vulns.verify_checksums(),
+            Err(VulnerabilitiesError::MissingChecksum)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Vulnerabilities::from_str("foo<1.0 eol https://x\n"),
+            Err(VulnerabilitiesError::MissingFormat)
+        ));
+        assert!(matches!(
+            Vulnerabilities::from_str("#FORMAT 2.0.0\n"),
+            Err(VulnerabilitiesError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            Vulnerabilities::from_str("#FORMAT 1.0.0\nfoo<1.0 eol\n"),
+            Err(VulnerabilitiesError::ParseLine { line: 2, .. })
+        ));
+        assert!(matches!(
+            Vulnerabilities::from_str("#FORMAT 1.0.0\nfoo-{a eol https://x\n"),
+            Err(VulnerabilitiesError::Pattern { line: 2, .. })
+        ));
+        assert!(matches!(
+            Vulnerabilities::from_str("#FORMAT 1.0.0\n#CHECKSUM FOO 00\n"),
+            Err(VulnerabilitiesError::Digest(_))
+        ));
+        assert!(matches!(
+            Vulnerabilities::from_str(&format!(
+                "{PGP_SIGNED_MESSAGE}\nHash: SHA512\n\n#FORMAT 1.0.0\n"
+            )),
+            Err(VulnerabilitiesError::UnterminatedSignature)
+        ));
+    }
+
+    #[test]
+    fn test_audit() -> Result<()> {
+        let vulns = Vulnerabilities::from_bytes(&fixture())?;
+
+        let found = vulns.check("openssl-3.0.13");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].kind(), "denial-of-service");
+        assert_eq!(found[1].kind(), "remote-code-execution");
+        assert!(vulns.check("openssl-3.0.15").is_empty());
+
+        let pkgname = PkgName::new("python27-2.7.18nb20");
+        let found = vulns.check_pkgname(&pkgname);
+        assert_eq!(found.len(), 1);
+        assert!(found[0].is_eol());
+
+        /* Alternate patterns are indexed under each PKGBASE. */
+        assert_eq!(vulns.check("php82-8.2.1").len(), 1);
+        assert_eq!(vulns.check("php83-8.3.1").len(), 1);
+
+        let findings =
+            vulns.audit(["openssl-3.0.13", "mktool-1.4.2", "php83-8.3.1"]);
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[2].pkgname(), "php83-8.3.1");
+        assert_eq!(findings[2].vulnerability().url(), "https://example.org/3");
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_pkgdb() -> Result<()> {
+        let vulns = Vulnerabilities::from_str(
+            "#FORMAT 1.0.0\nmktool<1.5 information-leak https://x\n",
+        )?;
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/data/pkgdb");
+        let findings = vulns.audit_pkgdb(PkgDB::open(&path)?)?;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].pkgname(), "mktool-1.4.2");
+        Ok(())
+    }
+}
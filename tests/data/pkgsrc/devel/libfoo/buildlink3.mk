@@ -0,0 +1,13 @@
+# $NetBSD: buildlink3.mk,v 1.1 2026/01/01 00:00:00 jperkin Exp $
+
+BUILDLINK_TREE+=	libfoo
+
+.if !defined(LIBFOO_BUILDLINK3_MK)
+LIBFOO_BUILDLINK3_MK:=
+
+BUILDLINK_API_DEPENDS.libfoo+=	libfoo>=1.0
+BUILDLINK_ABI_DEPENDS.libfoo+=	libfoo>=1.0nb1
+BUILDLINK_PKGSRCDIR.libfoo?=	../../devel/libfoo
+.endif	# LIBFOO_BUILDLINK3_MK
+
+BUILDLINK_TREE+=	-libfoo
@@ -0,0 +1 @@
+# $NetBSD: bsd.pkg.mk,v 1.1 2026/01/01 00:00:00 jperkin Exp $